@@ -5,12 +5,13 @@
 //! - Hash is computed over canonical JSON of (event + prev_hash)
 //! - Verifier replays and checks integrity end-to-end
 
-use pie_audit_spec::AuditEvent;
+use pie_audit_spec::{AuditEvent, CallStatus};
 use pie_common::sha256_canonical_json;
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
 use std::fs::{File, OpenOptions};
-use std::io::{BufRead, BufReader, Write};
-use std::path::Path;
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -23,6 +24,11 @@ pub enum AuditLogError {
     Canon(#[from] pie_common::CanonError),
     #[error("hash mismatch at line {line}: expected {expected}, got {got}")]
     HashMismatch { line: usize, expected: String, got: String },
+    #[error("log truncated: {len} bytes on disk, {offset} already read")]
+    Truncated { len: u64, offset: u64 },
+    /// The last line (starting at byte `offset`) is not a record, e.g. torn by a crash mid-append.
+    #[error("last line at byte {offset} is not an audit record: {source}")]
+    MalformedTail { offset: u64, source: serde_json::Error },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -53,10 +59,15 @@ pub struct AuditAppender {
 }
 
 impl AuditAppender {
+    /// Open (or create) a log for appending. An existing log is continued from its last record's
+    /// hash, so separate invocations appending to the same file keep one valid chain. Only the
+    /// tail of the file is read. A malformed last line fails with `MalformedTail` rather than
+    /// starting a second chain after it; repair or truncate the log first.
     pub fn open(path: impl AsRef<Path>) -> Result<Self, AuditLogError> {
         let path = path.as_ref();
         let file = OpenOptions::new().create(true).append(true).open(path)?;
-        Ok(Self { file, last_hash: genesis_hash() })
+        let last_hash = last_record_hash(path)?.unwrap_or_else(genesis_hash);
+        Ok(Self { file, last_hash })
     }
 
     pub fn with_last_hash(mut self, last_hash: String) -> Self {
@@ -77,10 +88,65 @@ impl AuditAppender {
     }
 }
 
+/// Hash of the last non-blank line, read backwards from the end in blocks so the cost is the
+/// size of that line, not of the log.
+fn last_record_hash(path: &Path) -> Result<Option<String>, AuditLogError> {
+    const BLOCK: u64 = 8 * 1024;
+    let mut f = File::open(path)?;
+    let parse = |offset: u64, line: &[u8]| {
+        serde_json::from_slice::<AuditRecord>(line)
+            .map(|r| Some(r.hash))
+            .map_err(|source| AuditLogError::MalformedTail { offset, source })
+    };
+    // `buf` holds the file from `pos` to the end.
+    let mut pos = f.metadata()?.len();
+    let mut buf: Vec<u8> = Vec::new();
+    loop {
+        let end = buf.iter().rposition(|b| !b.is_ascii_whitespace()).map(|i| i + 1);
+        if let Some(end) = end {
+            if let Some(nl) = buf[..end].iter().rposition(|&b| b == b'\n') {
+                return parse(pos + nl as u64 + 1, &buf[nl + 1..end]);
+            }
+        }
+        if pos == 0 {
+            return end.map_or(Ok(None), |end| parse(0, &buf[..end]));
+        }
+        let start = pos.saturating_sub(BLOCK);
+        let mut block = vec![0; (pos - start) as usize];
+        f.seek(SeekFrom::Start(start))?;
+        f.read_exact(&mut block)?;
+        block.extend_from_slice(&buf);
+        buf = block;
+        pos = start;
+    }
+}
+
 pub fn verify_log(path: impl AsRef<Path>) -> Result<String, AuditLogError> {
+    Ok(verify_log_report(path)?.final_hash)
+}
+
+/// Summary of a verified log, for post-run smoke checks.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LogReport {
+    pub records: u64,
+    pub event_counts: BTreeMap<String, u64>,
+    pub run_ids: BTreeSet<String>,
+    /// (min, max) tick_id over all events; None for an empty log.
+    pub tick_range: Option<(u64, u64)>,
+    /// Completed model calls, with the non-ok outcomes broken out below.
+    pub model_calls: u64,
+    pub errors: u64,
+    pub timeouts: u64,
+    pub rate_limited: u64,
+    pub final_hash: String,
+}
+
+/// Verify the hash chain (same checks as `verify_log`) while collecting a `LogReport`.
+pub fn verify_log_report(path: impl AsRef<Path>) -> Result<LogReport, AuditLogError> {
     let f = File::open(path)?;
     let reader = BufReader::new(f);
     let mut expected_prev = genesis_hash();
+    let mut report = LogReport::default();
 
     for (idx, line) in reader.lines().enumerate() {
         let line_no = idx + 1;
@@ -89,25 +155,118 @@ pub fn verify_log(path: impl AsRef<Path>) -> Result<String, AuditLogError> {
             continue;
         }
         let rec: AuditRecord = serde_json::from_str(&line)?;
-        if rec.prev_hash != expected_prev {
-            return Err(AuditLogError::HashMismatch {
-                line: line_no,
-                expected: expected_prev,
-                got: rec.prev_hash,
-            });
+        check_link(line_no, &expected_prev, &rec)?;
+        expected_prev = rec.hash;
+
+        report.records += 1;
+        *report.event_counts.entry(rec.event.event_type().to_string()).or_default() += 1;
+        report.run_ids.insert(rec.event.run_id().0.clone());
+        let tick = rec.event.tick_id().0;
+        report.tick_range = Some(match report.tick_range {
+            Some((lo, hi)) => (lo.min(tick), hi.max(tick)),
+            None => (tick, tick),
+        });
+        if let AuditEvent::ModelCallCompleted(e) = &rec.event {
+            report.model_calls += 1;
+            match e.result.status {
+                CallStatus::Ok => {}
+                CallStatus::Error => report.errors += 1,
+                CallStatus::Timeout => report.timeouts += 1,
+                CallStatus::RateLimited => report.rate_limited += 1,
+            }
         }
-        let computed = compute_record_hash(&rec.prev_hash, &rec.event)?;
-        if computed != rec.hash {
-            return Err(AuditLogError::HashMismatch {
-                line: line_no,
-                expected: computed,
-                got: rec.hash,
-            });
+    }
+
+    report.final_hash = expected_prev;
+    Ok(report)
+}
+
+/// Check that `rec` follows `expected_prev` and that its own hash is correct.
+fn check_link(line_no: usize, expected_prev: &str, rec: &AuditRecord) -> Result<(), AuditLogError> {
+    if rec.prev_hash != expected_prev {
+        return Err(AuditLogError::HashMismatch {
+            line: line_no,
+            expected: expected_prev.to_string(),
+            got: rec.prev_hash.clone(),
+        });
+    }
+    let computed = compute_record_hash(&rec.prev_hash, &rec.event)?;
+    if computed != rec.hash {
+        return Err(AuditLogError::HashMismatch { line: line_no, expected: computed, got: rec.hash.clone() });
+    }
+    Ok(())
+}
+
+/// Incremental verifier for a log that is still being written (tail -f with chain checks).
+///
+/// Each `poll` returns the records completed since the previous poll with their 1-based line
+/// numbers, each checked against its predecessor. A missing file reads as empty, and a trailing partial line is left for the next
+/// poll. If a batch hits a bad record, the good records before it are returned first and the
+/// error is reported by the following poll.
+pub struct AuditFollower {
+    path: PathBuf,
+    offset: u64,
+    line_no: usize,
+    expected_prev: String,
+}
+
+impl AuditFollower {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into(), offset: 0, line_no: 0, expected_prev: genesis_hash() }
+    }
+
+    pub fn poll(&mut self) -> Result<Vec<(usize, AuditRecord)>, AuditLogError> {
+        let mut f = match File::open(&self.path) {
+            Ok(f) => f,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let len = f.metadata()?.len();
+        if len < self.offset {
+            return Err(AuditLogError::Truncated { len, offset: self.offset });
         }
-        expected_prev = rec.hash;
+        f.seek(SeekFrom::Start(self.offset))?;
+        let mut buf = Vec::new();
+        f.read_to_end(&mut buf)?;
+
+        let mut out = Vec::new();
+        let mut pos = 0;
+        while let Some(nl) = buf[pos..].iter().position(|b| *b == b'\n') {
+            let line = String::from_utf8_lossy(&buf[pos..pos + nl]);
+            let line_no = self.line_no + 1;
+            if !line.trim().is_empty() {
+                let checked = serde_json::from_str::<AuditRecord>(&line)
+                    .map_err(AuditLogError::from)
+                    .and_then(|rec| check_link(line_no, &self.expected_prev, &rec).map(|_| rec));
+                match checked {
+                    Ok(rec) => {
+                        self.expected_prev = rec.hash.clone();
+                        out.push((line_no, rec));
+                    }
+                    Err(_) if !out.is_empty() => break,
+                    Err(e) => return Err(e),
+                }
+            }
+            pos += nl + 1;
+            self.offset += nl as u64 + 1;
+            self.line_no = line_no;
+        }
+        Ok(out)
     }
+}
 
-    Ok(expected_prev)
+/// Parse every record in the log, in order. Does not check the hash chain (use `verify_log`).
+pub fn read_records(path: impl AsRef<Path>) -> Result<Vec<AuditRecord>, AuditLogError> {
+    let f = File::open(path)?;
+    let mut out = Vec::new();
+    for line in BufReader::new(f).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        out.push(serde_json::from_str(&line)?);
+    }
+    Ok(out)
 }
 
 #[cfg(test)]
@@ -134,10 +293,149 @@ mod tests {
             model: "m".into(),
             endpoint_fingerprint: "sha256:abc".into(),
             request_post_hash: "sha256:def".into(),
+            request_body_hash: None,
         });
         app.append(e1).unwrap();
 
         let last = verify_log(&tmp).unwrap();
         assert!(last.starts_with("sha256:"));
+        assert_eq!(read_records(&tmp).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn reopened_appender_continues_the_chain() {
+        let tmp = std::env::temp_dir().join("pieBot_audit_reopen_test.jsonl");
+        let _ = fs::remove_file(&tmp);
+
+        let event = |tick| {
+            AuditEvent::ModelCallDispatched(ModelCallDispatched {
+                schema_version: 1,
+                run_id: RunId("r1".into()),
+                tick_id: TickId(tick),
+                ts: 1.0,
+                model_call: CallId(uuid::Uuid::new_v4()),
+                provider: "openai".into(),
+                model: "m".into(),
+                endpoint_fingerprint: "sha256:abc".into(),
+                request_post_hash: "sha256:def".into(),
+                request_body_hash: None,
+            })
+        };
+        let first = AuditAppender::open(&tmp).unwrap().append(event(1)).unwrap();
+        let second = AuditAppender::open(&tmp).unwrap().append(event(2)).unwrap();
+
+        assert_eq!(second.prev_hash, first.hash);
+        assert_eq!(verify_log(&tmp).unwrap(), second.hash);
+    }
+
+    fn deleted(tick: u64, reason: String) -> AuditEvent {
+        AuditEvent::EpisodeDeleted(EpisodeDeleted {
+            schema_version: 1,
+            run_id: RunId("r1".into()),
+            tick_id: TickId(tick),
+            ts: 1.0,
+            episode_id: uuid::Uuid::nil(),
+            episode_hash: "sha256:aa".into(),
+            reason,
+            unmirrored_remote_id: None,
+        })
+    }
+
+    #[test]
+    fn tail_hash_spans_blocks_and_skips_trailing_blank_lines() {
+        let tmp = std::env::temp_dir().join("pieBot_audit_tail_test.jsonl");
+        let _ = fs::remove_file(&tmp);
+        let mut app = AuditAppender::open(&tmp).unwrap();
+        app.append(deleted(1, "short".into())).unwrap();
+        // Longer than one read block, so the last line straddles a block boundary.
+        let last = app.append(deleted(2, "x".repeat(20_000))).unwrap();
+        OpenOptions::new().append(true).open(&tmp).unwrap().write_all(b"\n\n").unwrap();
+
+        assert_eq!(last_record_hash(&tmp).unwrap(), Some(last.hash.clone()));
+        let next = AuditAppender::open(&tmp).unwrap().append(deleted(3, "after".into())).unwrap();
+        assert_eq!(next.prev_hash, last.hash);
+
+        fs::write(&tmp, "\n").unwrap();
+        assert_eq!(last_record_hash(&tmp).unwrap(), None);
+    }
+
+    #[test]
+    fn torn_last_line_is_a_malformed_tail() {
+        let tmp = std::env::temp_dir().join("pieBot_audit_torn_test.jsonl");
+        let _ = fs::remove_file(&tmp);
+        AuditAppender::open(&tmp).unwrap().append(deleted(1, "ok".into())).unwrap();
+        let offset = fs::metadata(&tmp).unwrap().len();
+        OpenOptions::new().append(true).open(&tmp).unwrap().write_all(b"{\"prev_hash\":\"sha").unwrap();
+
+        let err = AuditAppender::open(&tmp).err().unwrap();
+        assert!(matches!(err, AuditLogError::MalformedTail { offset: o, .. } if o == offset), "{err}");
+    }
+
+    #[test]
+    fn follower_sees_new_records_and_waits_for_complete_lines() {
+        let tmp = std::env::temp_dir().join("pieBot_audit_follow_test.jsonl");
+        let _ = fs::remove_file(&tmp);
+        let event = |tick| {
+            AuditEvent::ModelCallDispatched(ModelCallDispatched {
+                schema_version: 1,
+                run_id: RunId("r1".into()),
+                tick_id: TickId(tick),
+                ts: 1.0,
+                model_call: CallId(uuid::Uuid::nil()),
+                provider: "openai".into(),
+                model: "m".into(),
+                endpoint_fingerprint: "sha256:abc".into(),
+                request_post_hash: "sha256:def".into(),
+                request_body_hash: None,
+            })
+        };
+
+        let mut follower = AuditFollower::new(&tmp);
+        assert!(follower.poll().unwrap().is_empty(), "missing file reads as empty");
+
+        let mut app = AuditAppender::open(&tmp).unwrap();
+        app.append(event(1)).unwrap();
+        app.append(event(2)).unwrap();
+        let seen: Vec<(usize, u64)> = follower.poll().unwrap().iter().map(|(n, r)| (*n, r.event.tick_id().0)).collect();
+        assert_eq!(seen, [(1, 1), (2, 2)]);
+
+        // A half-written line is not consumed until its newline lands.
+        let third = serde_json::to_string(&app.append(event(3)).unwrap()).unwrap();
+        let text = fs::read_to_string(&tmp).unwrap();
+        fs::write(&tmp, &text[..text.len() - third.len() / 2]).unwrap();
+        assert!(follower.poll().unwrap().is_empty());
+        fs::write(&tmp, &text).unwrap();
+        assert_eq!(follower.poll().unwrap()[0].1.event.tick_id().0, 3);
+
+        // A forged record is flagged with its line number.
+        let mut forged: AuditRecord = serde_json::from_str(&third).unwrap();
+        forged.prev_hash = genesis_hash();
+        fs::write(&tmp, format!("{text}{}\n", serde_json::to_string(&forged).unwrap())).unwrap();
+        assert!(matches!(follower.poll(), Err(AuditLogError::HashMismatch { line: 4, .. })));
+    }
+
+    #[test]
+    fn wall_clock_timestamps_survive_verification() {
+        let tmp = std::env::temp_dir().join("pieBot_audit_float_test.jsonl");
+        let _ = fs::remove_file(&tmp);
+
+        let mut app = AuditAppender::open(&tmp).unwrap();
+        for i in 0..200u32 {
+            let ts = 1_760_612_345.0 + f64::from(i) * 0.001_234_567 + 1e-7;
+            app.append(AuditEvent::ModelCallDispatched(ModelCallDispatched {
+                schema_version: 1,
+                run_id: RunId("r1".into()),
+                tick_id: TickId(u64::from(i)),
+                ts,
+                model_call: CallId(uuid::Uuid::new_v4()),
+                provider: "openai".into(),
+                model: "m".into(),
+                endpoint_fingerprint: "sha256:abc".into(),
+                request_post_hash: "sha256:def".into(),
+                request_body_hash: None,
+            }))
+            .unwrap();
+        }
+        verify_log(&tmp).unwrap();
     }
 }
\ No newline at end of file
@@ -5,32 +5,36 @@
 //! - ModelCallPrepared
 //! - ModelRequestRedacted
 //! - ModelCallDispatched
+//! - ModelCallRetried (one per retried attempt)
 //! - ModelCallCompleted
+//! - ModelStreamCompleted (streamed calls only)
 //! - OpenMemory query events
+//!
 //! NOTE: schema_version increments are per-event, not global.
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(transparent)]
 pub struct RunId(pub String);
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(transparent)]
 pub struct TickId(pub u64);
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(transparent)]
 pub struct CallId(pub Uuid);
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ArtifactRef {
     pub r#type: String, // "artifact_ref"
     pub hash: String,   // sha256:...
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum AgentRole {
     Planner,
@@ -39,7 +43,7 @@ pub enum AgentRole {
     Summarizer,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum RiskClass {
     Read,
@@ -48,33 +52,33 @@ pub enum RiskClass {
     Network,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Actor {
     pub subsystem: String, // "models"
     pub backend: String,   // "openai" etc
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct IntegrityPre {
     pub request_pre_hash: String, // sha256:...
     pub request_pre_size_bytes: u64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct IntegrityRedacted {
     pub request_pre_hash: String,
     pub request_post_hash: String,
     pub request_post_size_bytes: u64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct PolicyMeta {
     pub decision_id: String,
     pub risk_class: RiskClass,
     pub requires_approval: bool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ModelCallMeta {
     pub call_id: CallId,
     pub role: AgentRole,
@@ -82,7 +86,7 @@ pub struct ModelCallMeta {
     pub model: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ModelCallPrepared {
     pub schema_version: u8,
     pub run_id: RunId,
@@ -94,7 +98,7 @@ pub struct ModelCallPrepared {
     pub policy: PolicyMeta,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct RedactionMeta {
     pub profile: String,              // "strict" etc
     pub transform_count: u64,
@@ -102,7 +106,7 @@ pub struct RedactionMeta {
     pub summary_budget_chars: u64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ModelRequestRedacted {
     pub schema_version: u8,
     pub run_id: RunId,
@@ -114,14 +118,14 @@ pub struct ModelRequestRedacted {
     pub artifacts: RedactionArtifacts,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct RedactionArtifacts {
     pub pre_request_artifact: ArtifactRef,
     pub post_request_artifact: ArtifactRef,
     pub transform_log_artifact: ArtifactRef,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ModelCallDispatched {
     pub schema_version: u8,
     pub run_id: RunId,
@@ -132,9 +136,14 @@ pub struct ModelCallDispatched {
     pub model: String,
     pub endpoint_fingerprint: String, // sha256:...
     pub request_post_hash: String,    // sha256:...
+    /// sha256 of the body bytes sent to the provider (`Provider::request_body`). Unlike
+    /// `request_post_hash` it covers the provider-shaped body; absent for streamed calls and
+    /// transports that do not expose their body.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_body_hash: Option<String>,
 }
 
-#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+#[derive(Debug, Copy, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum CallStatus {
     Ok,
@@ -143,16 +152,30 @@ pub enum CallStatus {
     RateLimited,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ModelCallResult {
     pub status: CallStatus,
     pub latency_ms: u64,
     pub provider_request_id_hash: String,
     pub response_hash: String,
     pub response_size_bytes: u64,
+    /// Token usage reported by the provider (absent on errors or when not reported).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input_tokens: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_tokens: Option<u64>,
+    /// Reasoning tokens within `output_tokens` (o-series / thinking models); priced as output.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reasoning_tokens: Option<u64>,
+    /// Usage priced with the dispatcher's price table; absent without prices or usage.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub estimated_cost_usd: Option<f64>,
+    /// Served from the dispatcher's post_hash response cache; no provider call was made.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub cache_hit: bool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ModelCallCompleted {
     pub schema_version: u8,
     pub run_id: RunId,
@@ -163,28 +186,137 @@ pub struct ModelCallCompleted {
     pub artifacts: CompletionArtifacts,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct CompletionArtifacts {
     pub response_artifact: ArtifactRef,
     pub normalized_reply_artifact: ArtifactRef,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// A failed attempt that will be retried. Sits between the call's ModelCallDispatched and its
+/// ModelCallCompleted, which records only the final attempt.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ModelCallRetried {
+    pub schema_version: u8,
+    pub run_id: RunId,
+    pub tick_id: TickId,
+    pub ts: f64,
+    pub model_call: CallId,
+    /// 1-based number of the attempt that failed.
+    pub attempt: u32,
+    pub status: CallStatus,
+    /// Wait before the next attempt (the provider's Retry-After when it sent one).
+    pub wait_ms: u64,
+}
+
+/// Stream statistics for a streamed call. Follows that call's ModelCallCompleted, which records
+/// the aggregated response exactly as for a non-streamed call.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ModelStreamCompleted {
+    pub schema_version: u8,
+    pub run_id: RunId,
+    pub tick_id: TickId,
+    pub ts: f64,
+    pub model_call: CallId,
+    pub delta_count: u64,
+    /// Time from dispatch to the first content delta; None if no content arrived.
+    pub first_delta_ms: Option<u64>,
+    pub streamed_bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(tag = "event_type")]
 pub enum AuditEvent {
     ModelCallPrepared(ModelCallPrepared),
     ModelRequestRedacted(ModelRequestRedacted),
     ModelCallDispatched(ModelCallDispatched),
+    ModelCallRetried(ModelCallRetried),
     ModelCallCompleted(ModelCallCompleted),
+    ModelStreamCompleted(ModelStreamCompleted),
     EpisodeAppended(EpisodeAppended),
     EpisodeMirrorAttempted(EpisodeMirrorAttempted),
     EpisodeMirrored(EpisodeMirrored),
     EpisodeMirrorFailed(EpisodeMirrorFailed),
     EpisodeQueryPerformed(EpisodeQueryPerformed),
     EpisodeQueryFailed(EpisodeQueryFailed),
+    EpisodeDeleted(EpisodeDeleted),
+}
+
+impl AuditEvent {
+    /// The `event_type` tag this event serializes with.
+    pub fn event_type(&self) -> &'static str {
+        match self {
+            AuditEvent::ModelCallPrepared(_) => "ModelCallPrepared",
+            AuditEvent::ModelRequestRedacted(_) => "ModelRequestRedacted",
+            AuditEvent::ModelCallDispatched(_) => "ModelCallDispatched",
+            AuditEvent::ModelCallRetried(_) => "ModelCallRetried",
+            AuditEvent::ModelCallCompleted(_) => "ModelCallCompleted",
+            AuditEvent::ModelStreamCompleted(_) => "ModelStreamCompleted",
+            AuditEvent::EpisodeAppended(_) => "EpisodeAppended",
+            AuditEvent::EpisodeMirrorAttempted(_) => "EpisodeMirrorAttempted",
+            AuditEvent::EpisodeMirrored(_) => "EpisodeMirrored",
+            AuditEvent::EpisodeMirrorFailed(_) => "EpisodeMirrorFailed",
+            AuditEvent::EpisodeQueryPerformed(_) => "EpisodeQueryPerformed",
+            AuditEvent::EpisodeQueryFailed(_) => "EpisodeQueryFailed",
+            AuditEvent::EpisodeDeleted(_) => "EpisodeDeleted",
+        }
+    }
+
+    pub fn run_id(&self) -> &RunId {
+        match self {
+            AuditEvent::ModelCallPrepared(e) => &e.run_id,
+            AuditEvent::ModelRequestRedacted(e) => &e.run_id,
+            AuditEvent::ModelCallDispatched(e) => &e.run_id,
+            AuditEvent::ModelCallRetried(e) => &e.run_id,
+            AuditEvent::ModelCallCompleted(e) => &e.run_id,
+            AuditEvent::ModelStreamCompleted(e) => &e.run_id,
+            AuditEvent::EpisodeAppended(e) => &e.run_id,
+            AuditEvent::EpisodeMirrorAttempted(e) => &e.run_id,
+            AuditEvent::EpisodeMirrored(e) => &e.run_id,
+            AuditEvent::EpisodeMirrorFailed(e) => &e.run_id,
+            AuditEvent::EpisodeQueryPerformed(e) => &e.run_id,
+            AuditEvent::EpisodeQueryFailed(e) => &e.run_id,
+            AuditEvent::EpisodeDeleted(e) => &e.run_id,
+        }
+    }
+
+    pub fn tick_id(&self) -> &TickId {
+        match self {
+            AuditEvent::ModelCallPrepared(e) => &e.tick_id,
+            AuditEvent::ModelRequestRedacted(e) => &e.tick_id,
+            AuditEvent::ModelCallDispatched(e) => &e.tick_id,
+            AuditEvent::ModelCallRetried(e) => &e.tick_id,
+            AuditEvent::ModelCallCompleted(e) => &e.tick_id,
+            AuditEvent::ModelStreamCompleted(e) => &e.tick_id,
+            AuditEvent::EpisodeAppended(e) => &e.tick_id,
+            AuditEvent::EpisodeMirrorAttempted(e) => &e.tick_id,
+            AuditEvent::EpisodeMirrored(e) => &e.tick_id,
+            AuditEvent::EpisodeMirrorFailed(e) => &e.tick_id,
+            AuditEvent::EpisodeQueryPerformed(e) => &e.tick_id,
+            AuditEvent::EpisodeQueryFailed(e) => &e.tick_id,
+            AuditEvent::EpisodeDeleted(e) => &e.tick_id,
+        }
+    }
+
+    pub fn ts(&self) -> f64 {
+        match self {
+            AuditEvent::ModelCallPrepared(e) => e.ts,
+            AuditEvent::ModelRequestRedacted(e) => e.ts,
+            AuditEvent::ModelCallDispatched(e) => e.ts,
+            AuditEvent::ModelCallRetried(e) => e.ts,
+            AuditEvent::ModelCallCompleted(e) => e.ts,
+            AuditEvent::ModelStreamCompleted(e) => e.ts,
+            AuditEvent::EpisodeAppended(e) => e.ts,
+            AuditEvent::EpisodeMirrorAttempted(e) => e.ts,
+            AuditEvent::EpisodeMirrored(e) => e.ts,
+            AuditEvent::EpisodeMirrorFailed(e) => e.ts,
+            AuditEvent::EpisodeQueryPerformed(e) => e.ts,
+            AuditEvent::EpisodeQueryFailed(e) => e.ts,
+            AuditEvent::EpisodeDeleted(e) => e.ts,
+        }
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct EpisodeAppended {
     pub schema_version: u8,
     pub run_id: RunId,
@@ -200,7 +332,7 @@ pub struct EpisodeAppended {
     pub episode_artifact: ArtifactRef,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct EpisodeMirrorAttempted {
     pub schema_version: u8,
     pub run_id: RunId,
@@ -211,7 +343,7 @@ pub struct EpisodeMirrorAttempted {
     pub target: String, // e.g. "openmemory"
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct EpisodeMirrored {
     pub schema_version: u8,
     pub run_id: RunId,
@@ -223,7 +355,7 @@ pub struct EpisodeMirrored {
     pub remote_id: String, // returned by OpenMemory
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct EpisodeMirrorFailed {
     pub schema_version: u8,
     pub run_id: RunId,
@@ -235,7 +367,7 @@ pub struct EpisodeMirrorFailed {
     pub error: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct EpisodeQueryPerformed {
     pub schema_version: u8,
     pub run_id: RunId,
@@ -252,7 +384,7 @@ pub struct EpisodeQueryPerformed {
     pub response_artifact: ArtifactRef,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct EpisodeQueryFailed {
     pub schema_version: u8,
     pub run_id: RunId,
@@ -266,3 +398,17 @@ pub struct EpisodeQueryFailed {
     pub alias: Option<String>,
     pub error: String,
 }
+
+/// An episode was tombstoned in the local store (compliance-driven removal).
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct EpisodeDeleted {
+    pub schema_version: u8,
+    pub run_id: RunId,   // the deleted episode's run
+    pub tick_id: TickId, // the deleted episode's tick
+    pub ts: f64,
+    pub episode_id: Uuid,
+    pub episode_hash: String,
+    pub reason: String,
+    /// Remote memory removed from the mirror target, if the episode had been mirrored and unmirroring was requested.
+    pub unmirrored_remote_id: Option<String>,
+}
@@ -1,6 +1,6 @@
 //! pieBot_common
 //!
-//! Canonical JSON serialization + SHA-256 hashing utilities.
+//! Canonical JSON serialization + SHA-256 (and BLAKE3) hashing utilities.
 //! This exists to guarantee determinism for:
 //! - audit event hashing
 //! - redaction pre/post integrity hashes
@@ -42,6 +42,24 @@ pub fn sha256_bytes(bytes: &[u8]) -> String {
     format!("sha256:{}", hex::encode(digest))
 }
 
+/// Return "blake3:<hex>" of canonical JSON bytes.
+pub fn blake3_canonical_json<T: Serialize>(value: &T) -> Result<String, CanonError> {
+    let bytes = canonical_json_bytes(value)?;
+    Ok(blake3_bytes(&bytes))
+}
+
+/// Return "blake3:<hex>" of raw bytes.
+pub fn blake3_bytes(bytes: &[u8]) -> String {
+    format!("blake3:{}", blake3::hash(bytes).to_hex())
+}
+
+/// `ModelCallDispatched.endpoint_fingerprint`: "sha256:<hex>" of `provider:{p}|base_url:{u}|model:{m}`.
+/// Identifies where a call went without recording the URL itself; operators recompute it to
+/// compare environments, so the construction must not change.
+pub fn endpoint_fingerprint(provider: &str, base_url: &str, model: &str) -> String {
+    sha256_bytes(format!("provider:{provider}|base_url:{base_url}|model:{model}").as_bytes())
+}
+
 fn sort_json_value(v: serde_json::Value) -> serde_json::Value {
     use serde_json::Value;
     match v {
@@ -78,4 +96,20 @@ mod tests {
         let hy = sha256_canonical_json(&y).unwrap();
         assert_eq!(hx, hy);
     }
+
+    #[test]
+    fn blake3_is_prefixed_and_order_independent() {
+        let hx = blake3_canonical_json(&Obj { b: 2, a: 1 }).unwrap();
+        assert!(hx.starts_with("blake3:"));
+        assert_eq!(hx.len(), "blake3:".len() + 64);
+        assert_eq!(hx, blake3_bytes(br#"{"a":1,"b":2}"#));
+    }
+
+    #[test]
+    fn endpoint_fingerprint_hashes_the_labelled_triple() {
+        assert_eq!(
+            endpoint_fingerprint("openai", "https://api.openai.com", "gpt"),
+            sha256_bytes(b"provider:openai|base_url:https://api.openai.com|model:gpt")
+        );
+    }
 }
\ No newline at end of file
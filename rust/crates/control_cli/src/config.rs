@@ -0,0 +1,171 @@
+//! `pie-control.toml`: defaults for flags that every pipeline step would otherwise repeat.
+//!
+//! Discovery: `$PIE_CONFIG` if set (the file must exist), else `pie-control.toml` under the
+//! `--repo-root` given on the command line (optional).
+//!
+//! Precedence, highest first:
+//! 1. explicit CLI flag
+//! 2. environment variable the command already consults (for provider commands, the selected
+//!    provider's own base-URL variable, e.g. OPENAI_BASE_URL only when dispatching to openai)
+//! 3. config file value
+//! 4. built-in default
+//!
+//! Values are applied by appending the missing flags to argv before clap parses it, and only for
+//! subcommands that accept the flag. The provider `base_url` is the exception: which env var
+//! outranks it depends on the provider, which may only be known from the request, so it is
+//! resolved at provider selection instead. Relative paths resolve against the config file's directory.
+//! API keys are deliberately not accepted here; keep secrets in .env.
+//!
+//! ```toml
+//! repo_root = "."
+//! audit_log = "runtime/logs/audit_rust.jsonl"
+//! base_url = "http://localhost:8000"              # provider (dispatch*, run-call, provider-models)
+//! openmemory_base_url = "http://127.0.0.1:8080"   # OpenMemory commands
+//! timeout_ms = 30000
+//! user_id = "pie"
+//! ```
+
+use crate::{Args, CliError};
+use clap::CommandFactory;
+use serde::Deserialize;
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const CONFIG_FILE: &str = "pie-control.toml";
+pub const CONFIG_ENV: &str = "PIE_CONFIG";
+
+/// Subcommands whose `--base-url` is the model provider rather than OpenMemory.
+const PROVIDER_COMMANDS: &[&str] = &["dispatch", "dispatch-dir", "dispatch-resume", "run-call", "provider-models"];
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CliConfig {
+    pub repo_root: Option<PathBuf>,
+    pub audit_log: Option<PathBuf>,
+    pub base_url: Option<String>,
+    pub openmemory_base_url: Option<String>,
+    pub timeout_ms: Option<u64>,
+    pub user_id: Option<String>,
+}
+
+impl CliConfig {
+    pub fn load(path: &Path) -> Result<Self, CliError> {
+        let text = fs::read_to_string(path)?;
+        let mut cfg: CliConfig =
+            toml::from_str(&text).map_err(|e| CliError::Config(format!("{}: {e}", path.display())))?;
+
+        // Joining an absolute path replaces the base, so absolute values pass through unchanged.
+        let base = path.parent().unwrap_or(Path::new("."));
+        cfg.repo_root = cfg.repo_root.map(|p| base.join(p));
+        cfg.audit_log = cfg.audit_log.map(|p| base.join(p));
+        Ok(cfg)
+    }
+
+    pub fn discover(argv: &[OsString]) -> Result<Option<Self>, CliError> {
+        if let Some(p) = std::env::var_os(CONFIG_ENV) {
+            return Self::load(Path::new(&p)).map(Some);
+        }
+        match flag_value(argv, "repo-root") {
+            Some(root) => {
+                let p = PathBuf::from(root).join(CONFIG_FILE);
+                if p.exists() {
+                    Self::load(&p).map(Some)
+                } else {
+                    Ok(None)
+                }
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// (flag, value) defaults for subcommand `sub`. Provider commands get no `base-url` here;
+    /// `base_url` is resolved per provider (see the module docs).
+    fn defaults_for(&self, sub: &str) -> Vec<(&'static str, OsString)> {
+        let mut out = Vec::new();
+        if let Some(v) = &self.repo_root {
+            out.push(("repo-root", v.into()));
+        }
+        if let Some(v) = &self.audit_log {
+            out.push(("audit-log", v.into()));
+        }
+        if let Some(v) = self.openmemory_base_url.as_ref().filter(|_| !PROVIDER_COMMANDS.contains(&sub)) {
+            out.push(("base-url", v.into()));
+        }
+        if let Some(v) = self.timeout_ms {
+            out.push(("timeout-ms", v.to_string().into()));
+        }
+        if let Some(v) = &self.user_id {
+            out.push(("user-id", v.into()));
+        }
+        out
+    }
+
+    /// Append config values for flags the subcommand accepts but argv omits.
+    pub fn apply(&self, mut argv: Vec<OsString>) -> Vec<OsString> {
+        // First argv entry naming a subcommand (global flags like --format may precede it).
+        let cmd = Args::command();
+        let Some(sc) = argv.iter().skip(1).find_map(|a| cmd.find_subcommand(a)) else {
+            return argv;
+        };
+        let sub = sc.get_name().to_string();
+
+        for (flag, value) in self.defaults_for(&sub) {
+            let accepted = sc.get_arguments().any(|a| a.get_long() == Some(flag));
+            let explicit = flag_value(&argv, flag).is_some();
+            if accepted && !explicit {
+                argv.push(format!("--{flag}").into());
+                argv.push(value);
+            }
+        }
+        argv
+    }
+}
+
+/// Value of `--flag v` or `--flag=v` in argv, if present.
+fn flag_value(argv: &[OsString], flag: &str) -> Option<OsString> {
+    let long = format!("--{flag}");
+    let prefix = format!("{long}=");
+    let mut it = argv.iter();
+    while let Some(a) = it.next() {
+        let s = a.to_string_lossy();
+        if s == long {
+            return it.next().cloned();
+        }
+        if let Some(v) = s.strip_prefix(&prefix) {
+            return Some(v.into());
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cfg() -> CliConfig {
+        CliConfig {
+            base_url: Some("http://provider".into()),
+            openmemory_base_url: Some("http://openmemory".into()),
+            ..Default::default()
+        }
+    }
+
+    fn base_url_for(sub: &str) -> Option<OsString> {
+        cfg().defaults_for(sub).into_iter().find(|(flag, _)| *flag == "base-url").map(|(_, v)| v)
+    }
+
+    #[test]
+    fn openmemory_commands_get_the_openmemory_base_url() {
+        assert_eq!(base_url_for("episode-mirror"), Some("http://openmemory".into()));
+    }
+
+    #[test]
+    fn provider_commands_never_get_the_openmemory_base_url_injected() {
+        // run-call, dispatch-resume and provider-models included: their base URL is the provider's,
+        // resolved against that provider's env var at selection time.
+        for sub in PROVIDER_COMMANDS {
+            assert_eq!(base_url_for(sub), None, "{sub}");
+        }
+    }
+}
@@ -1,15 +1,19 @@
-use clap::{Parser, Subcommand};
+mod config;
+
+use clap::{CommandFactory, Parser, Subcommand};
 use dotenvy::from_path as dotenv_from_path;
 use serde_json::json;
 use serde_json::Value as JsonValue;
-use pie_audit_log::{verify_log, AuditAppender};
+use pie_audit_log::{verify_log, verify_log_report, AuditAppender, AuditFollower};
 use pie_common::sha256_bytes;
 use pie_redaction::{ModelRequest, RedactionEngine, RedactionProfile, SanitizedModelRequest, CallManifest};
 use pie_audit_spec as spec;
-use pie_providers::{OpenAICompatProvider, Provider};
+use pie_providers::{
+    build_provider_with_options, verify_integrity, MockProvider, OpenAICompatProvider, Provider, ProviderOptions,
+};
 use pie_episodes as episodes;
 use pie_openmemory_mirror as om;
-use std::time::Instant;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 use std::fs;
 use std::path::{Path, PathBuf};
 use thiserror::Error;
@@ -33,16 +37,207 @@ enum CliError {
     Episodes(#[from] episodes::EpisodeError),
     #[error("openmemory error: {0}")]
     OpenMemory(#[from] om::OpenMemoryError),    
+    #[error("config error: {0}")]
+    Config(String),
+    /// Malformed input the provider never saw (bad call id, unsealed request, ...).
+    #[error("invalid input: {0}")]
+    Input(String),
+    #[error("replay failed: {0} artifact(s) do not match the audit log")]
+    ReplayMismatch(usize),
+    #[error("validation failed: {0} issue(s)")]
+    Invalid(usize),
+    #[error("--provider {flag} conflicts with the request's declared provider {declared} (pass --force-provider to override)")]
+    ProviderConflict { flag: String, declared: String },
+    /// Bad command line; clap renders its own message.
+    #[error("{0}")]
+    Usage(clap::Error),
+}
+
+impl CliError {
+    /// Process exit code for this error's category (see the EXIT CODES section of `--help`).
+    fn exit_code(&self) -> i32 {
+        use episodes::EpisodeError;
+        use pie_audit_log::AuditLogError;
+        use pie_providers::ProviderError;
+        use pie_redaction::RedactionError;
+        match self {
+            // Filesystem failures surface as I/O whichever layer hit them.
+            CliError::Io(_)
+            | CliError::Redaction(RedactionError::Io(_))
+            | CliError::Audit(AuditLogError::Io(_))
+            | CliError::Episodes(EpisodeError::Io(_)) => 2,
+            CliError::Json(_)
+            | CliError::Canon(_)
+            | CliError::Invalid(_)
+            | CliError::Config(_)
+            | CliError::Input(_)
+            | CliError::Usage(_)
+            | CliError::ProviderConflict { .. } => 3,
+            // Raised before anything is sent: retrying cannot help.
+            CliError::Provider(ProviderError::InvalidRequest(_) | ProviderError::UnknownProvider(_)) => 3,
+            CliError::Redaction(_) => 4,
+            CliError::Audit(_)
+            | CliError::ReplayMismatch(_)
+            | CliError::Provider(ProviderError::IntegrityMismatch { .. }) => 5,
+            CliError::Provider(_) => 6,
+            CliError::OpenMemory(_) => 7,
+            CliError::Episodes(EpisodeError::Json(_) | EpisodeError::Canon(_)) => 3,
+            CliError::Episodes(_) => 1,
+        }
+    }
 }
 
+/// Stand-in base URL recorded in the endpoint fingerprint for `--dry-run` dispatches.
+const DRY_RUN_ENDPOINT: &str = "dry-run://mock";
+
 #[derive(Parser)]
-#[command(name = "pie-control", version, about = "pieBot Rust control-plane utilities (6B boundary)")]
+#[command(
+    name = "pie-control",
+    version,
+    about = "pieBot Rust control-plane utilities (6B boundary)",
+    after_help = "Common flag defaults may be set in pie-control.toml (at --repo-root, or the path in $PIE_CONFIG).\n\
+                  Precedence: explicit flag > environment variable > config file > built-in default.\n\
+                  Omitted --ts/--ts-* flags default to the current Unix time; tests should pass explicit\n\
+                  timestamps (or --deterministic-ts) so audit bytes are reproducible.\n\n\
+                  EXIT CODES:\n  \
+                  0  success\n  \
+                  1  other failure (e.g. episode not found)\n  \
+                  2  I/O error (missing file, permissions)\n  \
+                  3  bad input: invalid JSON, failed validation, config or usage error, unknown provider\n  \
+                  4  redaction error\n  \
+                  5  audit integrity failure (broken chain, replay mismatch, tampered request)\n  \
+                  6  provider failure (HTTP, timeout, rate limit; often worth retrying)\n  \
+                  7  OpenMemory failure"
+)]
 struct Args {
+    /// Output style for command results: compact single-line JSON, or indented JSON for humans.
+    #[arg(long, global = true, value_enum, default_value_t = OutputFormat::Json)]
+    format: OutputFormat,
+
+    /// Write 0.0 for every omitted --ts/--ts-* flag instead of the current Unix time.
+    #[arg(long, global = true)]
+    deterministic_ts: bool,
+
+    /// More diagnostics on stderr: -v info, -vv debug (e.g. .env loading), -vvv trace.
+    #[arg(short, long, global = true, action = clap::ArgAction::Count, conflicts_with = "quiet")]
+    verbose: u8,
+
+    /// Only report errors on stderr.
+    #[arg(short, long, global = true)]
+    quiet: bool,
+
     #[command(subcommand)]
     cmd: Command,
 }
 
-#[derive(Debug, serde::Deserialize)]
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+#[value(rename_all = "snake_case")]
+enum RequestKind {
+    ModelRequest,
+    Sanitized,
+}
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+#[value(rename_all = "snake_case")]
+enum SchemaType {
+    ModelRequest,
+    Sanitized,
+    EpisodeAppend,
+    AuditEvent,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum ProviderChoice {
+    Auto,
+    Openai,
+    Anthropic,
+    Gemini,
+    Ollama,
+    Bedrock,
+}
+
+impl ProviderChoice {
+    /// The factory `ProviderId` this choice pins, or None for `auto`.
+    fn id(self) -> Option<&'static str> {
+        match self {
+            ProviderChoice::Auto => None,
+            ProviderChoice::Openai => Some("openai"),
+            ProviderChoice::Anthropic => Some("anthropic"),
+            ProviderChoice::Gemini => Some("gemini"),
+            ProviderChoice::Ollama => Some("ollama"),
+            ProviderChoice::Bedrock => Some("bedrock"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+enum HashAlgo {
+    Sha256,
+    Blake3,
+}
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+enum OutputFormat {
+    Json,
+    Pretty,
+}
+
+/// Resolves `--ts*` flags: an explicit value always wins; otherwise the current Unix time, or 0.0
+/// under `--deterministic-ts`. Tests that compare audit bytes should pass explicit timestamps.
+#[derive(Debug, Clone, Copy)]
+struct Clock {
+    deterministic: bool,
+}
+
+impl Clock {
+    fn ts(self, explicit: Option<f64>) -> f64 {
+        match explicit {
+            Some(ts) => ts,
+            None if self.deterministic => 0.0,
+            None => SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs_f64()).unwrap_or(0.0),
+        }
+    }
+}
+
+/// Leveled diagnostics on stderr (stdout stays reserved for command results). Warnings show by
+/// default; each `-v` adds a level and `-q` keeps only errors.
+fn init_logging(verbose: u8, quiet: bool) {
+    let level = match (quiet, verbose) {
+        (true, _) => tracing::Level::ERROR,
+        (false, 0) => tracing::Level::WARN,
+        (false, 1) => tracing::Level::INFO,
+        (false, 2) => tracing::Level::DEBUG,
+        (false, _) => tracing::Level::TRACE,
+    };
+    tracing_subscriber::fmt()
+        .with_max_level(level)
+        .with_writer(std::io::stderr)
+        .with_target(false)
+        .without_time()
+        .init();
+}
+
+/// Read a request file, or all of stdin when the path is `-`.
+fn read_input(path: &Path) -> Result<Vec<u8>, CliError> {
+    if path == Path::new("-") {
+        let mut buf = Vec::new();
+        std::io::Read::read_to_end(&mut std::io::stdin(), &mut buf)?;
+        return Ok(buf);
+    }
+    Ok(fs::read(path)?)
+}
+
+/// Print one command result in the selected `--format`.
+fn emit(value: &impl serde::Serialize, format: OutputFormat) -> Result<(), CliError> {
+    let out = match format {
+        OutputFormat::Json => serde_json::to_string(value)?,
+        OutputFormat::Pretty => serde_json::to_string_pretty(value)?,
+    };
+    println!("{out}");
+    Ok(())
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
 struct EpisodeAppendRequest {
     schema_version: u8,
     run_id: String,
@@ -59,7 +254,7 @@ struct EpisodeAppendRequest {
     created_ts: f64,
 }
 
-#[derive(Debug, serde::Deserialize)]
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
 struct EpisodeAppendArtifact {
     hash: String,
     #[serde(default)]
@@ -80,7 +275,7 @@ enum Command {
         #[arg(long)]
         repo_root: PathBuf,
 
-        /// Path to ModelRequest JSON file (internal/unsafe)
+        /// Path to ModelRequest JSON file (internal/unsafe), or `-` to read stdin
         #[arg(long)]
         request_json: PathBuf,
 
@@ -109,12 +304,12 @@ enum Command {
         summary_budget_chars: u64,
 
         /// Timestamp for ModelCallPrepared (float seconds)
-        #[arg(long, default_value_t = 0.0)]
-        ts_prepared: f64,
+        #[arg(long)]
+        ts_prepared: Option<f64>,
 
         /// Timestamp for ModelRequestRedacted (float seconds)
-        #[arg(long, default_value_t = 0.0)]
-        ts_redacted: f64,
+        #[arg(long)]
+        ts_redacted: Option<f64>,
     },
 
     /// Dispatch a call by pointing at the call directory created by redact-only.
@@ -138,11 +333,194 @@ enum Command {
         #[arg(long)]
         api_key: Option<String>,
 
-        #[arg(long, default_value_t = 0.0)]
-        ts_dispatched: f64,
+        /// Provider HTTP timeout in ms (whole request). Omit for no timeout.
+        #[arg(long)]
+        timeout_ms: Option<u64>,
+
+        /// Hard budget for the provider call in ms, independent of the HTTP timeout; a fired
+        /// deadline is recorded as status Timeout.
+        #[arg(long)]
+        deadline_ms: Option<u64>,
+
+        /// Retry a failed call (error, timeout, rate limit) up to N more times; each retry is
+        /// recorded as ModelCallRetried. The deadline, if any, covers all attempts.
+        #[arg(long, default_value_t = 0)]
+        max_retries: u32,
+
+        /// Wait between attempts in ms; a provider `Retry-After` takes precedence.
+        #[arg(long, default_value_t = 500)]
+        retry_backoff_ms: u64,
+
+        /// USD per 1k input tokens, for estimated_cost_usd (with --price-output-per-1k).
+        #[arg(long, requires = "price_output_per_1k")]
+        price_input_per_1k: Option<f64>,
+
+        /// USD per 1k output tokens (with --price-input-per-1k).
+        #[arg(long, requires = "price_input_per_1k")]
+        price_output_per_1k: Option<f64>,
+
+        /// JSON price table {"<model>": {"input_per_1k": f, "output_per_1k": f}}. The --price-*
+        /// flags take precedence; a model missing from the table gets no cost.
+        #[arg(long)]
+        prices_file: Option<PathBuf>,
+
+        /// Serve a request whose integrity.post_hash was already answered from runtime/cache/
+        /// instead of calling the provider (recorded with cache_hit); Ok replies are cached.
+        #[arg(long, conflicts_with_all = ["dry_run"])]
+        cache: bool,
+
+        /// Skip the network: answer with a canned deterministic reply (`MockProvider::dry_run`).
+        /// Artifacts and audit events are still written; the response id is marked `dry-run:`.
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Provider implementation to dispatch with; `auto` uses the request's declared provider.
+        #[arg(long, value_enum, default_value_t = ProviderChoice::Auto)]
+        provider: ProviderChoice,
+
+        /// Allow --provider to differ from the request's declared provider.
+        #[arg(long)]
+        force_provider: bool,
+
+        #[arg(long)]
+        ts_dispatched: Option<f64>,
+
+        #[arg(long)]
+        ts_completed: Option<f64>,
+    },
+
+    /// Re-dispatch the calls an audit log shows as dispatched but not completed Ok (dangling, or
+    /// completed with Error/Timeout/RateLimited), e.g. after a partial batch run.
+    ///
+    /// Each call is read back from runtime/artifacts/models/<run>/<call>/ and recorded as
+    /// ModelCallRetried (status of the failed attempt; Error if it never completed), then a fresh
+    /// ModelCallDispatched and ModelCallCompleted. Calls go back to the provider their last
+    /// ModelCallDispatched recorded (so `--force-provider` sticks). Calls whose directory is gone are skipped.
+    DispatchResume {
+        #[arg(long)]
+        repo_root: PathBuf,
+
+        #[arg(long)]
+        audit_log: PathBuf,
+
+        #[arg(long)]
+        base_url: Option<String>,
+
+        #[arg(long)]
+        api_key: Option<String>,
+
+        /// Provider HTTP timeout in ms (whole request). Omit for no timeout.
+        #[arg(long)]
+        timeout_ms: Option<u64>,
+
+        /// Hard budget for each re-dispatched call in ms; a fired deadline is recorded as status Timeout.
+        #[arg(long)]
+        deadline_ms: Option<u64>,
+
+        /// Skip the network: answer with a canned deterministic reply (`MockProvider::dry_run`).
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Redact a ModelRequest and dispatch it in one process: the redact-only + dispatch-dir happy path.
+    ///
+    /// Emits ModelCallPrepared, ModelRequestRedacted, ModelCallDispatched and ModelCallCompleted
+    /// through a single audit appender, and writes all artifacts into the call directory.
+    RunCall {
+        /// Repo root containing runtime/
+        #[arg(long)]
+        repo_root: PathBuf,
+
+        /// Path to ModelRequest JSON file (internal/unsafe), or `-` to read stdin
+        #[arg(long)]
+        request_json: PathBuf,
+
+        #[arg(long)]
+        audit_log: PathBuf,
+
+        #[arg(long, default_value = "policy_decision_unspecified")]
+        policy_decision_id: String,
+
+        #[arg(long, default_value_t = true)]
+        requires_approval: bool,
+
+        #[arg(long, default_value = "policy_unspecified")]
+        policy_id: String,
+
+        /// Redaction profile: "strict" or "explicit_allowlist"
+        #[arg(long, default_value = "strict")]
+        profile: String,
+
+        #[arg(long, default_value_t = 1200)]
+        summary_budget_chars: u64,
+
+        /// Provider base URL; defaults per provider (OPENAI_BASE_URL, then https://api.openai.com for openai).
+        #[arg(long)]
+        base_url: Option<String>,
+
+        /// API key; defaults to the provider's env var (OPENAI_API_KEY, ANTHROPIC_API_KEY, GEMINI_API_KEY).
+        #[arg(long)]
+        api_key: Option<String>,
+
+        /// Provider HTTP timeout in ms (whole request). Omit for no timeout.
+        #[arg(long)]
+        timeout_ms: Option<u64>,
+
+        /// Hard budget for the provider call in ms, independent of the HTTP timeout; a fired
+        /// deadline is recorded as status Timeout.
+        #[arg(long)]
+        deadline_ms: Option<u64>,
+
+        /// Retry a failed call (error, timeout, rate limit) up to N more times; each retry is
+        /// recorded as ModelCallRetried. The deadline, if any, covers all attempts.
+        #[arg(long, default_value_t = 0)]
+        max_retries: u32,
+
+        /// Wait between attempts in ms; a provider `Retry-After` takes precedence.
+        #[arg(long, default_value_t = 500)]
+        retry_backoff_ms: u64,
+
+        /// USD per 1k input tokens, for estimated_cost_usd (with --price-output-per-1k).
+        #[arg(long, requires = "price_output_per_1k")]
+        price_input_per_1k: Option<f64>,
+
+        /// USD per 1k output tokens (with --price-input-per-1k).
+        #[arg(long, requires = "price_input_per_1k")]
+        price_output_per_1k: Option<f64>,
+
+        /// JSON price table {"<model>": {"input_per_1k": f, "output_per_1k": f}}. The --price-*
+        /// flags take precedence; a model missing from the table gets no cost.
+        #[arg(long)]
+        prices_file: Option<PathBuf>,
+
+        /// Serve a request whose integrity.post_hash was already answered from runtime/cache/
+        /// instead of calling the provider (recorded with cache_hit); Ok replies are cached.
+        #[arg(long, conflicts_with_all = ["dry_run"])]
+        cache: bool,
+
+        /// Skip the network and answer with the canned dry-run reply (see dispatch --dry-run).
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Provider implementation to dispatch with; `auto` uses the request's declared provider.
+        #[arg(long, value_enum, default_value_t = ProviderChoice::Auto)]
+        provider: ProviderChoice,
+
+        /// Allow --provider to differ from the request's declared provider.
+        #[arg(long)]
+        force_provider: bool,
+
+        #[arg(long)]
+        ts_prepared: Option<f64>,
+
+        #[arg(long)]
+        ts_redacted: Option<f64>,
+
+        #[arg(long)]
+        ts_dispatched: Option<f64>,
 
-        #[arg(long, default_value_t = 0.0)]
-        ts_completed: f64,
+        #[arg(long)]
+        ts_completed: Option<f64>,
     },
 
     Dispatch {
@@ -159,11 +537,12 @@ enum Command {
         audit_log: PathBuf,
 
         /// Provider base URL (e.g. http://localhost:8000 or https://api.openai.com)
-        /// Can be supplied via env OPENAI_BASE_URL.
+        /// Defaults to the selected provider's public endpoint; openai also reads env OPENAI_BASE_URL.
         #[arg(long)]
         base_url: Option<String>,
 
-        /// API key (optional). Can be supplied via env OPENAI_API_KEY.
+        /// API key (optional). Defaults to the selected provider's env var (OPENAI_API_KEY,
+        /// ANTHROPIC_API_KEY, GEMINI_API_KEY or GOOGLE_API_KEY).
         #[arg(long)]
         api_key: Option<String>,
 
@@ -171,13 +550,67 @@ enum Command {
         #[arg(long)]
         call_id: String,
 
+        /// Provider HTTP timeout in ms (whole request). Omit for no timeout.
+        #[arg(long)]
+        timeout_ms: Option<u64>,
+
+        /// Hard budget for the provider call in ms, independent of the HTTP timeout; a fired
+        /// deadline is recorded as status Timeout.
+        #[arg(long)]
+        deadline_ms: Option<u64>,
+
+        /// Retry a failed call (error, timeout, rate limit) up to N more times; each retry is
+        /// recorded as ModelCallRetried. The deadline, if any, covers all attempts.
+        #[arg(long, default_value_t = 0)]
+        max_retries: u32,
+
+        /// Wait between attempts in ms; a provider `Retry-After` takes precedence.
+        #[arg(long, default_value_t = 500)]
+        retry_backoff_ms: u64,
+
+        /// USD per 1k input tokens, for estimated_cost_usd (with --price-output-per-1k).
+        #[arg(long, requires = "price_output_per_1k")]
+        price_input_per_1k: Option<f64>,
+
+        /// USD per 1k output tokens (with --price-input-per-1k).
+        #[arg(long, requires = "price_input_per_1k")]
+        price_output_per_1k: Option<f64>,
+
+        /// JSON price table {"<model>": {"input_per_1k": f, "output_per_1k": f}}. The --price-*
+        /// flags take precedence; a model missing from the table gets no cost.
+        #[arg(long)]
+        prices_file: Option<PathBuf>,
+
+        /// Serve a request whose integrity.post_hash was already answered from runtime/cache/
+        /// instead of calling the provider (recorded with cache_hit); Ok replies are cached.
+        #[arg(long, conflicts_with_all = ["dry_run", "stream"])]
+        cache: bool,
+
+        /// Skip the network: answer with a canned deterministic reply (`MockProvider::dry_run`).
+        /// Artifacts and audit events are still written; the response id is marked `dry-run:`.
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Provider implementation to dispatch with; `auto` uses the request's declared provider.
+        #[arg(long, value_enum, default_value_t = ProviderChoice::Auto)]
+        provider: ProviderChoice,
+
+        /// Allow --provider to differ from the request's declared provider.
+        #[arg(long)]
+        force_provider: bool,
+
+        /// Print reply text to stdout as it arrives (before the JSON summary line). Artifacts are
+        /// written from the aggregated response; ModelStreamCompleted follows ModelCallCompleted.
+        #[arg(long)]
+        stream: bool,
+
         /// Timestamp for ModelCallDispatched
-        #[arg(long, default_value_t = 0.0)]
-        ts_dispatched: f64,
+        #[arg(long)]
+        ts_dispatched: Option<f64>,
 
-        /// Timestamp for ModelCallCompleted
-        #[arg(long, default_value_t = 0.0)]
-        ts_completed: f64,
+        /// Timestamp for ModelCallCompleted (and ModelStreamCompleted)
+        #[arg(long)]
+        ts_completed: Option<f64>,
     },
 
     /// Append a deterministic episode to runtime/memory/episodes and emit an audit event.
@@ -192,7 +625,7 @@ enum Command {
         #[arg(long)]
         repo_root: PathBuf,
 
-        /// JSON request describing the episode content
+        /// JSON request describing the episode content (path, or `-` for stdin)
         #[arg(long)]
         request_json: PathBuf,
 
@@ -201,17 +634,20 @@ enum Command {
         audit_log: PathBuf,
 
         /// Timestamp for EpisodeAppended
-        #[arg(long, default_value_t = 0.0)]
-        ts: f64,
+        #[arg(long)]
+        ts: Option<f64>,
     },
 
     /// Query the deterministic episode index in runtime/memory/episodes.
     ///
     /// Filters:
     /// - optional thread_id
+    /// - optional run_id
     /// - tags must include all provided --tag values
+    /// - tags must include at least one --tag-any value (when given)
     /// - optional since_tick (inclusive)
-    /// - limit
+    /// - limit (`--limit 0` returns nothing; `--all` removes the cap)
+    /// - order: asc (oldest first, default) or desc (newest first, applied before the limit)
     ///
     /// Output:
     /// - JSON array of index entries sorted deterministically
@@ -222,15 +658,34 @@ enum Command {
         #[arg(long)]
         thread_id: Option<String>,
 
+        #[arg(long)]
+        run_id: Option<String>,
+
         /// Provide multiple times: --tag role:planner --tag status:ok
         #[arg(long = "tag")]
         tags: Vec<String>,
 
+        /// Match episodes carrying ANY of these (repeatable): --tag-any role:planner --tag-any role:critic
+        #[arg(long = "tag-any")]
+        tags_any: Vec<String>,
+
         #[arg(long)]
         since_tick: Option<u64>,
 
         #[arg(long, default_value_t = 20)]
         limit: usize,
+
+        /// Return every matching episode (overrides the default --limit).
+        #[arg(long, conflicts_with = "limit")]
+        all: bool,
+
+        /// asc | desc (by tick, then line).
+        #[arg(long, default_value = "asc")]
+        order: episodes::SortOrder,
+
+        /// Also return episodes that have been superseded by a newer version.
+        #[arg(long)]
+        include_superseded: bool,
     },
 
     /// Load a full episode by episode_id (verifies hash + index).
@@ -245,104 +700,506 @@ enum Command {
         episode_id: String,
     },
 
-    /// Verify a hash-chained audit log JSONL and print final hash.
-    VerifyAudit {
+    /// Print episode store statistics (counts per thread/tag, tick range, bytes) as JSON.
+    EpisodeStats {
         #[arg(long)]
-        audit_log: PathBuf,
+        repo_root: PathBuf,
     },
 
-    /// Mirror a locally-stored episode into OpenMemory (best-effort, non-authoritative).
+    /// Rebuild runtime/memory/episodes/index.json (and tags.json) from episodes.jsonl.
+    /// Prints the number of entries written.
+    EpisodeReindex {
+        #[arg(long)]
+        repo_root: PathBuf,
+    },
+
+    /// Restore a store from an exported bundle (`EpisodeStore::export`).
     ///
-    /// This does NOT affect deterministic replay. It only emits audit events describing the attempt/result.
-    EpisodeMirror {
+    /// Verifies the bundle hash, every episode hash and the bundled index before writing.
+    /// Refuses a non-empty store unless --force, which replaces its contents.
+    /// Prints {"imported": episodes, "index_entries": entries}.
+    EpisodeImport {
         #[arg(long)]
         repo_root: PathBuf,
 
         #[arg(long)]
-        episode_id: String,
+        bundle: PathBuf,
+
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Check every index entry against episodes.jsonl and print the report as JSON.
+    /// Exits non-zero on the first discrepancy (suitable for cron).
+    EpisodeVerify {
+        #[arg(long)]
+        repo_root: PathBuf,
+    },
 
+    /// Verify a hash-chained audit log JSONL and print final hash.
+    VerifyAudit {
         #[arg(long)]
         audit_log: PathBuf,
+    },
 
-        /// OpenMemory base URL (default matches local backend dev server).
-        #[arg(long, default_value = "http://127.0.0.1:8080")]
-        base_url: String,
+    /// Verify an audit log and print a one-line JSON summary: counts per event type, run_ids,
+    /// tick range, model calls (with error/timeout/rate-limited counts) and the final chain hash.
+    /// Exits non-zero if verification fails.
+    AuditReport {
+        #[arg(long)]
+        audit_log: PathBuf,
+    },
 
-        /// Optional OpenMemory API key. If omitted, reads OPENMEMORY_API_KEY env var.
+    /// Follow an audit log as it grows, printing one line per new record
+    /// ({line, event_type, run_id, tick_id, ts, hash} with the hash shortened) and verifying the
+    /// chain as it goes. Waits for the file if it does not exist yet. Exits non-zero at the first
+    /// chain break; otherwise runs until interrupted (or until --max-records have been printed).
+    AuditWatch {
         #[arg(long)]
-        api_key: Option<String>,
+        audit_log: PathBuf,
+
+        #[arg(long, default_value_t = 250)]
+        poll_ms: u64,
 
-        /// Optional OpenMemory user_id (for multi-user isolation). Defaults to thread_id if omitted.
         #[arg(long)]
-        user_id: Option<String>,
+        max_records: Option<u64>,
+    },
 
-        #[arg(long, default_value_t = 2000)]
-        timeout_ms: u64,
+    /// Print the JSON Schema of an input/output wire type: model_request (redact-only input),
+    /// sanitized (dispatch input), episode_append (episode-append input) or audit_event (the
+    /// `event` of each audit log record).
+    Schema {
+        #[arg(long = "type", value_enum)]
+        kind: SchemaType,
+    },
 
-        #[arg(long, default_value_t = 0.0)]
-        ts: f64,
+    /// Print a shell completion script to stdout, e.g.
+    /// `pie-control completions --shell bash > /etc/bash_completion.d/pie-control`.
+    Completions {
+        #[arg(long, value_enum)]
+        shell: clap_complete::Shell,
     },
-    /// Query OpenMemory (/memory/query) and return reference-only results (no raw content).
-    EpisodeQueryRemote {
-        #[arg(long)]
-        repo_root: std::path::PathBuf,
 
-        /// Query text (will be hashed in audit; not stored verbatim).
+    /// Print the prefixed hash ("sha256:<hex>" / "blake3:<hex>") of a file.
+    ///
+    /// By default the file is parsed as JSON and the canonical bytes are hashed, matching the
+    /// hashes recorded in ArtifactRefs and audit events. --raw hashes the file bytes as-is.
+    Hash {
+        /// File to hash ("-" reads stdin)
         #[arg(long)]
-        query: String,
+        file: PathBuf,
 
-        /// Top-K results.
-        #[arg(long, default_value_t = 5)]
-        k: u32,
+        #[arg(long, value_enum, default_value_t = HashAlgo::Sha256)]
+        algo: HashAlgo,
 
-        /// Optional OpenMemory user_id filter.
+        /// Hash the bytes directly instead of canonical JSON.
         #[arg(long)]
-        user_id: Option<String>,
+        raw: bool,
+    },
 
-        /// Optional minimum similarity score (0-1). Server may ignore depending on deployment.
+    /// Print the endpoint fingerprint a dispatch to this provider/base URL/model records in
+    /// ModelCallDispatched, for comparing environments without the audit log. Dry runs record
+    /// the base URL as `dry-run://mock`.
+    EndpointFingerprint {
+        /// ProviderId as dispatched (e.g. "openai")
         #[arg(long)]
-        min_score: Option<f64>,
+        provider: String,
 
-        /// Base URL of OpenMemory backend.
-        #[arg(long, default_value = "http://127.0.0.1:8080")]
+        #[arg(long)]
         base_url: String,
 
-        /// Audit log path.
         #[arg(long)]
-        audit_log: std::path::PathBuf,
+        model: String,
+    },
 
-        /// Optional run_id for audit (defaults to run_demo).
-        #[arg(long, default_value = "run_demo")]
-        run_id: String,
+    /// List the models an OpenAI-compatible endpoint serves (`GET /v1/models`).
+    ///
+    /// Prints {"models":[...]}; with --model also "found", and exits 3 if that model is not listed.
+    ProviderModels {
+        /// Defaults to $OPENAI_BASE_URL, then https://api.openai.com
+        #[arg(long)]
+        base_url: Option<String>,
 
-        /// Optional tick_id for audit (defaults to 0).
-        #[arg(long, default_value_t = 0)]
-        tick_id: u64,
+        /// Defaults to $OPENAI_API_KEY
+        #[arg(long)]
+        api_key: Option<String>,
 
-        /// Timestamp for audit events.
-        #[arg(long, default_value_t = 0.0)]
-        ts: f64,
+        /// Provider HTTP timeout in ms (whole request). Omit for no timeout.
+        #[arg(long)]
+        timeout_ms: Option<u64>,
 
-        /// Request timeout in ms.
-        #[arg(long, default_value_t = 10_000)]
-        timeout_ms: u64,
-    },    
-}
+        /// Model id that must be listed.
+        #[arg(long)]
+        model: Option<String>,
+    },
 
-#[tokio::main]
-async fn main() {
-    if let Err(e) = run().await {
-        eprintln!("ERROR: {e}");
-        std::process::exit(1);
-    }
-}
+    /// Check that a request file deserializes and satisfies basic invariants (known roles, sane
+    /// sampling params, non-empty messages; for sanitized requests also a matching post hash).
+    ///
+    /// Prints {"kind","ok","errors":[{"path","message"}]}; exits non-zero if any check fails.
+    Validate {
+        #[arg(long, value_enum)]
+        kind: RequestKind,
 
-async fn run() -> Result<(), CliError> {
-    let args = Args::parse();
-    match args.cmd {
-        Command::VerifyAudit { audit_log } => {
-            let last = verify_log(audit_log)?;
-            println!("{last}");
+        /// Request JSON ("-" reads stdin)
+        #[arg(long)]
+        file: PathBuf,
+    },
+
+    /// Compare two call directories (e.g. the same prompt run in two environments).
+    ///
+    /// Reports which of request_post.json, reply_normalized.json and transform_log.json differ by
+    /// canonical hash, plus a field-level diff of request_post.json ({path, left, right}; a field
+    /// missing on one side shows as null). Exits zero whether or not the calls differ.
+    CallDiff {
+        #[arg(long)]
+        left: PathBuf,
+
+        #[arg(long)]
+        right: PathBuf,
+    },
+
+    /// Re-verify a call directory against the audit log.
+    ///
+    /// Recomputes the canonical hash of each artifact and compares it with the hash recorded for
+    /// the manifest's call_id:
+    /// - request_pre.json       vs ModelCallPrepared.integrity.request_pre_hash
+    /// - request_post.json      vs ModelRequestRedacted post_request_artifact
+    /// - transform_log.json     vs ModelRequestRedacted.redaction.transform_log_hash
+    /// - response_raw.json      vs ModelCallCompleted.result.response_hash
+    /// - reply_normalized.json  vs ModelCallCompleted normalized_reply_artifact
+    ///
+    /// Prints a per-artifact report as JSON; exits non-zero if any artifact fails.
+    Replay {
+        #[arg(long)]
+        repo_root: PathBuf,
+
+        /// Directory runtime/artifacts/models/<run>/<call>/ (relative paths resolve against --repo-root)
+        #[arg(long)]
+        call_dir: PathBuf,
+
+        #[arg(long)]
+        audit_log: PathBuf,
+    },
+
+    /// Tombstone an episode (content is removed on the next compaction) and emit EpisodeDeleted.
+    ///
+    /// With --unmirror, a previously mirrored copy is also deleted from OpenMemory (best-effort:
+    /// a remote failure is reported in the output but does not undo the local deletion).
+    EpisodeDelete {
+        #[arg(long)]
+        repo_root: PathBuf,
+
+        #[arg(long)]
+        episode_id: String,
+
+        /// Why the episode is being removed (recorded in the tombstone and the audit event).
+        #[arg(long)]
+        reason: String,
+
+        #[arg(long)]
+        audit_log: PathBuf,
+
+        #[arg(long)]
+        ts: Option<f64>,
+
+        /// Also delete the mirrored memory from OpenMemory, if one was recorded.
+        #[arg(long)]
+        unmirror: bool,
+
+        /// OpenMemory base URL (only used with --unmirror).
+        #[arg(long, default_value = "http://127.0.0.1:8080")]
+        base_url: String,
+
+        /// Optional OpenMemory API key. If omitted, reads OPENMEMORY_API_KEY env var.
+        #[arg(long)]
+        api_key: Option<String>,
+
+        #[arg(long, default_value_t = 2000)]
+        timeout_ms: u64,
+    },
+
+    /// Mirror a locally-stored episode into OpenMemory (best-effort, non-authoritative).
+    ///
+    /// This does NOT affect deterministic replay. It only emits audit events describing the attempt/result.
+    EpisodeMirror {
+        #[arg(long)]
+        repo_root: PathBuf,
+
+        #[arg(long)]
+        episode_id: String,
+
+        #[arg(long)]
+        audit_log: PathBuf,
+
+        /// OpenMemory base URL (default matches local backend dev server).
+        #[arg(long, default_value = "http://127.0.0.1:8080")]
+        base_url: String,
+
+        /// Optional OpenMemory API key. If omitted, reads OPENMEMORY_API_KEY env var.
+        #[arg(long)]
+        api_key: Option<String>,
+
+        /// Optional OpenMemory user_id (for multi-user isolation). Defaults to thread_id if omitted.
+        #[arg(long)]
+        user_id: Option<String>,
+
+        #[arg(long, default_value_t = 2000)]
+        timeout_ms: u64,
+
+        #[arg(long)]
+        ts: Option<f64>,
+    },
+    /// Mirror every live (non-deleted) episode in the store into OpenMemory, e.g. to backfill a
+    /// new instance. Emits the same audit events as episode-mirror per episode, then prints a summary.
+    EpisodeMirrorAll {
+        #[arg(long)]
+        repo_root: PathBuf,
+
+        #[arg(long)]
+        audit_log: PathBuf,
+
+        /// OpenMemory base URL (default matches local backend dev server).
+        #[arg(long, default_value = "http://127.0.0.1:8080")]
+        base_url: String,
+
+        /// Optional OpenMemory API key. If omitted, reads OPENMEMORY_API_KEY env var.
+        #[arg(long)]
+        api_key: Option<String>,
+
+        /// Optional OpenMemory user_id. Defaults to each episode's thread_id if omitted.
+        #[arg(long)]
+        user_id: Option<String>,
+
+        #[arg(long, default_value_t = 2000)]
+        timeout_ms: u64,
+
+        #[arg(long)]
+        ts: Option<f64>,
+
+        /// Also mirror episodes that have been superseded by a newer version.
+        #[arg(long)]
+        include_superseded: bool,
+    },
+
+    /// Query OpenMemory (/memory/query) and return reference-only results (no raw content).
+    EpisodeQueryRemote {
+        #[arg(long)]
+        repo_root: std::path::PathBuf,
+
+        /// Query text (will be hashed in audit; not stored verbatim).
+        #[arg(long)]
+        query: String,
+
+        /// Top-K results.
+        #[arg(long, default_value_t = 5)]
+        k: u32,
+
+        /// Skip this many results (page through results beyond the top-K).
+        #[arg(long)]
+        offset: Option<u32>,
+
+        /// Optional OpenMemory user_id filter.
+        #[arg(long)]
+        user_id: Option<String>,
+
+        /// Optional minimum similarity score (0-1). Server may ignore depending on deployment.
+        #[arg(long)]
+        min_score: Option<f64>,
+
+        /// Base URL of OpenMemory backend.
+        #[arg(long, default_value = "http://127.0.0.1:8080")]
+        base_url: String,
+
+        /// Audit log path.
+        #[arg(long)]
+        audit_log: std::path::PathBuf,
+
+        /// Optional run_id for audit (defaults to run_demo).
+        #[arg(long, default_value = "run_demo")]
+        run_id: String,
+
+        /// Optional tick_id for audit (defaults to 0).
+        #[arg(long, default_value_t = 0)]
+        tick_id: u64,
+
+        /// Timestamp for audit events.
+        #[arg(long)]
+        ts: Option<f64>,
+
+        /// Request timeout in ms.
+        #[arg(long, default_value_t = 10_000)]
+        timeout_ms: u64,
+    },
+
+    /// Check that the OpenMemory backend is reachable and accepts our credentials (GET /health).
+    /// Prints the status as JSON; exits non-zero when unreachable, rejected, or unhealthy.
+    OpenmemoryHealth {
+        /// OpenMemory base URL (default matches local backend dev server).
+        #[arg(long, default_value = "http://127.0.0.1:8080")]
+        base_url: String,
+
+        /// Optional OpenMemory API key. If omitted, reads OPENMEMORY_API_KEY env var.
+        #[arg(long)]
+        api_key: Option<String>,
+
+        #[arg(long, default_value_t = 2000)]
+        timeout_ms: u64,
+    },
+}
+
+#[tokio::main]
+async fn main() {
+    if let Err(e) = run().await {
+        match &e {
+            CliError::Usage(usage) => {
+                let _ = usage.print();
+            }
+            other => eprintln!("ERROR: {other}"),
+        }
+        std::process::exit(e.exit_code());
+    }
+}
+
+async fn run() -> Result<(), CliError> {
+    let argv: Vec<std::ffi::OsString> = std::env::args_os().collect();
+    let cfg = config::CliConfig::discover(&argv)?;
+    // Ranks below the selected provider's own base-URL env var, so it is resolved per provider in
+    // `provider_endpoint` rather than appended to argv by `apply`.
+    let config_base_url = cfg.as_ref().and_then(|c| c.base_url.clone());
+    let config_base_url = config_base_url.as_deref();
+    let argv = match &cfg {
+        Some(cfg) => cfg.apply(argv),
+        None => argv,
+    };
+    let args = match Args::try_parse_from(argv) {
+        Ok(args) => args,
+        // --help / --version
+        Err(e) if !e.use_stderr() => e.exit(),
+        Err(e) => return Err(CliError::Usage(e)),
+    };
+    init_logging(args.verbose, args.quiet);
+    let format = args.format;
+    let clock = Clock { deterministic: args.deterministic_ts };
+    match args.cmd {
+        Command::VerifyAudit { audit_log } => {
+            let last = verify_log(audit_log)?;
+            println!("{last}");
+            Ok(())
+        }
+        Command::AuditReport { audit_log } => {
+            let report = verify_log_report(audit_log)?;
+            emit(&report, format)?;
+            Ok(())
+        }
+        Command::AuditWatch { audit_log, poll_ms, max_records } => {
+            let mut follower = AuditFollower::new(audit_log);
+            let mut seen = 0u64;
+            loop {
+                for (line, rec) in follower.poll()? {
+                    let short = rec.hash.get(..19).unwrap_or(&rec.hash);
+                    emit(&json!({
+                        "line": line,
+                        "event_type": rec.event.event_type(),
+                        "run_id": rec.event.run_id().0,
+                        "tick_id": rec.event.tick_id().0,
+                        "ts": rec.event.ts(),
+                        "hash": short,
+                    }), format)?;
+                    seen += 1;
+                    if max_records.is_some_and(|m| seen >= m) {
+                        return Ok(());
+                    }
+                }
+                tokio::time::sleep(std::time::Duration::from_millis(poll_ms)).await;
+            }
+        }
+        Command::Schema { kind } => {
+            let schema = match kind {
+                SchemaType::ModelRequest => schemars::schema_for!(ModelRequest),
+                SchemaType::Sanitized => schemars::schema_for!(SanitizedModelRequest),
+                SchemaType::EpisodeAppend => schemars::schema_for!(EpisodeAppendRequest),
+                SchemaType::AuditEvent => schemars::schema_for!(spec::AuditEvent),
+            };
+            emit(&schema, format)?;
+            Ok(())
+        }
+        Command::Completions { shell } => {
+            clap_complete::generate(shell, &mut Args::command(), "pie-control", &mut std::io::stdout());
+            Ok(())
+        }
+        Command::Hash { file, algo, raw } => {
+            let bytes = read_input(&file)?;
+            let bytes = if raw {
+                bytes
+            } else {
+                pie_common::canonical_json_bytes(&serde_json::from_slice::<JsonValue>(&bytes)?)?
+            };
+            let hash = match algo {
+                HashAlgo::Sha256 => sha256_bytes(&bytes),
+                HashAlgo::Blake3 => pie_common::blake3_bytes(&bytes),
+            };
+            println!("{hash}");
+            Ok(())
+        }
+        Command::EndpointFingerprint { provider, base_url, model } => {
+            println!("{}", pie_common::endpoint_fingerprint(&provider, &base_url, &model));
+            Ok(())
+        }
+        Command::ProviderModels { base_url, api_key, timeout_ms, model } => {
+            let (base_url, api_key) = provider_endpoint("openai", base_url, config_base_url, api_key);
+            let opts = ProviderOptions { timeout_ms, ..Default::default() };
+            let models = OpenAICompatProvider::with_options(base_url, api_key, &opts)?.list_models().await?;
+            let Some(model) = model else {
+                emit(&json!({ "models": models }), format)?;
+                return Ok(());
+            };
+            let found = models.contains(&model);
+            emit(&json!({ "models": models, "found": found }), format)?;
+            if !found {
+                return Err(CliError::Invalid(1));
+            }
+            Ok(())
+        }
+        Command::Validate { kind, file } => {
+            let bytes = read_input(&file)?;
+            let (name, parsed) = match kind {
+                RequestKind::ModelRequest => {
+                    ("model_request", serde_json::from_slice::<ModelRequest>(&bytes).map(|r| r.validate()))
+                }
+                RequestKind::Sanitized => {
+                    ("sanitized", serde_json::from_slice::<SanitizedModelRequest>(&bytes).map(|r| r.validate()))
+                }
+            };
+            let errors = parsed.unwrap_or_else(|e| {
+                vec![pie_redaction::ValidationIssue { path: "$".into(), message: e.to_string() }]
+            });
+            emit(&json!({
+                "kind": name,
+                "ok": errors.is_empty(),
+                "errors": errors,
+            }), format)?;
+            if !errors.is_empty() {
+                return Err(CliError::Invalid(errors.len()));
+            }
+            Ok(())
+        }
+        Command::CallDiff { left, right } => {
+            emit(&diff_calls(&left, &right)?, format)?;
+            Ok(())
+        }
+        Command::Replay { repo_root, call_dir, audit_log } => {
+            let call_dir = if call_dir.is_relative() { repo_root.join(call_dir) } else { call_dir };
+            let report = replay_call(&call_dir, &audit_log)?;
+            emit(&report, format)?;
+
+            let failed = report["artifacts"]
+                .as_array()
+                .map(|a| a.iter().filter(|r| r["ok"] != true).count())
+                .unwrap_or(0);
+            if failed > 0 {
+                return Err(CliError::ReplayMismatch(failed));
+            }
             Ok(())
         }
         Command::RedactOnly {
@@ -357,40 +1214,20 @@ async fn run() -> Result<(), CliError> {
             ts_prepared,
             ts_redacted,
         } => {
+            let ts_prepared = clock.ts(ts_prepared);
+            let ts_redacted = clock.ts(ts_redacted);
 
-            // Load .env from repo root or CWD (best-effort, but visible)
-            let repo_env = repo_root.join(".env");
-            if repo_env.exists() {
-                let _ = dotenv_from_path(&repo_env);
-                eprintln!("loaded env from {}", repo_env.display());
-            } else if Path::new(".env").exists() {
-                let _ = dotenv_from_path(".env");
-                eprintln!("loaded env from ./.env");
-            } else {
-                eprintln!("no .env file found (expected at {} or CWD)", repo_env.display());
-            }
+            // Load .env from repo root or CWD (best-effort; reported at debug level)
+            load_repo_env(&repo_root);
 
             ensure_runtime_dirs(&repo_root)?;
 
-            let bytes = fs::read(&request_json)?;
+            let bytes = read_input(&request_json)?;
             let req: ModelRequest = serde_json::from_slice(&bytes)?;
 
             let mut audit = AuditAppender::open(&audit_log)?;
 
-            let prof = match profile.as_str() {
-                "strict" => RedactionProfile::Strict,
-                "explicit_allowlist" => RedactionProfile::ExplicitAllowlist(
-                    // Keep empty for now (refs-only boundary). Expand later if needed.
-                    pie_redaction::RedactionAllowlist { context_paths: vec![] },
-                ),
-                other => {
-                    return Err(CliError::Redaction(pie_redaction::RedactionError::InvalidAllowlist(
-                        format!("unknown profile: {other}"),
-                    )))
-                }
-            };
-
-            let engine = RedactionEngine::new(policy_id, prof, summary_budget_chars);
+            let engine = RedactionEngine::new(policy_id, parse_profile(&profile)?, summary_budget_chars);
 
             let result = engine.redact_and_audit(
                 &repo_root,
@@ -403,28 +1240,21 @@ async fn run() -> Result<(), CliError> {
             )?;
 
             // Print useful outputs for scripting
-            println!(
-                "{{\"call_id\":\"{}\",\"pre_hash\":\"{}\",\"post_hash\":\"{}\",\"transform_log_hash\":\"{}\"}}",
-                result.call_id,
-                result.artifacts.pre_request_hash,
-                result.artifacts.post_request_hash,
-                result.artifacts.transform_log_hash
-            );
+            emit(&json!({
+                "call_id": result.call_id.to_string(),
+                "pre_hash": result.artifacts.pre_request_hash,
+                "post_hash": result.artifacts.post_request_hash,
+                "transform_log_hash": result.artifacts.transform_log_hash,
+            }), format)?;
             Ok(())
         }
 
         Command::EpisodeAppend { repo_root, request_json, audit_log, ts } => {
+            let ts = clock.ts(ts);
             // Load repo_root/.env if present (local-only secrets; not required for episodes but keeps behavior consistent)
-            let repo_env = repo_root.join(".env");
-            if repo_env.exists() {
-                let _ = dotenv_from_path(&repo_env);
-                eprintln!("loaded env from {}", repo_env.display());
-            } else if Path::new(".env").exists() {
-                let _ = dotenv_from_path(".env");
-                eprintln!("loaded env from ./.env");
-            }
+            load_repo_env(&repo_root);
 
-            let bytes = fs::read(&request_json)?;
+            let bytes = read_input(&request_json)?;
             let req: EpisodeAppendRequest = serde_json::from_slice(&bytes)?;
             if req.schema_version != 1 {
                 return Err(CliError::Episodes(episodes::EpisodeError::Corrupt(format!(
@@ -452,7 +1282,7 @@ async fn run() -> Result<(), CliError> {
 
             // Append to authoritative store
             let store = episodes::EpisodeStore::new(repo_root.clone());
-            store.append(&ep)?;
+            let ep = store.append(&ep)?;
 
             // Emit audit event
             let mut audit = AuditAppender::open(&audit_log)?;
@@ -470,17 +1300,22 @@ async fn run() -> Result<(), CliError> {
             });
             audit.append(evt)?;
 
-            println!(
-                "{{\"episode_id\":\"{}\",\"episode_hash\":\"{}\"}}",
-                ep.episode_id, ep.hash
-            );
+            emit(&json!({ "episode_id": ep.episode_id.to_string(), "episode_hash": ep.hash }), format)?;
             Ok(())
         }      
         
-        Command::EpisodeQuery { repo_root, thread_id, tags, since_tick, limit } => {
+        Command::EpisodeQuery { repo_root, thread_id, run_id, tags, tags_any, since_tick, limit, all, order, include_superseded } => {
             let store = episodes::EpisodeStore::new(repo_root);
             let since = since_tick.map(episodes::TickId);
-            let results = store.query(thread_id.as_deref(), &tags, since, limit)?;
+            let filter = episodes::EpisodeFilter {
+                thread_id: thread_id.as_deref(),
+                run_id: run_id.as_deref(),
+                tags_all: &tags,
+                tags_any: &tags_any,
+                since_tick: since,
+                include_superseded,
+            };
+            let results = store.query(&filter, if all { None } else { Some(limit) }, order)?;
 
             // Print stable JSON array (no pretty print; callers can jq if needed).
             // Fields chosen match EpisodeIndexEntry.
@@ -499,7 +1334,7 @@ async fn run() -> Result<(), CliError> {
                 })
                 .collect::<Vec<_>>();
 
-            println!("{}", serde_json::to_string(&out)?);
+            emit(&out, format)?;
             Ok(())
         }
 
@@ -520,139 +1355,278 @@ async fn run() -> Result<(), CliError> {
 
             // Print full episode JSON as stored (includes hash).
             // No pretty print; deterministic pipelines can hash canonical bytes separately.
-            println!("{}", serde_json::to_string(&ep)?);
+            emit(&ep, format)?;
             Ok(())
         }        
 
+        Command::EpisodeStats { repo_root } => {
+            let store = episodes::EpisodeStore::new(repo_root);
+            emit(&store.stats()?, format)?;
+            Ok(())
+        }
+
+        Command::EpisodeReindex { repo_root } => {
+            let store = episodes::EpisodeStore::new(repo_root);
+            emit(&json!({ "entries": store.rebuild_index()? }), format)?;
+            Ok(())
+        }
+
+        Command::EpisodeImport { repo_root, bundle, force } => {
+            let store = episodes::EpisodeStore::new(repo_root);
+            let imported = store.import(&bundle, force)?;
+            let index_entries = store.load_index()?.entries.len();
+            emit(&json!({ "imported": imported, "index_entries": index_entries }), format)?;
+            Ok(())
+        }
+
+        Command::EpisodeVerify { repo_root } => {
+            let store = episodes::EpisodeStore::new(repo_root);
+            let report = store.verify_store()?;
+            emit(&report, format)?;
+            match report.first_discrepancy {
+                None => Ok(()),
+                Some(d) => Err(CliError::Episodes(episodes::EpisodeError::Corrupt(format!(
+                    "store verification failed at line {}: {}",
+                    d.line_no, d.reason
+                )))),
+            }
+        }
+
         Command::DispatchDir {
             repo_root,
             call_dir,
             audit_log,
             base_url,
             api_key,
+            timeout_ms,
+            deadline_ms,
+            max_retries,
+            retry_backoff_ms,
+            price_input_per_1k,
+            price_output_per_1k,
+            prices_file,
+            cache,
+            dry_run,
+            provider,
+            force_provider,
             ts_dispatched,
             ts_completed,
         } => {
 
-            // Load .env from repo root or CWD (best-effort, but visible)
-            let repo_env = repo_root.join(".env");
-            if repo_env.exists() {
-                let _ = dotenv_from_path(&repo_env);
-                eprintln!("loaded env from {}", repo_env.display());
-            } else if Path::new(".env").exists() {
-                let _ = dotenv_from_path(".env");
-                eprintln!("loaded env from ./.env");
-            } else {
-                eprintln!("no .env file found (expected at {} or CWD)", repo_env.display());
-            }
+            // Load .env from repo root or CWD (best-effort; reported at debug level)
+            load_repo_env(&repo_root);
 
             ensure_runtime_dirs(&repo_root)?;
-            let manifest_path = call_dir.join("call_manifest.json");
-            let post_path = call_dir.join("request_post.json");
-
-            let mbytes = fs::read(&manifest_path)?;
-            let manifest: CallManifest = serde_json::from_slice(&mbytes)?;
-
-            // Delegate to Dispatch logic by reusing the same code path:
-            // We just reconstruct args inline.
-            let base_url = base_url
-                .or_else(|| std::env::var("OPENAI_BASE_URL").ok())
-                .unwrap_or_else(|| "https://api.openai.com".to_string());
-            let api_key = api_key.or_else(|| std::env::var("OPENAI_API_KEY").ok());
-
-            // Helpful guardrail: if you're pointing at OpenAI and no API key is set, fail loudly.
-            if api_key.as_deref().unwrap_or("").is_empty()
-                && base_url.contains("api.openai.com")
-            {
-                return Err(CliError::Provider(pie_providers::ProviderError::InvalidResponse(
-                    "OPENAI_API_KEY is required for https://api.openai.com (set it in .env or env var)".into(),
-                )));
-            }
-
-            let bytes = fs::read(&post_path)?;
-            let req: SanitizedModelRequest = serde_json::from_slice(&bytes)?;
+            let (manifest, req, call_uuid) = load_call_dir(&call_dir)?;
+
+            // Resolve the provider before emitting anything so unknown providers leave no dangling event
+            let opts = ProviderOptions { timeout_ms, ..Default::default() };
+            let selected = select_provider(
+                &req.provider,
+                provider,
+                force_provider,
+                base_url,
+                config_base_url,
+                api_key,
+                &opts,
+                dry_run,
+            )?;
+            let price = resolve_price(&req.model.0, price_input_per_1k, price_output_per_1k, prices_file.as_deref())?;
 
-            let call_uuid = Uuid::parse_str(&manifest.call_id)
-                .map_err(|_| CliError::Provider(pie_providers::ProviderError::InvalidResponse("invalid call_id in manifest".into())))?;
+            let mut audit = AuditAppender::open(&audit_log)?;
+            let out = dispatch_audited(
+                &mut audit,
+                &selected,
+                &req,
+                call_uuid,
+                &call_dir,
+                DispatchMode {
+                    stream: false,
+                    deadline_ms,
+                    retry: RetryPolicy { max_retries, backoff_ms: retry_backoff_ms },
+                    price,
+                    cache_dir: cache.then(|| response_cache_dir(&repo_root)),
+                },
+                (clock, ts_dispatched, ts_completed),
+            )
+            .await?;
+
+            emit(&json!({
+                "call_id": manifest.call_id,
+                "status": format!("{:?}", out.status),
+                "latency_ms": out.latency_ms,
+                "attempts": out.attempts,
+                "response_hash": out.response_hash,
+                "usage": out.usage,
+                "estimated_cost_usd": out.estimated_cost_usd,
+                "cache_hit": out.cache_hit,
+            }), format)?;
+            Ok(())
+        }
+        Command::DispatchResume { repo_root, audit_log, base_url, api_key, timeout_ms, deadline_ms, dry_run } => {
+            load_repo_env(&repo_root);
+            let opts = ProviderOptions { timeout_ms, ..Default::default() };
 
-            // Emit dispatched
             let mut audit = AuditAppender::open(&audit_log)?;
-            let endpoint_fp = sha256_bytes(format!("provider:{}|base_url:{}|model:{}", req.provider.0, base_url, req.model.0).as_bytes());
-            let dispatched = spec::AuditEvent::ModelCallDispatched(spec::ModelCallDispatched {
-                schema_version: 1,
-                run_id: spec::RunId(req.run_id.0.clone()),
-                tick_id: spec::TickId(req.tick_id.0),
-                ts: ts_dispatched,
-                model_call: spec::CallId(call_uuid),
-                provider: req.provider.0.clone(),
-                model: req.model.0.clone(),
-                endpoint_fingerprint: endpoint_fp.clone(),
-                request_post_hash: req.integrity.post_hash.clone(),
-            });
-            audit.append(dispatched)?;
-
-            let provider = OpenAICompatProvider::new(base_url.clone(), api_key.clone());
-            let start = Instant::now();
-            let resp = provider.dispatch(&req).await;
-            let latency_ms = start.elapsed().as_millis() as u64;
-
-            let artifacts_dir = call_dir.clone();
-            let (status, provider_request_id_hash, response_hash, response_size, _raw_path, norm_path) = match resp {
-                Ok(ok) => {
-                    let raw_path = artifacts_dir.join("response_raw.json");
-                    let raw_bytes = pie_common::canonical_json_bytes(&ok.raw_json)?;
-                    fs::write(&raw_path, &raw_bytes)?;
-                    let response_hash = sha256_bytes(&raw_bytes);
-
-                    let norm_path = artifacts_dir.join("reply_normalized.json");
-                    let norm_bytes = pie_common::canonical_json_bytes(&ok.normalized)?;
-                    fs::write(&norm_path, &norm_bytes)?;
-
-                    let pid_hash = sha256_bytes(ok.normalized.provider_request_id.unwrap_or_default().as_bytes());
-                    (spec::CallStatus::Ok, pid_hash, response_hash, raw_bytes.len() as u64, raw_path, norm_path)
+            let mut resumed = Vec::new();
+            for call in unfinished_calls(&audit_log)? {
+                let call_dir =
+                    pie_redaction::models_artifact_dir(&repo_root, &pie_redaction::RunId(call.run_id), &call.call_uuid);
+                let previous = call.last_status.map(|s| format!("{s:?}"));
+                if !call_dir.join("call_manifest.json").exists() {
+                    resumed.push(json!({
+                        "call_id": call.call_uuid.to_string(),
+                        "previous_status": previous,
+                        "skipped": "call directory not found",
+                    }));
+                    continue;
                 }
-                Err(e) => {
-                    let raw_path = artifacts_dir.join("response_raw.json");
-                    let err_obj = serde_json::json!({"error": format!("{e}")});
-                    let raw_bytes = pie_common::canonical_json_bytes(&err_obj)?;
-                    fs::write(&raw_path, &raw_bytes)?;
-                    let response_hash = sha256_bytes(&raw_bytes);
-
-                    let norm_path = artifacts_dir.join("reply_normalized.json");
-                    let placeholder = serde_json::json!({"content":"", "finish_reason":"error", "usage":{"input_tokens":null,"output_tokens":null}, "provider_request_id": null});
-                    let norm_bytes = pie_common::canonical_json_bytes(&placeholder)?;
-                    fs::write(&norm_path, &norm_bytes)?;
-
-                    let pid_hash = sha256_bytes(b"");
-                    (spec::CallStatus::Error, pid_hash, response_hash, raw_bytes.len() as u64, raw_path, norm_path)
-                }
-            };
+                let (_, req, call_uuid) = load_call_dir(&call_dir)?;
+                let choice = <ProviderChoice as clap::ValueEnum>::from_str(&call.provider, false)
+                    .map_err(|_| CliError::Provider(pie_providers::ProviderError::UnknownProvider(call.provider.clone())))?;
+                let selected = select_provider(
+                    &req.provider,
+                    choice,
+                    true,
+                    base_url.clone(),
+                    config_base_url,
+                    api_key.clone(),
+                    &opts,
+                    dry_run,
+                )?;
+
+                audit.append(spec::AuditEvent::ModelCallRetried(spec::ModelCallRetried {
+                    schema_version: 1,
+                    run_id: spec::RunId(req.run_id.0.clone()),
+                    tick_id: spec::TickId(req.tick_id.0),
+                    ts: clock.ts(None),
+                    model_call: spec::CallId(call_uuid),
+                    attempt: call.attempts,
+                    status: call.last_status.unwrap_or(spec::CallStatus::Error),
+                    wait_ms: 0,
+                }))?;
+                let out = dispatch_audited(
+                    &mut audit,
+                    &selected,
+                    &req,
+                    call_uuid,
+                    &call_dir,
+                    DispatchMode {
+                        stream: false,
+                        deadline_ms,
+                        retry: RetryPolicy { max_retries: 0, backoff_ms: 0 },
+                        price: None,
+                        cache_dir: None,
+                    },
+                    (clock, None, None),
+                )
+                .await?;
+                resumed.push(json!({
+                    "call_id": call_uuid.to_string(),
+                    "previous_status": previous,
+                    "status": format!("{:?}", out.status),
+                    "response_hash": out.response_hash,
+                }));
+            }
+            emit(&json!({ "resumed": resumed }), format)?;
+            Ok(())
+        }
+        Command::RunCall {
+            repo_root,
+            request_json,
+            audit_log,
+            policy_decision_id,
+            requires_approval,
+            policy_id,
+            profile,
+            summary_budget_chars,
+            base_url,
+            api_key,
+            timeout_ms,
+            deadline_ms,
+            max_retries,
+            retry_backoff_ms,
+            price_input_per_1k,
+            price_output_per_1k,
+            prices_file,
+            cache,
+            dry_run,
+            provider,
+            force_provider,
+            ts_prepared,
+            ts_redacted,
+            ts_dispatched,
+            ts_completed,
+        } => {
+            load_repo_env(&repo_root);
+            ensure_runtime_dirs(&repo_root)?;
+
+            let req: ModelRequest = serde_json::from_slice(&read_input(&request_json)?)?;
+            let engine = RedactionEngine::new(policy_id, parse_profile(&profile)?, summary_budget_chars);
+
+            // Resolve the provider before emitting anything so unknown providers leave no dangling event
+            let opts = ProviderOptions { timeout_ms, ..Default::default() };
+            let selected = select_provider(
+                &req.provider,
+                provider,
+                force_provider,
+                base_url,
+                config_base_url,
+                api_key,
+                &opts,
+                dry_run,
+            )?;
+            let price = resolve_price(&req.model.0, price_input_per_1k, price_output_per_1k, prices_file.as_deref())?;
+
+            let mut audit = AuditAppender::open(&audit_log)?;
+            let result = engine.redact_and_audit(
+                &repo_root,
+                &mut audit,
+                &req,
+                policy_decision_id,
+                requires_approval,
+                clock.ts(ts_prepared),
+                clock.ts(ts_redacted),
+            )?;
 
-            let norm_hash = sha256_bytes(fs::read(&norm_path)?.as_slice());
-            let completed = spec::AuditEvent::ModelCallCompleted(spec::ModelCallCompleted {
-                schema_version: 1,
-                run_id: spec::RunId(req.run_id.0.clone()),
-                tick_id: spec::TickId(req.tick_id.0),
-                ts: ts_completed,
-                model_call: spec::CallId(call_uuid),
-                result: spec::ModelCallResult {
-                    status,
-                    latency_ms,
-                    provider_request_id_hash,
-                    response_hash: response_hash.clone(),
-                    response_size_bytes: response_size,
-                },
-                artifacts: spec::CompletionArtifacts {
-                    response_artifact: spec::ArtifactRef { r#type: "artifact_ref".into(), hash: response_hash.clone() },
-                    normalized_reply_artifact: spec::ArtifactRef { r#type: "artifact_ref".into(), hash: norm_hash },
-                },
-            });
-            audit.append(completed)?;
+            // Dispatch exactly what was written to request_post.json, as dispatch-dir would.
+            let post_path = &result.artifacts.post_request_path;
+            let sanitized: SanitizedModelRequest = serde_json::from_slice(&fs::read(post_path)?)?;
+            verify_integrity(&sanitized)?;
+            let call_dir = post_path
+                .parent()
+                .ok_or_else(|| CliError::Input("request_post.json has no parent".into()))?;
 
-            println!(
-                "{{\"call_id\":\"{}\",\"status\":\"{:?}\",\"latency_ms\":{},\"response_hash\":\"{}\"}}",
-                manifest.call_id, status, latency_ms, response_hash
-            );
+            let out = dispatch_audited(
+                &mut audit,
+                &selected,
+                &sanitized,
+                result.call_id,
+                call_dir,
+                DispatchMode {
+                    stream: false,
+                    deadline_ms,
+                    retry: RetryPolicy { max_retries, backoff_ms: retry_backoff_ms },
+                    price,
+                    cache_dir: cache.then(|| response_cache_dir(&repo_root)),
+                },
+                (clock, ts_dispatched, ts_completed),
+            )
+            .await?;
+
+            emit(&json!({
+                "call_id": result.call_id.to_string(),
+                "pre_hash": result.artifacts.pre_request_hash,
+                "post_hash": result.artifacts.post_request_hash,
+                "transform_log_hash": result.artifacts.transform_log_hash,
+                "status": format!("{:?}", out.status),
+                "latency_ms": out.latency_ms,
+                "attempts": out.attempts,
+                "response_hash": out.response_hash,
+                "usage": out.usage,
+                "estimated_cost_usd": out.estimated_cost_usd,
+                "cache_hit": out.cache_hit,
+            }), format)?;
             Ok(())
         }
         Command::Dispatch {
@@ -662,132 +1636,146 @@ async fn run() -> Result<(), CliError> {
             base_url,
             api_key,
             call_id,
+            timeout_ms,
+            deadline_ms,
+            max_retries,
+            retry_backoff_ms,
+            price_input_per_1k,
+            price_output_per_1k,
+            prices_file,
+            cache,
+            dry_run,
+            provider,
+            force_provider,
+            stream,
             ts_dispatched,
             ts_completed,
         } => {
             ensure_runtime_dirs(&repo_root)?;
 
-            let base_url = base_url
-                .or_else(|| std::env::var("OPENAI_BASE_URL").ok())
-                .unwrap_or_else(|| "https://api.openai.com".to_string());
-            let api_key = api_key.or_else(|| std::env::var("OPENAI_API_KEY").ok());
-
-
             let bytes = fs::read(&sanitized_json)?;
             let req: SanitizedModelRequest = serde_json::from_slice(&bytes)?;
 
             // Defensive: ensure integrity hashes exist (should have been set during redaction)
             if !req.integrity.pre_hash.starts_with("sha256:") || !req.integrity.post_hash.starts_with("sha256:") {
-                return Err(CliError::Provider(pie_providers::ProviderError::InvalidResponse(
-                    "sanitized request missing integrity hashes".into(),
-                )));
+                return Err(CliError::Input("sanitized request missing integrity hashes".into()));
             }
+            verify_integrity(&req)?;
 
             let call_uuid = Uuid::parse_str(&call_id)
-                .map_err(|_| CliError::Provider(pie_providers::ProviderError::InvalidResponse("invalid call_id".into())))?;
-
-
-            // Emit ModelCallDispatched
-            let mut audit = AuditAppender::open(&audit_log)?;
-            let endpoint_fp = sha256_bytes(format!("provider:{}|base_url:{}|model:{}", req.provider.0, base_url, req.model.0).as_bytes());
- 
-            let dispatched = spec::AuditEvent::ModelCallDispatched(spec::ModelCallDispatched {
-                schema_version: 1,
-                run_id: spec::RunId(req.run_id.0.clone()),
-                tick_id: spec::TickId(req.tick_id.0),
-                ts: ts_dispatched,
-                model_call: spec::CallId(call_uuid),
-                provider: req.provider.0.clone(),
-                model: req.model.0.clone(),
-                endpoint_fingerprint: endpoint_fp.clone(),
-                request_post_hash: req.integrity.post_hash.clone(),
-            });
-            audit.append(dispatched)?;
-
-
-            // Dispatch via provider (OpenAI-compatible for Stage 6B baseline)
-            let provider = OpenAICompatProvider::new(base_url.clone(), api_key.clone());
-            let start = Instant::now();
-            let resp = provider.dispatch(&req).await;
-            let latency_ms = start.elapsed().as_millis() as u64;
+                .map_err(|_| CliError::Input(format!("invalid call_id: {call_id}")))?;
+
+
+            // Resolve the provider before emitting anything so unknown providers leave no dangling event
+            let opts = ProviderOptions { timeout_ms, ..Default::default() };
+            let selected = select_provider(
+                &req.provider,
+                provider,
+                force_provider,
+                base_url,
+                config_base_url,
+                api_key,
+                &opts,
+                dry_run,
+            )?;
+            let price = resolve_price(&req.model.0, price_input_per_1k, price_output_per_1k, prices_file.as_deref())?;
 
-            // Determine artifacts dir (same folder as request_post.json)
+            // Artifacts land next to request_post.json
             let artifacts_dir = sanitized_json
                 .parent()
-                .ok_or_else(|| CliError::Provider(pie_providers::ProviderError::InvalidResponse("sanitized_json has no parent".into())))?
+                .ok_or_else(|| CliError::Input("sanitized_json has no parent".into()))?
                 .to_path_buf();
 
-            // Always store raw response artifact, even on error (as structured object)
-            let (status, provider_request_id_hash, response_hash, response_size, _raw_path, norm_path) = match resp {
-                Ok(ok) => {
-                    let raw_path = artifacts_dir.join("response_raw.json");
-                    let raw_bytes = pie_common::canonical_json_bytes(&ok.raw_json)?;
-                    fs::write(&raw_path, &raw_bytes)?;
-                    let response_hash = sha256_bytes(&raw_bytes);
+            let mut audit = AuditAppender::open(&audit_log)?;
+            let out = dispatch_audited(
+                &mut audit,
+                &selected,
+                &req,
+                call_uuid,
+                &artifacts_dir,
+                DispatchMode {
+                    stream,
+                    deadline_ms,
+                    retry: RetryPolicy { max_retries, backoff_ms: retry_backoff_ms },
+                    price,
+                    cache_dir: cache.then(|| response_cache_dir(&repo_root)),
+                },
+                (clock, ts_dispatched, ts_completed),
+            )
+            .await?;
+
+            emit(&json!({
+                "call_id": call_id,
+                "status": format!("{:?}", out.status),
+                "latency_ms": out.latency_ms,
+                "attempts": out.attempts,
+                "response_hash": out.response_hash,
+                "usage": out.usage,
+                "estimated_cost_usd": out.estimated_cost_usd,
+                "cache_hit": out.cache_hit,
+            }), format)?;
+            Ok(())
+        }
 
-                    let norm_path = artifacts_dir.join("reply_normalized.json");
-                    let norm_bytes = pie_common::canonical_json_bytes(&ok.normalized)?;
-                    fs::write(&norm_path, &norm_bytes)?;
+        Command::EpisodeDelete { repo_root, episode_id, reason, audit_log, ts, unmirror, base_url, api_key, timeout_ms } => {
+            let ts = clock.ts(ts);
+            let store = episodes::EpisodeStore::new(repo_root.clone());
+            let uid = Uuid::parse_str(&episode_id)
+                .map_err(|_| CliError::Episodes(episodes::EpisodeError::Corrupt("invalid episode_id".into())))?;
+            let idx = store.load_index()?;
+            let entry = idx
+                .entries
+                .iter()
+                .find(|e| e.episode_id == uid)
+                .ok_or(CliError::Episodes(episodes::EpisodeError::NotFound(uid)))?;
+            let ep = store.load_episode_by_entry(entry)?;
 
-                    let pid_hash = sha256_bytes(ok.normalized.provider_request_id.unwrap_or_default().as_bytes());
-                    (spec::CallStatus::Ok, pid_hash, response_hash, raw_bytes.len() as u64, raw_path, norm_path)
+            store.delete(uid, &reason, ts)?;
+
+            let mut unmirrored = None;
+            let mut unmirror_error = None;
+            if unmirror {
+                load_repo_env(&repo_root);
+                let state_path = om::MirrorState::path_for(&store);
+                let mut state = om::MirrorState::load(&state_path)?;
+                if let Some(rec) = state.mirrored_to(&ep.hash, "openmemory").cloned() {
+                    let client = om::OpenMemoryClient::new(base_url, resolve_openmemory_key(api_key), timeout_ms)?;
+                    match client.delete_memory(&rec.remote_id).await {
+                        Ok(()) => {
+                            state.forget(&ep.hash);
+                            state.save(&state_path)?;
+                            unmirrored = Some(rec.remote_id);
+                        }
+                        Err(e) => unmirror_error = Some(e.to_string()),
+                    }
                 }
-                Err(e) => {
-                    let raw_path = artifacts_dir.join("response_raw.json");
-                    let err_obj = serde_json::json!({"error": format!("{e}")});
-                    let raw_bytes = pie_common::canonical_json_bytes(&err_obj)?;
-                    fs::write(&raw_path, &raw_bytes)?;
-                    let response_hash = sha256_bytes(&raw_bytes);
-
-                    // normalized reply absent on error; still write placeholder for replay determinism
-                    let norm_path = artifacts_dir.join("reply_normalized.json");
-                    let placeholder = serde_json::json!({"content":"", "finish_reason":"error", "usage":{"input_tokens":null,"output_tokens":null}, "provider_request_id": null});
-                    let norm_bytes = pie_common::canonical_json_bytes(&placeholder)?;
-                    fs::write(&norm_path, &norm_bytes)?;
-
-                    let pid_hash = sha256_bytes(b"");
-                    (spec::CallStatus::Error, pid_hash, response_hash, raw_bytes.len() as u64, raw_path, norm_path)
-                }
-            };
+            }
 
-            // Emit ModelCallCompleted
-            let norm_hash = sha256_bytes(fs::read(&norm_path)?.as_slice());
-            let completed = spec::AuditEvent::ModelCallCompleted(spec::ModelCallCompleted {
+            let mut app = AuditAppender::open(&audit_log)?;
+            app.append(spec::AuditEvent::EpisodeDeleted(spec::EpisodeDeleted {
                 schema_version: 1,
-                run_id: spec::RunId(req.run_id.0.clone()),
-                tick_id: spec::TickId(req.tick_id.0),
-                ts: ts_completed,
-                model_call: spec::CallId(call_uuid),
-                result: spec::ModelCallResult {
-                    status,
-                    latency_ms,
-                    provider_request_id_hash,
-                    response_hash: response_hash.clone(),
-                    response_size_bytes: response_size,
-                },
-                artifacts: spec::CompletionArtifacts {
-                    response_artifact: spec::ArtifactRef { r#type: "artifact_ref".into(), hash: response_hash.clone() },
-                    normalized_reply_artifact: spec::ArtifactRef { r#type: "artifact_ref".into(), hash: norm_hash },
-                },
-            });
-            audit.append(completed)?;
+                run_id: spec::RunId(ep.run_id.0.clone()),
+                tick_id: spec::TickId(ep.tick_id.0),
+                ts,
+                episode_id: uid,
+                episode_hash: ep.hash.clone(),
+                reason,
+                unmirrored_remote_id: unmirrored.clone(),
+            }))?;
 
-            println!(
-                "{{\"call_id\":\"{}\",\"status\":\"{:?}\",\"latency_ms\":{},\"response_hash\":\"{}\"}}",
-                call_id, status, latency_ms, response_hash
-            );
+            emit(&json!({
+                "episode_id": uid.to_string(),
+                "episode_hash": ep.hash,
+                "deleted": true,
+                "unmirrored_remote_id": unmirrored,
+                "unmirror_error": unmirror_error,
+            }), format)?;
             Ok(())
         }
 
-        Command::EpisodeMirror { repo_root, episode_id, audit_log, base_url, api_key, user_id, timeout_ms, ts } => {            // Load .env exactly like other commands (local-only convenience)
-            let repo_env = repo_root.join(".env");
-            if repo_env.exists() {
-                let _ = dotenv_from_path(&repo_env);
-                eprintln!("loaded env from {}", repo_env.display());
-            } else if Path::new(".env").exists() {
-                let _ = dotenv_from_path(".env");
-                eprintln!("loaded env from ./.env");
-            }
+        Command::EpisodeMirror { repo_root, episode_id, audit_log, base_url, api_key, user_id, timeout_ms, ts } => {
+            let ts = clock.ts(ts);
+            load_repo_env(&repo_root);
 
             let store = episodes::EpisodeStore::new(repo_root);
             let idx = store.load_index()?;
@@ -800,118 +1788,96 @@ async fn run() -> Result<(), CliError> {
                 .ok_or_else(|| CliError::Episodes(episodes::EpisodeError::Corrupt("episode_id not found in index".into())))?;
 
             let ep = store.load_episode_by_entry(entry)?;
+            let state_path = om::MirrorState::path_for(&store);
+            let mut state = om::MirrorState::load(&state_path)?;
 
             // Audit appender
             let mut app = AuditAppender::open(&audit_log)?;
+            let client = om::OpenMemoryClient::new(base_url, resolve_openmemory_key(api_key), timeout_ms)?;
 
-            let attempted = spec::AuditEvent::EpisodeMirrorAttempted(spec::EpisodeMirrorAttempted {
-                schema_version: 1,
-                run_id: spec::RunId(ep.run_id.0.clone()),
-                tick_id: spec::TickId(ep.tick_id.0),
-                ts,
-                episode_id: ep.episode_id,
-                episode_hash: ep.hash.clone(),
-                target: "openmemory".to_string(),
-            });
-            app.append(attempted)?;
-
-            // Build OpenMemory request payload.
-            // Content = title + summary (keeps it readable in OpenMemory dashboards).
-            let mut content = String::new();
-            if !ep.title.trim().is_empty() {
-                content.push_str(ep.title.trim());
-                content.push_str("\n\n");
-            }
-            content.push_str(ep.summary.trim());
-
-            // Metadata: keep it tight and explicit.
-            let meta: JsonValue = json!({
-                "source": "pieBot",
-                "episode_id": ep.episode_id,
-                "episode_hash": ep.hash,
-                "run_id": ep.run_id,
-                "tick_id": ep.tick_id,
-                "thread_id": ep.thread_id,
-                "tags": ep.tags,
-                "created_ts": ep.created_ts,
-            });
-
-            // Match local-agent-core behavior: OPENMEMORY_API_KEY or OM_API_KEY
-            let key = api_key.or_else(|| {
-                std::env::var("OPENMEMORY_API_KEY")
-                    .ok()
-                    .or_else(|| std::env::var("OM_API_KEY").ok())
-            });
-
-            // No key? Make it explicit (without leaking secrets).
-            if key.is_none() {
-                eprintln!("openmemory: no api key found (set OPENMEMORY_API_KEY or OM_API_KEY, or pass --api-key)");
-            }
-            let om_user_id = user_id.or_else(|| Some(ep.thread_id.clone()));
+            let result = mirror_episode_audited(&client, &mut app, &mut state, &ep, user_id, ts).await?;
+            state.save(&state_path)?;
+            emit(&result, format)?;
+            Ok(())
+        }
 
-            let client = om::OpenMemoryClient::new(base_url, key, timeout_ms)?;
+        Command::EpisodeMirrorAll {
+            repo_root,
+            audit_log,
+            base_url,
+            api_key,
+            user_id,
+            timeout_ms,
+            ts,
+            include_superseded,
+        } => {
+            let ts = clock.ts(ts);
+            load_repo_env(&repo_root);
 
+            let store = episodes::EpisodeStore::new(repo_root);
+            let excluded: std::collections::BTreeSet<Uuid> = store
+                .load_index()?
+                .entries
+                .iter()
+                .filter(|e| e.deleted || (e.superseded_by.is_some() && !include_superseded))
+                .map(|e| e.episode_id)
+                .collect();
 
-            let req = om::AddMemoryRequest {
-                content,
-                tags: ep.tags.clone(),
-                metadata: Some(meta),
-                user_id: om_user_id,
-            };
+            let state_path = om::MirrorState::path_for(&store);
+            let mut state = om::MirrorState::load(&state_path)?;
 
-            match client.add_memory(&req).await {
-                Ok(resp) => {
-                    let mirrored = spec::AuditEvent::EpisodeMirrored(spec::EpisodeMirrored {
-                        schema_version: 1,
-                        run_id: spec::RunId(ep.run_id.0.clone()),
-                        tick_id: spec::TickId(ep.tick_id.0),
-                        ts,
-                        episode_id: ep.episode_id,
-                        episode_hash: ep.hash.clone(),
-                        target: "openmemory".to_string(),
-                        remote_id: resp.id.clone(),
-                    });
-                    app.append(mirrored)?;
+            let mut app = AuditAppender::open(&audit_log)?;
+            let client = om::OpenMemoryClient::new(base_url, resolve_openmemory_key(api_key), timeout_ms)?;
 
-                    println!("{}", serde_json::to_string(&json!({
-                        "episode_id": ep.episode_id.to_string(),
-                        "episode_hash": ep.hash,
-                        "target": "openmemory",
-                        "remote_id": resp.id,
-                        "primary_sector": resp.primary_sector,
-                        "sectors": resp.sectors
-                    }))?);
-                    Ok(())
+            let (mut mirrored, mut failed, mut skipped) = (0u64, 0u64, 0u64);
+            for ep in store.iter() {
+                let ep = ep?;
+                if excluded.contains(&ep.episode_id) {
+                    continue;
                 }
-                Err(e) => {
-                    let failed = spec::AuditEvent::EpisodeMirrorFailed(spec::EpisodeMirrorFailed {
-                        schema_version: 1,
-                        run_id: spec::RunId(ep.run_id.0.clone()),
-                        tick_id: spec::TickId(ep.tick_id.0),
-                        ts,
-                        episode_id: ep.episode_id,
-                        episode_hash: ep.hash.clone(),
-                        target: "openmemory".to_string(),
-                        error: e.to_string(),
-                    });
-                    app.append(failed)?;
-
-                    println!("{}", serde_json::to_string(&json!({
-                        "episode_id": ep.episode_id.to_string(),
-                        "episode_hash": ep.hash,
-                        "target": "openmemory",
-                        "status": "Error",
-                        "error": e.to_string()
-                    }))?);
-                    Ok(())
+                let result = mirror_episode_audited(&client, &mut app, &mut state, &ep, user_id.clone(), ts).await?;
+                match result.get("status").and_then(|s| s.as_str()) {
+                    Some("Mirrored") => {
+                        mirrored += 1;
+                        // Persist as we go so an interrupted backfill resumes where it stopped.
+                        state.save(&state_path)?;
+                    }
+                    Some("AlreadyMirrored") => skipped += 1,
+                    _ => failed += 1,
                 }
             }
+
+            emit(&json!({
+                "target": "openmemory",
+                "mirrored": mirrored,
+                "failed": failed,
+                "skipped": skipped,
+            }), format)?;
+            Ok(())
         }
         
+        Command::OpenmemoryHealth { base_url, api_key, timeout_ms } => {
+            let client = om::OpenMemoryClient::new(base_url, resolve_openmemory_key(api_key), timeout_ms)?;
+            let health = client.health().await?;
+            emit(&json!({
+                "target": "openmemory",
+                "healthy": health.healthy,
+                "status": health.status,
+            }), format)?;
+            if !health.healthy {
+                return Err(CliError::OpenMemory(om::OpenMemoryError::InvalidResponse(format!(
+                    "openmemory reports unhealthy status {:?}",
+                    health.status
+                ))));
+            }
+            Ok(())
+        }
+
         Command::EpisodeQueryRemote {
             repo_root,
             query,
             k,
+            offset,
             user_id,
             min_score,
             base_url,
@@ -921,15 +1887,8 @@ async fn run() -> Result<(), CliError> {
             ts,
             timeout_ms,
         } => {
-            // Load .env (repo root first, then cwd) exactly like other commands.
-            let repo_env = repo_root.join(".env");
-            if repo_env.exists() {
-                let _ = dotenv_from_path(&repo_env);
-                eprintln!("loaded env from {}", repo_env.display());
-            } else if std::path::Path::new(".env").exists() {
-                let _ = dotenv_from_path(".env");
-                eprintln!("loaded env from ./.env");
-            }
+            let ts = clock.ts(ts);
+            load_repo_env(&repo_root);
 
             // Key resolution matches local-agent-core behavior:
             // OPENMEMORY_API_KEY or OM_API_KEY.
@@ -938,12 +1897,15 @@ async fn run() -> Result<(), CliError> {
                 .or_else(|| std::env::var("OM_API_KEY").ok());
 
             let client = pie_openmemory_mirror::OpenMemoryClient::new(base_url, api_key, timeout_ms)?;
+            // Map hits back to local episodes via the content hashes recorded when mirroring.
+            let mirror_state = om::MirrorState::load(&om::MirrorState::path_for(&episodes::EpisodeStore::new(&repo_root)))?;
 
             let req = pie_openmemory_mirror::QueryMemoryRequest {
                 query: query.clone(),
                 k: Some(k),
                 user_id: user_id.clone(),
                 min_score,
+                offset,
             };
             // Audit appender
             let mut app = AuditAppender::open(&audit_log)?;
@@ -952,7 +1914,7 @@ async fn run() -> Result<(), CliError> {
 
             // Hash query for audit (never store verbatim in log)
             let q_hash = sha256_bytes(query.as_bytes());
-            let q_len = query.as_bytes().len() as u64;
+            let q_len = query.len() as u64;
 
             match client.query_memory(&req).await {
                 Ok(parsed) => {
@@ -993,20 +1955,25 @@ async fn run() -> Result<(), CliError> {
                     });
                     app.append(ev)?;
 
-                    // Print refs only: id + score + content_hash
+                    // Print refs only: id + score + content_hash (+ tags/created_ts/metadata, content stripped)
                     let safe = serde_json::json!({
                         "target": "openmemory",
                         "query_hash": q_hash,
                         "k": k,
+                        "offset": offset,
                         "result_count": parsed.hits.len(),
                         "response_hash": resp_hash,
                         "hits": parsed.hits.iter().map(|h| serde_json::json!({
                             "id": h.id,
                             "score": h.score,
                             "content_hash": h.content_hash,
+                            "tags": h.tags,
+                            "created_ts": h.created_ts,
+                            "metadata": h.metadata,
+                            "local_episode_id": mirror_state.episode_for_content(&h.content_hash),
                         })).collect::<Vec<_>>(),
                     });
-                    println!("{}", serde_json::to_string(&safe)?);
+                    emit(&safe, format)?;
                     Ok(())
                 }
                 Err(e) => {
@@ -1030,7 +1997,7 @@ async fn run() -> Result<(), CliError> {
                         "status": "Error",
                         "error": e.to_string(),
                     });
-                    println!("{}", serde_json::to_string(&out)?);
+                    emit(&out, format)?;
                     Ok(())
                 }
             }
@@ -1039,6 +2006,687 @@ async fn run() -> Result<(), CliError> {
     }
 }
 
+/// Load .env (repo root first, then cwd); local-only convenience shared by the commands that
+/// need secrets. Which file was used is logged at debug level (`-vv`).
+fn load_repo_env(repo_root: &Path) {
+    let repo_env = repo_root.join(".env");
+    if repo_env.exists() {
+        let _ = dotenv_from_path(&repo_env);
+        tracing::debug!("loaded env from {}", repo_env.display());
+    } else if Path::new(".env").exists() {
+        let _ = dotenv_from_path(".env");
+        tracing::debug!("loaded env from ./.env");
+    } else {
+        tracing::debug!("no .env file found (expected at {} or CWD)", repo_env.display());
+    }
+}
+
+/// Match local-agent-core behavior: --api-key, else OPENMEMORY_API_KEY or OM_API_KEY.
+fn resolve_openmemory_key(api_key: Option<String>) -> Option<String> {
+    let key = api_key.or_else(|| {
+        std::env::var("OPENMEMORY_API_KEY")
+            .ok()
+            .or_else(|| std::env::var("OM_API_KEY").ok())
+    });
+
+    // No key? Make it explicit (without leaking secrets).
+    if key.is_none() {
+        tracing::warn!("openmemory: no api key found (set OPENMEMORY_API_KEY or OM_API_KEY, or pass --api-key)");
+    }
+    key
+}
+
+/// Mirror one episode into OpenMemory, recording EpisodeMirrorAttempted and then
+/// EpisodeMirrored or EpisodeMirrorFailed. Remote failures are not errors (mirroring is
+/// best-effort); they are reported in the returned JSON line, whose `status` is `Mirrored`,
+/// `AlreadyMirrored` or `Error`.
+///
+/// Episodes already in `state` are skipped without contacting the server or emitting events;
+/// successful mirrors are recorded in `state` (the caller saves it).
+async fn mirror_episode_audited(
+    client: &om::OpenMemoryClient,
+    app: &mut AuditAppender,
+    state: &mut om::MirrorState,
+    ep: &episodes::Episode,
+    user_id: Option<String>,
+    ts: f64,
+) -> Result<JsonValue, CliError> {
+    if let Some(prev) = state.mirrored_to(&ep.hash, "openmemory") {
+        return Ok(json!({
+            "episode_id": ep.episode_id.to_string(),
+            "episode_hash": ep.hash,
+            "target": "openmemory",
+            "status": "AlreadyMirrored",
+            "remote_id": prev.remote_id,
+        }));
+    }
+
+    let attempted = spec::AuditEvent::EpisodeMirrorAttempted(spec::EpisodeMirrorAttempted {
+        schema_version: 1,
+        run_id: spec::RunId(ep.run_id.0.clone()),
+        tick_id: spec::TickId(ep.tick_id.0),
+        ts,
+        episode_id: ep.episode_id,
+        episode_hash: ep.hash.clone(),
+        target: "openmemory".to_string(),
+    });
+    app.append(attempted)?;
+
+    match client.mirror_episode(ep, user_id).await {
+        Ok(resp) => {
+            let mirrored = spec::AuditEvent::EpisodeMirrored(spec::EpisodeMirrored {
+                schema_version: 1,
+                run_id: spec::RunId(ep.run_id.0.clone()),
+                tick_id: spec::TickId(ep.tick_id.0),
+                ts,
+                episode_id: ep.episode_id,
+                episode_hash: ep.hash.clone(),
+                target: "openmemory".to_string(),
+                remote_id: resp.id.clone(),
+            });
+            app.append(mirrored)?;
+            state.record(ep, "openmemory", &resp.id, ts);
+
+            Ok(json!({
+                "episode_id": ep.episode_id.to_string(),
+                "episode_hash": ep.hash,
+                "target": "openmemory",
+                "status": "Mirrored",
+                "remote_id": resp.id,
+                "primary_sector": resp.primary_sector,
+                "sectors": resp.sectors
+            }))
+        }
+        Err(e) => {
+            let failed = spec::AuditEvent::EpisodeMirrorFailed(spec::EpisodeMirrorFailed {
+                schema_version: 1,
+                run_id: spec::RunId(ep.run_id.0.clone()),
+                tick_id: spec::TickId(ep.tick_id.0),
+                ts,
+                episode_id: ep.episode_id,
+                episode_hash: ep.hash.clone(),
+                target: "openmemory".to_string(),
+                error: e.to_string(),
+            });
+            app.append(failed)?;
+
+            Ok(json!({
+                "episode_id": ep.episode_id.to_string(),
+                "episode_hash": ep.hash,
+                "target": "openmemory",
+                "status": "Error",
+                "error": e.to_string()
+            }))
+        }
+    }
+}
+
+/// Read a redact-only call directory (`call_manifest.json` + `request_post.json`) for dispatch.
+/// The manifest and the request must agree, and the request must still hash to its own post_hash.
+fn load_call_dir(call_dir: &Path) -> Result<(CallManifest, SanitizedModelRequest, Uuid), CliError> {
+    let manifest: CallManifest = serde_json::from_slice(&fs::read(call_dir.join("call_manifest.json"))?)?;
+    let req: SanitizedModelRequest = serde_json::from_slice(&fs::read(call_dir.join("request_post.json"))?)?;
+    if req.integrity.post_hash != manifest.post_hash {
+        return Err(CliError::Provider(pie_providers::ProviderError::IntegrityMismatch {
+            expected: manifest.post_hash.clone(),
+            actual: req.integrity.post_hash.clone(),
+        }));
+    }
+    verify_integrity(&req)?;
+    let call_uuid = Uuid::parse_str(&manifest.call_id)
+        .map_err(|_| CliError::Input("invalid call_id in manifest".into()))?;
+    Ok((manifest, req, call_uuid))
+}
+
+/// A call whose latest attempt in the audit log did not complete Ok.
+struct UnfinishedCall {
+    call_uuid: Uuid,
+    run_id: String,
+    /// Provider recorded by the last ModelCallDispatched.
+    provider: String,
+    /// Provider calls recorded so far: the first attempt plus one per ModelCallRetried. A resume
+    /// writes ModelCallRetried before its ModelCallDispatched, so later dispatches are not counted again.
+    attempts: u32,
+    /// Status of the last ModelCallCompleted, or None if the call never completed.
+    last_status: Option<spec::CallStatus>,
+}
+
+/// Calls with a ModelCallDispatched whose latest ModelCallCompleted is missing or not Ok, in
+/// first-dispatch order.
+fn unfinished_calls(audit_log: &Path) -> Result<Vec<UnfinishedCall>, CliError> {
+    let mut calls: Vec<UnfinishedCall> = Vec::new();
+    let find = |calls: &[UnfinishedCall], id: Uuid| calls.iter().position(|c| c.call_uuid == id);
+    for rec in pie_audit_log::read_records(audit_log)? {
+        match rec.event {
+            spec::AuditEvent::ModelCallDispatched(e) => match find(&calls, e.model_call.0) {
+                Some(i) => {
+                    calls[i].provider = e.provider;
+                    calls[i].last_status = None;
+                }
+                None => calls.push(UnfinishedCall {
+                    call_uuid: e.model_call.0,
+                    run_id: e.run_id.0,
+                    provider: e.provider,
+                    attempts: 1,
+                    last_status: None,
+                }),
+            },
+            spec::AuditEvent::ModelCallRetried(e) => {
+                if let Some(i) = find(&calls, e.model_call.0) {
+                    calls[i].attempts += 1;
+                }
+            }
+            spec::AuditEvent::ModelCallCompleted(e) => {
+                if let Some(i) = find(&calls, e.model_call.0) {
+                    calls[i].last_status = Some(e.result.status);
+                }
+            }
+            _ => {}
+        }
+    }
+    calls.retain(|c| !matches!(c.last_status, Some(spec::CallStatus::Ok)));
+    Ok(calls)
+}
+
+/// Build the replay report for one call directory (see `Command::Replay`).
+///
+/// When the log holds several events of a kind for the call (e.g. a re-dispatch), the last one wins.
+/// Missing artifacts or events are reported as failures rather than errors.
+fn replay_call(call_dir: &Path, audit_log: &Path) -> Result<JsonValue, CliError> {
+    let manifest: CallManifest = serde_json::from_slice(&fs::read(call_dir.join("call_manifest.json"))?)?;
+    let call_uuid = Uuid::parse_str(&manifest.call_id)
+        .map_err(|_| CliError::Input("invalid call_id in manifest".into()))?;
+
+    let (mut pre, mut post, mut transform_log, mut response, mut normalized) = (None, None, None, None, None);
+    for rec in pie_audit_log::read_records(audit_log)? {
+        match rec.event {
+            spec::AuditEvent::ModelCallPrepared(e) if e.model_call.call_id.0 == call_uuid => {
+                pre = Some(e.integrity.request_pre_hash);
+            }
+            spec::AuditEvent::ModelRequestRedacted(e) if e.model_call.0 == call_uuid => {
+                post = Some(e.artifacts.post_request_artifact.hash);
+                transform_log = Some(e.redaction.transform_log_hash);
+            }
+            spec::AuditEvent::ModelCallCompleted(e) if e.model_call.0 == call_uuid => {
+                response = Some(e.result.response_hash);
+                normalized = Some(e.artifacts.normalized_reply_artifact.hash);
+            }
+            _ => {}
+        }
+    }
+
+    let checks = [
+        ("request_pre.json", pre),
+        ("request_post.json", post),
+        ("transform_log.json", transform_log),
+        ("response_raw.json", response),
+        ("reply_normalized.json", normalized),
+    ];
+    let mut artifacts = Vec::with_capacity(checks.len());
+    let mut all_ok = true;
+    for (name, expected) in checks {
+        let actual = read_json_artifact(&call_dir.join(name))?
+            .map(|v| pie_common::sha256_canonical_json(&v))
+            .transpose()?;
+        let ok = expected.is_some() && expected == actual;
+        all_ok &= ok;
+        artifacts.push(json!({ "artifact": name, "expected": expected, "actual": actual, "ok": ok }));
+    }
+
+    Ok(json!({ "call_id": manifest.call_id, "ok": all_ok, "artifacts": artifacts }))
+}
+
+/// Load a JSON artifact, or None if the file does not exist.
+fn read_json_artifact(path: &Path) -> Result<Option<JsonValue>, CliError> {
+    match fs::read(path) {
+        Ok(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Collect leaf-level differences between two JSON values as {path, left, right}.
+/// A key or element present on only one side is reported with null on the other.
+fn diff_json(left: &JsonValue, right: &JsonValue, path: &str, out: &mut Vec<JsonValue>) {
+    let join = |seg: &str| if path.is_empty() { seg.to_string() } else { format!("{path}.{seg}") };
+    match (left, right) {
+        (JsonValue::Object(l), JsonValue::Object(r)) => {
+            let keys: std::collections::BTreeSet<&String> = l.keys().chain(r.keys()).collect();
+            for k in keys {
+                let (lv, rv) = (l.get(k).unwrap_or(&JsonValue::Null), r.get(k).unwrap_or(&JsonValue::Null));
+                diff_json(lv, rv, &join(k), out);
+            }
+        }
+        (JsonValue::Array(l), JsonValue::Array(r)) => {
+            for i in 0..l.len().max(r.len()) {
+                let (lv, rv) = (l.get(i).unwrap_or(&JsonValue::Null), r.get(i).unwrap_or(&JsonValue::Null));
+                diff_json(lv, rv, &format!("{path}[{i}]"), out);
+            }
+        }
+        (l, r) if l != r => out.push(json!({ "path": path, "left": l, "right": r })),
+        _ => {}
+    }
+}
+
+/// Compare two call directories: canonical hashes of the request, reply and transform log, plus a
+/// field-level diff of request_post.json.
+fn diff_calls(left: &Path, right: &Path) -> Result<JsonValue, CliError> {
+    let mut artifacts = Vec::new();
+    let mut identical = true;
+    for name in ["request_post.json", "reply_normalized.json", "transform_log.json"] {
+        let hash = |dir: &Path| -> Result<Option<String>, CliError> {
+            Ok(read_json_artifact(&dir.join(name))?.map(|v| pie_common::sha256_canonical_json(&v)).transpose()?)
+        };
+        let (l, r) = (hash(left)?, hash(right)?);
+        let same = l == r;
+        identical &= same;
+        artifacts.push(json!({ "artifact": name, "left": l, "right": r, "same": same }));
+    }
+
+    let mut request_diff = Vec::new();
+    if let (Some(l), Some(r)) =
+        (read_json_artifact(&left.join("request_post.json"))?, read_json_artifact(&right.join("request_post.json"))?)
+    {
+        diff_json(&l, &r, "", &mut request_diff);
+    }
+
+    Ok(json!({ "identical": identical, "artifacts": artifacts, "request_diff": request_diff }))
+}
+
+/// How `dispatch_audited` calls the provider and prices the result.
+#[derive(Debug, Clone)]
+struct DispatchMode {
+    /// Echo reply text to stdout as it arrives (`Provider::dispatch_stream`).
+    stream: bool,
+    /// Hard budget for the call; on expiry it is abandoned and recorded as Timeout.
+    deadline_ms: Option<u64>,
+    retry: RetryPolicy,
+    /// Prices usage into ModelCallCompleted.result.estimated_cost_usd.
+    price: Option<ModelPrice>,
+    /// Response cache root (`--cache`); None disables both lookup and store.
+    cache_dir: Option<PathBuf>,
+}
+
+/// `runtime/cache/`: one `<post_hash hex>/<endpoint_fingerprint hex>/` directory per cached
+/// request and endpoint holding its `response_raw.json` and `reply_normalized.json`.
+fn response_cache_dir(repo_root: &Path) -> PathBuf {
+    repo_root.join("runtime").join("cache")
+}
+
+/// post_hash alone misses `--force-provider` and `--base-url`, so the endpoint is part of the key.
+fn response_cache_entry(cache_dir: &Path, post_hash: &str, endpoint_fp: &str) -> PathBuf {
+    cache_dir.join(post_hash.trim_start_matches("sha256:")).join(endpoint_fp.trim_start_matches("sha256:"))
+}
+
+/// The cached response for an entry, or None on a miss.
+fn read_cached_response(entry: &Path) -> Result<Option<pie_providers::ProviderResponse>, CliError> {
+    let norm_path = entry.join("reply_normalized.json");
+    if !norm_path.exists() {
+        return Ok(None);
+    }
+    Ok(Some(pie_providers::ProviderResponse {
+        raw_json: serde_json::from_slice(&fs::read(entry.join("response_raw.json"))?)?,
+        normalized: serde_json::from_slice(&fs::read(norm_path)?)?,
+    }))
+}
+
+/// USD per 1k tokens for one model.
+#[derive(Debug, Clone, Copy, serde::Deserialize)]
+struct ModelPrice {
+    input_per_1k: f64,
+    output_per_1k: f64,
+}
+
+impl ModelPrice {
+    /// Cost of a call, or None unless the provider reported both token counts. Reasoning tokens
+    /// are part of `output_tokens`, so they are priced at the output rate without being added again.
+    fn cost(&self, usage: &pie_providers::Usage) -> Option<f64> {
+        let (input, output) = (usage.input_tokens?, usage.output_tokens?);
+        Some(input as f64 / 1000.0 * self.input_per_1k + output as f64 / 1000.0 * self.output_per_1k)
+    }
+}
+
+/// `--price-input-per-1k`/`--price-output-per-1k` if given, else `model`'s entry in `--prices-file`.
+fn resolve_price(
+    model: &str,
+    input_per_1k: Option<f64>,
+    output_per_1k: Option<f64>,
+    prices_file: Option<&Path>,
+) -> Result<Option<ModelPrice>, CliError> {
+    if let (Some(input_per_1k), Some(output_per_1k)) = (input_per_1k, output_per_1k) {
+        return Ok(Some(ModelPrice { input_per_1k, output_per_1k }));
+    }
+    let Some(path) = prices_file else { return Ok(None) };
+    let mut table: std::collections::HashMap<String, ModelPrice> = serde_json::from_slice(&fs::read(path)?)?;
+    Ok(table.remove(model))
+}
+
+/// CLI-level retries; providers never retry on their own.
+#[derive(Debug, Clone, Copy)]
+struct RetryPolicy {
+    /// Attempts after the first; 0 disables retrying.
+    max_retries: u32,
+    /// Wait between attempts unless the provider sent `Retry-After`.
+    backoff_ms: u64,
+}
+
+impl RetryPolicy {
+    /// How long to wait before retrying after `e`, or None if `e` is not worth retrying:
+    /// malformed requests, integrity failures and 4xx rejections fail the same way every time.
+    fn wait_after(&self, e: &pie_providers::ProviderError) -> Option<std::time::Duration> {
+        use pie_providers::ProviderError as E;
+        let backoff = std::time::Duration::from_millis(self.backoff_ms);
+        match e {
+            E::RateLimited { retry_after } => Some(retry_after.unwrap_or(backoff)),
+            E::Provider { status, .. } if (400..500).contains(status) && *status != 408 => None,
+            E::InvalidRequest(_) | E::IntegrityMismatch { .. } | E::UnknownProvider(_) => None,
+            _ => Some(backoff),
+        }
+    }
+}
+
+struct DispatchOutcome {
+    status: spec::CallStatus,
+    latency_ms: u64,
+    /// Provider calls made, including the final one.
+    attempts: u32,
+    response_hash: String,
+    usage: pie_providers::Usage,
+    estimated_cost_usd: Option<f64>,
+    cache_hit: bool,
+}
+
+/// Dispatch a sanitized request and record it: ModelCallDispatched, a ModelCallRetried per failed
+/// attempt that is retried under `mode.retry`, then the final attempt's response artifacts
+/// (`response_raw.json`, `reply_normalized.json`) in `artifacts_dir`, then ModelCallCompleted.
+/// Provider failures are recorded in the artifacts and the completion status, not returned as errors.
+/// With `mode.cache_dir`, a cached reply for the same post_hash is used instead of calling the
+/// provider (zero attempts, no usage) and a fresh Ok reply is stored for next time.
+/// With `mode.stream`, reply text is echoed to stdout as it arrives and ModelStreamCompleted follows.
+/// `ts` is (clock, ts_dispatched, ts_completed); omitted timestamps are taken when each event is emitted.
+async fn dispatch_audited(
+    audit: &mut AuditAppender,
+    selected: &SelectedProvider,
+    req: &SanitizedModelRequest,
+    call_uuid: Uuid,
+    artifacts_dir: &Path,
+    mode: DispatchMode,
+    ts: (Clock, Option<f64>, Option<f64>),
+) -> Result<DispatchOutcome, CliError> {
+    let (clock, ts_dispatched, ts_completed) = ts;
+    let endpoint_fp = pie_common::endpoint_fingerprint(&selected.id, &selected.base_url, &req.model.0);
+    // Streamed bodies add stream options, so only the plain body is hashed. A body that cannot be
+    // built fails again in `dispatch` and is recorded there.
+    let request_body_hash = if mode.stream {
+        None
+    } else {
+        selected.provider.request_body(req).ok().flatten().map(|b| pie_common::sha256_bytes(&b))
+    };
+    let dispatched = spec::AuditEvent::ModelCallDispatched(spec::ModelCallDispatched {
+        schema_version: 1,
+        run_id: spec::RunId(req.run_id.0.clone()),
+        tick_id: spec::TickId(req.tick_id.0),
+        ts: clock.ts(ts_dispatched),
+        model_call: spec::CallId(call_uuid),
+        provider: selected.id.clone(),
+        model: req.model.0.clone(),
+        endpoint_fingerprint: endpoint_fp.clone(),
+        request_post_hash: req.integrity.post_hash.clone(),
+        request_body_hash,
+    });
+    audit.append(dispatched)?;
+
+    let start = Instant::now();
+    // (delta_count, first_delta_ms, streamed_bytes)
+    let mut stream_stats: (u64, Option<u64>, u64) = (0, None, 0);
+    let deadline = mode.deadline_ms.map(|ms| start + std::time::Duration::from_millis(ms));
+    let cache_entry = mode.cache_dir.as_deref().map(|d| response_cache_entry(d, &req.integrity.post_hash, &endpoint_fp));
+    let cached = match &cache_entry {
+        Some(entry) => read_cached_response(entry)?,
+        None => None,
+    };
+    let cache_hit = cached.is_some();
+    let mut attempts = u32::from(!cache_hit);
+    let resp = match cached {
+        Some(hit) => Ok(hit),
+        None => loop {
+            stream_stats = (0, None, 0);
+            let resp = if mode.stream {
+                let mut on_delta = |delta: &str| {
+                    stream_stats.0 += 1;
+                    stream_stats.1.get_or_insert(start.elapsed().as_millis() as u64);
+                    stream_stats.2 += delta.len() as u64;
+                    print!("{delta}");
+                    let _ = std::io::Write::flush(&mut std::io::stdout());
+                };
+                let call = selected.provider.dispatch_stream(req, &mut on_delta);
+                match deadline {
+                    Some(d) => tokio::time::timeout_at(d.into(), call)
+                        .await
+                        .unwrap_or(Err(pie_providers::ProviderError::Timeout)),
+                    None => call.await,
+                }
+            } else {
+                match deadline {
+                    Some(d) => selected.provider.dispatch_with_deadline(req, d).await,
+                    None => selected.provider.dispatch(req).await,
+                }
+            };
+            let Err(e) = &resp else { break resp };
+            // Deltas already echoed cannot be taken back, so a stream that failed mid-way is final.
+            if attempts > mode.retry.max_retries || stream_stats.0 > 0 {
+                break resp;
+            }
+            let Some(wait) = mode.retry.wait_after(e) else { break resp };
+            if deadline.is_some_and(|d| Instant::now() + wait >= d) {
+                break resp;
+            }
+            audit.append(spec::AuditEvent::ModelCallRetried(spec::ModelCallRetried {
+                schema_version: 1,
+                run_id: spec::RunId(req.run_id.0.clone()),
+                tick_id: spec::TickId(req.tick_id.0),
+                ts: clock.ts(None),
+                model_call: spec::CallId(call_uuid),
+                attempt: attempts,
+                status: call_status_for_error(e),
+                wait_ms: wait.as_millis() as u64,
+            }))?;
+            tokio::time::sleep(wait).await;
+            attempts += 1;
+        },
+    };
+    let latency_ms = start.elapsed().as_millis() as u64;
+    if stream_stats.0 > 0 {
+        println!();
+    }
+
+    // Always store raw response artifact, even on error (as structured object)
+    let raw_path = artifacts_dir.join("response_raw.json");
+    let norm_path = artifacts_dir.join("reply_normalized.json");
+    let mut usage = pie_providers::Usage::default();
+    let (status, provider_request_id_hash, raw_bytes, norm_bytes) = match resp {
+        Ok(ok) => {
+            let raw_bytes = pie_common::canonical_json_bytes(&ok.raw_json)?;
+            let norm_bytes = pie_common::canonical_json_bytes(&ok.normalized)?;
+            let pid_hash = sha256_bytes(ok.normalized.provider_request_id.unwrap_or_default().as_bytes());
+            // A cache hit spends no tokens.
+            if !cache_hit {
+                usage = ok.normalized.usage;
+            }
+            (spec::CallStatus::Ok, pid_hash, raw_bytes, norm_bytes)
+        }
+        Err(e) => {
+            let err_obj = serde_json::json!({"error": format!("{e}")});
+            let raw_bytes = pie_common::canonical_json_bytes(&err_obj)?;
+            // normalized reply absent on error; still write placeholder for replay determinism
+            let placeholder = serde_json::json!({"content":"", "finish_reason":"error", "usage":{"input_tokens":null,"output_tokens":null}, "provider_request_id": null});
+            let norm_bytes = pie_common::canonical_json_bytes(&placeholder)?;
+            (call_status_for_error(&e), sha256_bytes(b""), raw_bytes, norm_bytes)
+        }
+    };
+    fs::write(&raw_path, &raw_bytes)?;
+    fs::write(&norm_path, &norm_bytes)?;
+    if let (Some(entry), false, spec::CallStatus::Ok) = (&cache_entry, cache_hit, &status) {
+        fs::create_dir_all(entry)?;
+        fs::write(entry.join("response_raw.json"), &raw_bytes)?;
+        fs::write(entry.join("reply_normalized.json"), &norm_bytes)?;
+    }
+    let response_hash = sha256_bytes(&raw_bytes);
+    let norm_hash = sha256_bytes(&norm_bytes);
+    let estimated_cost_usd = mode.price.and_then(|p| p.cost(&usage));
+
+    let completed = spec::AuditEvent::ModelCallCompleted(spec::ModelCallCompleted {
+        schema_version: 1,
+        run_id: spec::RunId(req.run_id.0.clone()),
+        tick_id: spec::TickId(req.tick_id.0),
+        ts: clock.ts(ts_completed),
+        model_call: spec::CallId(call_uuid),
+        result: spec::ModelCallResult {
+            status,
+            latency_ms,
+            provider_request_id_hash,
+            response_hash: response_hash.clone(),
+            response_size_bytes: raw_bytes.len() as u64,
+            input_tokens: usage.input_tokens,
+            output_tokens: usage.output_tokens,
+            reasoning_tokens: usage.reasoning_tokens,
+            estimated_cost_usd,
+            cache_hit,
+        },
+        artifacts: spec::CompletionArtifacts {
+            response_artifact: spec::ArtifactRef { r#type: "artifact_ref".into(), hash: response_hash.clone() },
+            normalized_reply_artifact: spec::ArtifactRef { r#type: "artifact_ref".into(), hash: norm_hash },
+        },
+    });
+    audit.append(completed)?;
+
+    if mode.stream {
+        audit.append(spec::AuditEvent::ModelStreamCompleted(spec::ModelStreamCompleted {
+            schema_version: 1,
+            run_id: spec::RunId(req.run_id.0.clone()),
+            tick_id: spec::TickId(req.tick_id.0),
+            ts: clock.ts(ts_completed),
+            model_call: spec::CallId(call_uuid),
+            delta_count: stream_stats.0,
+            first_delta_ms: stream_stats.1,
+            streamed_bytes: stream_stats.2,
+        }))?;
+    }
+
+    Ok(DispatchOutcome { status, latency_ms, attempts, response_hash, usage, estimated_cost_usd, cache_hit })
+}
+
+/// `--profile` value to a redaction profile: "strict" or "explicit_allowlist".
+fn parse_profile(profile: &str) -> Result<RedactionProfile, CliError> {
+    match profile {
+        "strict" => Ok(RedactionProfile::Strict),
+        "explicit_allowlist" => Ok(RedactionProfile::ExplicitAllowlist(
+            // Keep empty for now (refs-only boundary). Expand later if needed.
+            pie_redaction::RedactionAllowlist { context_paths: vec![], allow_image_urls: false },
+        )),
+        other => Err(CliError::Redaction(pie_redaction::RedactionError::InvalidAllowlist(format!(
+            "unknown profile: {other}"
+        )))),
+    }
+}
+
+/// The transport a dispatch runs on.
+struct SelectedProvider {
+    provider: Box<dyn Provider>,
+    /// ProviderId dispatched to; recorded in ModelCallDispatched. Differs from the request's
+    /// declared provider only under --force-provider.
+    id: String,
+    /// Base URL to fingerprint: `DRY_RUN_ENDPOINT` for dry runs so they are distinguishable in the audit log.
+    base_url: String,
+}
+
+/// Base URL and API key for provider `id`: the explicit flags, else that provider's own env vars,
+/// else the config file's `base_url` (URL only), else its public endpoint. Only `openai` consults
+/// the OPENAI_* variables, so OPENAI_BASE_URL never hides the config URL from another provider.
+fn provider_endpoint(
+    id: &str,
+    base_url: Option<String>,
+    config_base_url: Option<&str>,
+    api_key: Option<String>,
+) -> (String, Option<String>) {
+    let env = |keys: &[&str]| keys.iter().find_map(|k| std::env::var(k).ok());
+    let (default_url, url_env, key_env): (String, &[&str], &[&str]) = match id {
+        "openai" => ("https://api.openai.com".into(), &["OPENAI_BASE_URL"], &["OPENAI_API_KEY"]),
+        "anthropic" => ("https://api.anthropic.com".into(), &[], &["ANTHROPIC_API_KEY"]),
+        "gemini" => ("https://generativelanguage.googleapis.com".into(), &[], &["GEMINI_API_KEY", "GOOGLE_API_KEY"]),
+        "ollama" => ("http://localhost:11434".into(), &[], &[]),
+        "bedrock" => {
+            let region = env(&["AWS_REGION", "AWS_DEFAULT_REGION"]).unwrap_or_default();
+            (format!("https://bedrock-runtime.{region}.amazonaws.com"), &[], &[])
+        }
+        _ => (String::new(), &[], &[]),
+    };
+    (
+        base_url.or_else(|| env(url_env)).or_else(|| config_base_url.map(str::to_string)).unwrap_or(default_url),
+        api_key.or_else(|| env(key_env)),
+    )
+}
+
+/// Env var a hosted provider's key is read from, when its public endpoint needs one.
+fn required_key_env(id: &str) -> Option<(&'static str, &'static str)> {
+    match id {
+        "openai" => Some(("OPENAI_API_KEY", "api.openai.com")),
+        "anthropic" => Some(("ANTHROPIC_API_KEY", "api.anthropic.com")),
+        "gemini" => Some(("GEMINI_API_KEY or GOOGLE_API_KEY", "generativelanguage.googleapis.com")),
+        _ => None,
+    }
+}
+
+/// Resolve `--provider` against the request's declared provider (a mismatch needs `force`) and
+/// build it through the provider factory, or the canned dry-run mock. Base URL and key default
+/// per selected provider (see `provider_endpoint`).
+#[allow(clippy::too_many_arguments)]
+fn select_provider(
+    declared: &pie_redaction::ProviderId,
+    choice: ProviderChoice,
+    force: bool,
+    base_url: Option<String>,
+    config_base_url: Option<&str>,
+    api_key: Option<String>,
+    opts: &ProviderOptions,
+    dry_run: bool,
+) -> Result<SelectedProvider, CliError> {
+    let id = match choice.id() {
+        None => declared.0.clone(),
+        Some(id) if id == declared.0 || force => id.to_string(),
+        Some(id) => return Err(CliError::ProviderConflict { flag: id.into(), declared: declared.0.clone() }),
+    };
+    if dry_run {
+        return Ok(SelectedProvider {
+            provider: Box::new(MockProvider::dry_run()),
+            id,
+            base_url: DRY_RUN_ENDPOINT.to_string(),
+        });
+    }
+    let (base_url, api_key) = provider_endpoint(&id, base_url, config_base_url, api_key);
+
+    // Helpful guardrail: if you're pointing at a hosted API and no API key is set, fail loudly.
+    if let Some((key_env, host)) = required_key_env(&id) {
+        if api_key.as_deref().unwrap_or("").is_empty() && base_url.contains(host) {
+            return Err(CliError::Config(format!(
+                "{key_env} is required for https://{host} (set it in .env or env var)"
+            )));
+        }
+    }
+    let provider = build_provider_with_options(&pie_redaction::ProviderId(id.clone()), base_url.clone(), api_key, opts)?;
+    Ok(SelectedProvider { provider, id, base_url })
+}
+
+/// Map a provider failure onto the audit spec's CallStatus. Client timeouts and fired deadlines
+/// are both Timeout; connection failures have no status of their own and record as Error.
+fn call_status_for_error(e: &pie_providers::ProviderError) -> spec::CallStatus {
+    match e {
+        pie_providers::ProviderError::RateLimited { .. } => spec::CallStatus::RateLimited,
+        pie_providers::ProviderError::Timeout => spec::CallStatus::Timeout,
+        _ => spec::CallStatus::Error,
+    }
+}
+
 fn ensure_runtime_dirs(repo_root: &Path) -> Result<(), CliError> {
     let logs = repo_root.join("runtime").join("logs");
     let artifacts = repo_root.join("runtime").join("artifacts");
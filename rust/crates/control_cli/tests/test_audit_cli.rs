@@ -0,0 +1,173 @@
+use assert_cmd::prelude::*;
+use pie_audit_log::AuditAppender;
+use pie_audit_spec::*;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use tempfile::TempDir;
+use uuid::Uuid;
+
+fn artifact(hash: &str) -> ArtifactRef {
+    ArtifactRef { r#type: "artifact_ref".into(), hash: hash.into() }
+}
+
+fn completed(run: &str, tick: u64, status: CallStatus) -> AuditEvent {
+    AuditEvent::ModelCallCompleted(ModelCallCompleted {
+        schema_version: 1,
+        run_id: RunId(run.into()),
+        tick_id: TickId(tick),
+        ts: 0.0,
+        model_call: CallId(Uuid::new_v4()),
+        result: ModelCallResult {
+            status,
+            latency_ms: 1,
+            provider_request_id_hash: "sha256:p".into(),
+            response_hash: "sha256:r".into(),
+            response_size_bytes: 1,
+            input_tokens: None,
+            output_tokens: None,
+            reasoning_tokens: None,
+            estimated_cost_usd: None,
+            cache_hit: false,
+        },
+        artifacts: CompletionArtifacts {
+            response_artifact: artifact("sha256:r"),
+            normalized_reply_artifact: artifact("sha256:n"),
+        },
+    })
+}
+
+fn seed_log(path: &Path) {
+    let mut app = AuditAppender::open(path).unwrap();
+    app.append(completed("run_a", 1, CallStatus::Ok)).unwrap();
+    app.append(completed("run_a", 4, CallStatus::Timeout)).unwrap();
+    app.append(completed("run_a", 2, CallStatus::RateLimited)).unwrap();
+    app.append(AuditEvent::EpisodeAppended(EpisodeAppended {
+        schema_version: 1,
+        run_id: RunId("run_b".into()),
+        tick_id: TickId(3),
+        ts: 0.0,
+        episode_id: Uuid::new_v4(),
+        thread_id: "main".into(),
+        tags: vec![],
+        title: "t".into(),
+        episode_hash: "sha256:e".into(),
+        episode_artifact: artifact("sha256:e"),
+    }))
+    .unwrap();
+}
+
+#[test]
+fn audit_report_summarizes_seeded_log() {
+    let dir = TempDir::new().unwrap();
+    let log = dir.path().join("audit.jsonl");
+    seed_log(&log);
+
+    let out = Command::new(assert_cmd::cargo::cargo_bin!("pie-control"))
+        .args(["audit-report", "--audit-log", log.to_str().unwrap()])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let report: serde_json::Value = serde_json::from_slice(&out).unwrap();
+
+    assert_eq!(report["records"], 4);
+    assert_eq!(report["event_counts"]["ModelCallCompleted"], 3);
+    assert_eq!(report["event_counts"]["EpisodeAppended"], 1);
+    assert_eq!(report["run_ids"], serde_json::json!(["run_a", "run_b"]));
+    assert_eq!(report["tick_range"], serde_json::json!([1, 4]));
+    assert_eq!(report["model_calls"], 3);
+    assert_eq!(report["errors"], 0);
+    assert_eq!(report["timeouts"], 1);
+    assert_eq!(report["rate_limited"], 1);
+    assert_eq!(report["final_hash"], pie_audit_log::verify_log(&log).unwrap());
+}
+
+#[test]
+fn audit_report_fails_on_broken_chain() {
+    let dir = TempDir::new().unwrap();
+    let log = dir.path().join("audit.jsonl");
+    seed_log(&log);
+    let tampered = fs::read_to_string(&log).unwrap().replacen("\"timeout\"", "\"ok\"", 1);
+    fs::write(&log, tampered).unwrap();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("pie-control"))
+        .args(["audit-report", "--audit-log", log.to_str().unwrap()])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn format_flag_switches_between_compact_and_pretty_json() {
+    let dir = TempDir::new().unwrap();
+    let log = dir.path().join("audit.jsonl");
+    seed_log(&log);
+
+    let run = |format: &str| {
+        let out = Command::new(assert_cmd::cargo::cargo_bin!("pie-control"))
+            .args(["--format", format, "audit-report", "--audit-log", log.to_str().unwrap()])
+            .assert()
+            .success()
+            .get_output()
+            .stdout
+            .clone();
+        String::from_utf8(out).unwrap()
+    };
+
+    let compact = run("json");
+    assert_eq!(compact.trim_end().lines().count(), 1);
+    let pretty = run("pretty");
+    assert!(pretty.contains("\n  \"records\": 4"), "{pretty}");
+    let a: serde_json::Value = serde_json::from_str(&compact).unwrap();
+    let b: serde_json::Value = serde_json::from_str(&pretty).unwrap();
+    assert_eq!(a, b);
+}
+
+#[test]
+fn audit_watch_follows_a_log_created_after_it_starts() {
+    let dir = TempDir::new().unwrap();
+    let log = dir.path().join("audit.jsonl");
+
+    let child = Command::new(assert_cmd::cargo::cargo_bin!("pie-control"))
+        .args(["audit-watch", "--audit-log", log.to_str().unwrap(), "--poll-ms", "20", "--max-records", "3"])
+        .stdout(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    std::thread::sleep(std::time::Duration::from_millis(100));
+    let mut app = AuditAppender::open(&log).unwrap();
+    for tick in 1..=3 {
+        app.append(completed("run_w", tick, CallStatus::Ok)).unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(50));
+    }
+
+    let out = child.wait_with_output().unwrap();
+    assert!(out.status.success());
+    let lines: Vec<serde_json::Value> =
+        String::from_utf8(out.stdout).unwrap().lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+    let seen: Vec<_> = lines.iter().map(|l| (l["line"].as_u64().unwrap(), l["tick_id"].as_u64().unwrap())).collect();
+    assert_eq!(seen, [(1, 1), (2, 2), (3, 3)]);
+    assert_eq!(lines[0]["event_type"], "ModelCallCompleted");
+    assert_eq!(lines[0]["run_id"], "run_w");
+    assert!(lines[0]["hash"].as_str().unwrap().starts_with("sha256:"));
+}
+
+#[test]
+fn audit_watch_stops_at_a_chain_break() {
+    let dir = TempDir::new().unwrap();
+    let log = dir.path().join("audit.jsonl");
+    seed_log(&log);
+    let tampered = fs::read_to_string(&log).unwrap().replacen("\"timeout\"", "\"ok\"", 1);
+    fs::write(&log, tampered).unwrap();
+
+    let out = Command::new(assert_cmd::cargo::cargo_bin!("pie-control"))
+        .args(["audit-watch", "--audit-log", log.to_str().unwrap(), "--poll-ms", "20"])
+        .assert()
+        .code(5)
+        .get_output()
+        .clone();
+    // The record before the break is still reported.
+    assert_eq!(String::from_utf8(out.stdout).unwrap().lines().count(), 1);
+    assert!(String::from_utf8(out.stderr).unwrap().contains("hash mismatch at line 2"));
+}
@@ -0,0 +1,28 @@
+use assert_cmd::prelude::*;
+use predicates::prelude::*;
+use std::process::Command;
+
+#[test]
+fn bash_completions_list_subcommands() {
+    let out = Command::new(assert_cmd::cargo::cargo_bin!("pie-control"))
+        .args(["completions", "--shell", "bash"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let script = String::from_utf8(out).unwrap();
+    assert!(script.contains("complete -F _pie__control"));
+    for sub in ["redact-only", "dispatch-dir", "episode-append", "verify-audit", "completions"] {
+        assert!(script.contains(sub), "missing {sub}");
+    }
+}
+
+#[test]
+fn completions_reject_unknown_shell() {
+    Command::new(assert_cmd::cargo::cargo_bin!("pie-control"))
+        .args(["completions", "--shell", "tcsh"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid value 'tcsh'"));
+}
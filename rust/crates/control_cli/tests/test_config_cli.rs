@@ -0,0 +1,136 @@
+use assert_cmd::prelude::*;
+use predicates::prelude::*;
+use std::fs;
+use std::process::Command;
+use tempfile::TempDir;
+
+#[test]
+fn config_file_supplies_omitted_flags_and_explicit_flags_win() {
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.path().join("from_config.jsonl"), "").unwrap();
+    let cfg = dir.path().join("pie-control.toml");
+    // Relative paths resolve against the config file's directory.
+    fs::write(&cfg, "audit_log = \"from_config.jsonl\"\n").unwrap();
+
+    // Flag omitted: the config's (empty, valid) log is verified.
+    Command::new(assert_cmd::cargo::cargo_bin!("pie-control"))
+        .env("PIE_CONFIG", &cfg)
+        .args(["audit-report"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"records\":0"));
+
+    // Flag present: it overrides the config value.
+    let missing = dir.path().join("missing.jsonl");
+    Command::new(assert_cmd::cargo::cargo_bin!("pie-control"))
+        .env("PIE_CONFIG", &cfg)
+        .args(["audit-report", "--audit-log", missing.to_str().unwrap()])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("io error"));
+}
+
+#[test]
+fn config_file_is_discovered_under_repo_root() {
+    let repo = TempDir::new().unwrap();
+    fs::create_dir_all(repo.path().join("runtime").join("logs")).unwrap();
+    fs::write(repo.path().join("pie-control.toml"), "audit_log = \"runtime/logs/audit_rust.jsonl\"\n").unwrap();
+    let req = repo.path().join("episode_append.json");
+    fs::write(
+        &req,
+        r#"{"schema_version":1,"run_id":"run_demo","tick_id":1,"title":"t","summary":"s"}"#,
+    )
+    .unwrap();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("pie-control"))
+        .env_remove("PIE_CONFIG")
+        .args(["episode-append", "--repo-root", repo.path().to_str().unwrap()])
+        .args(["--request-json", req.to_str().unwrap()])
+        .assert()
+        .success();
+
+    let log = fs::read_to_string(repo.path().join("runtime").join("logs").join("audit_rust.jsonl")).unwrap();
+    assert!(log.contains("\"EpisodeAppended\""));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn provider_base_url_yields_only_to_the_selected_providers_env_var() {
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/api/chat"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "model": "llama3",
+            "message": { "role": "assistant", "content": "hey" },
+            "done": true,
+            "done_reason": "stop"
+        })))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/v1/models"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({ "data": [{ "id": "gpt" }] })))
+        .mount(&server)
+        .await;
+
+    let repo = TempDir::new().unwrap();
+    let root = repo.path().to_str().unwrap();
+    let audit = repo.path().join("audit.jsonl");
+    let req = repo.path().join("model_request.json");
+    fs::write(
+        &req,
+        serde_json::json!({
+            "schema_version": 1,
+            "run_id": "run_cfg",
+            "tick_id": 1,
+            "role": "planner",
+            "provider": "ollama",
+            "model": "llama3",
+            "prompt": {
+                "format": "chat",
+                "messages": [{ "role": "user", "content": "hello" }],
+                "max_output_tokens": 16,
+                "temperature": 0.2,
+                "top_p": 1.0,
+                "stop": []
+            }
+        })
+        .to_string(),
+    )
+    .unwrap();
+    let out = Command::new(assert_cmd::cargo::cargo_bin!("pie-control"))
+        .env_remove("PIE_CONFIG")
+        .args(["redact-only", "--repo-root", root, "--request-json", req.to_str().unwrap()])
+        .args(["--audit-log", audit.to_str().unwrap()])
+        .output()
+        .unwrap();
+    assert!(out.status.success());
+    let call_id =
+        serde_json::from_slice::<serde_json::Value>(&out.stdout).unwrap()["call_id"].as_str().unwrap().to_string();
+    let post = repo.path().join("runtime/artifacts/models/run_cfg").join(&call_id).join("request_post.json");
+
+    // OPENAI_BASE_URL is openai's variable: it must not send an ollama dispatch elsewhere.
+    let cfg = repo.path().join("provider.toml");
+    fs::write(&cfg, format!("base_url = \"{}\"\n", server.uri())).unwrap();
+    Command::new(assert_cmd::cargo::cargo_bin!("pie-control"))
+        .env("PIE_CONFIG", &cfg)
+        .env("OPENAI_BASE_URL", "http://127.0.0.1:9")
+        .args(["dispatch", "--repo-root", root, "--sanitized-json", post.to_str().unwrap()])
+        .args(["--audit-log", audit.to_str().unwrap(), "--call-id", &call_id])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"status\":\"Ok\""));
+
+    // For openai itself the env var still outranks the config file.
+    let unreachable = repo.path().join("unreachable.toml");
+    fs::write(&unreachable, "base_url = \"http://127.0.0.1:9\"\n").unwrap();
+    Command::new(assert_cmd::cargo::cargo_bin!("pie-control"))
+        .env("PIE_CONFIG", &unreachable)
+        .env("OPENAI_BASE_URL", server.uri())
+        .args(["provider-models", "--api-key", "k"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("gpt"));
+}
@@ -43,7 +43,7 @@ fn episodes_cli_append_query_get_roundtrip() {
     let pie_control = assert_cmd::cargo::cargo_bin!("pie-control");
 
     // 1) episode-append
-    Command::new(&pie_control)
+    Command::new(pie_control)
         .args([
             "episode-append",
             "--repo-root",
@@ -61,7 +61,7 @@ fn episodes_cli_append_query_get_roundtrip() {
         .stdout(predicate::str::contains("\"episode_hash\""));
 
     // 2) episode-query
-    let query_out = Command::new(&pie_control)
+    let query_out = Command::new(pie_control)
         .args([
             "episode-query",
             "--repo-root",
@@ -96,7 +96,7 @@ fn episodes_cli_append_query_get_roundtrip() {
     let episode_id = &s[start..end];
 
     // 3) episode-get
-    Command::new(&pie_control)
+    Command::new(pie_control)
         .args([
             "episode-get",
             "--repo-root",
@@ -106,11 +106,28 @@ fn episodes_cli_append_query_get_roundtrip() {
         ])
         .assert()
         .success()
-        .stdout(predicate::str::contains("\"schema_version\":1"))
+        .stdout(predicate::str::contains("\"schema_version\":2"))
         .stdout(predicate::str::contains("\"run_id\":\"run_demo\""))
         .stdout(predicate::str::contains("\"thread_id\":\"main\""))
         .stdout(predicate::str::contains("\"hash\":\"sha256:"));
 
+    // 4) --limit 0 is an explicit empty result; --all lifts the cap (and conflicts with --limit)
+    let root = repo.path().to_str().unwrap();
+    Command::new(pie_control)
+        .args(["episode-query", "--repo-root", root, "--limit", "0"])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("[]"));
+    Command::new(pie_control)
+        .args(["episode-query", "--repo-root", root, "--all"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(episode_id));
+    Command::new(pie_control)
+        .args(["episode-query", "--repo-root", root, "--all", "--limit", "5"])
+        .assert()
+        .failure();
+
     // Sanity: episodes files exist
     assert!(repo
         .path()
@@ -126,4 +143,185 @@ fn episodes_cli_append_query_get_roundtrip() {
         .join("episodes")
         .join("index.json")
         .exists());
-}
\ No newline at end of file
+}
+
+#[test]
+fn omitted_ts_uses_wall_clock_unless_deterministic() {
+    let repo = TempDir::new().unwrap();
+    fs::create_dir_all(repo.path().join("runtime").join("logs")).unwrap();
+    let req = write_append_req(&repo);
+    let audit = audit_log_path(&repo);
+
+    let append = |extra: &[&str]| {
+        Command::new(assert_cmd::cargo::cargo_bin!("pie-control"))
+            .args(extra)
+            .args(["episode-append", "--repo-root", repo.path().to_str().unwrap()])
+            .args(["--request-json", req.to_str().unwrap(), "--audit-log", audit.to_str().unwrap()])
+            .assert()
+            .success();
+    };
+    append(&[]);
+    append(&["--deterministic-ts"]);
+
+    let log = fs::read_to_string(&audit).unwrap();
+    let ts: Vec<f64> = log
+        .lines()
+        .map(|l| serde_json::from_str::<serde_json::Value>(l).unwrap()["event"]["ts"].as_f64().unwrap())
+        .collect();
+    assert_eq!(ts.len(), 2);
+    assert!(ts[0] > 1_600_000_000.0, "{ts:?}");
+    assert_eq!(ts[1], 0.0);
+}
+
+#[test]
+fn episode_reindex_recovers_index_and_verify_flags_corruption() {
+    let repo = TempDir::new().unwrap();
+    let store = pie_episodes::EpisodeStore::new(repo.path().to_path_buf());
+    for (tick, summary) in [(1, "first"), (2, "second"), (3, "third")] {
+        let ep = pie_episodes::Episode::new(
+            pie_episodes::RunId("run_demo".into()),
+            pie_episodes::TickId(tick),
+            "main",
+            vec![],
+            "t",
+            summary,
+            vec![],
+            0.0,
+        )
+        .unwrap();
+        store.append(&ep).unwrap();
+    }
+    fs::remove_file(store.index_path()).unwrap();
+
+    let pie_control = assert_cmd::cargo::cargo_bin!("pie-control");
+    let root = repo.path().to_str().unwrap();
+    Command::new(pie_control)
+        .args(["episode-reindex", "--repo-root", root])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"entries\":3"));
+    Command::new(pie_control)
+        .args(["episode-verify", "--repo-root", root])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"verified\":3"));
+
+    let text = fs::read_to_string(store.episodes_path()).unwrap();
+    fs::write(store.episodes_path(), text.replace("\"second\"", "\"tampered\"")).unwrap();
+    Command::new(pie_control)
+        .args(["episode-verify", "--repo-root", root])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("store verification failed at line 1"));
+}
+
+#[test]
+fn episode_delete_hides_episode_and_emits_audit_event() {
+    let repo = TempDir::new().unwrap();
+    fs::create_dir_all(repo.path().join("runtime").join("logs")).unwrap();
+    let req = write_append_req(&repo);
+    let audit = audit_log_path(&repo);
+    let root = repo.path().to_str().unwrap();
+    let pie_control = assert_cmd::cargo::cargo_bin!("pie-control");
+
+    let out = Command::new(pie_control)
+        .args(["episode-append", "--repo-root", root, "--request-json", req.to_str().unwrap()])
+        .args(["--audit-log", audit.to_str().unwrap()])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let appended: serde_json::Value = serde_json::from_slice(&out).unwrap();
+    let episode_id = appended["episode_id"].as_str().unwrap();
+
+    Command::new(pie_control)
+        .args(["episode-delete", "--repo-root", root, "--episode-id", episode_id, "--reason", "gdpr request"])
+        .args(["--audit-log", audit.to_str().unwrap(), "--ts", "5.0"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"deleted\":true"));
+
+    Command::new(pie_control)
+        .args(["episode-query", "--repo-root", root, "--all"])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("[]"));
+
+    let log = fs::read_to_string(&audit).unwrap();
+    assert!(log.contains("\"event_type\":\"EpisodeDeleted\""));
+    assert!(log.contains("\"reason\":\"gdpr request\""));
+
+    Command::new(pie_control)
+        .args(["episode-delete", "--repo-root", root, "--episode-id", &uuid::Uuid::new_v4().to_string()])
+        .args(["--reason", "x", "--audit-log", audit.to_str().unwrap()])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("episode not found"));
+}
+
+#[test]
+fn episode_append_reads_request_from_stdin() {
+    let repo = TempDir::new().unwrap();
+    fs::create_dir_all(repo.path().join("runtime").join("logs")).unwrap();
+    let req = fs::read_to_string(write_append_req(&repo)).unwrap();
+    let audit = audit_log_path(&repo);
+
+    assert_cmd::Command::new(assert_cmd::cargo::cargo_bin!("pie-control"))
+        .args(["episode-append", "--repo-root", repo.path().to_str().unwrap(), "--request-json", "-"])
+        .args(["--audit-log", audit.to_str().unwrap()])
+        .write_stdin(req)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"episode_id\""));
+}
+
+#[test]
+fn episode_import_restores_an_exported_store() {
+    let src = TempDir::new().unwrap();
+    fs::create_dir_all(src.path().join("runtime").join("logs")).unwrap();
+    let req = write_append_req(&src);
+    let pie_control = assert_cmd::cargo::cargo_bin!("pie-control");
+    for _ in 0..2 {
+        Command::new(pie_control)
+            .args(["episode-append", "--repo-root", src.path().to_str().unwrap(), "--request-json", req.to_str().unwrap()])
+            .args(["--audit-log", audit_log_path(&src).to_str().unwrap(), "--ts", "0.0"])
+            .assert()
+            .success();
+    }
+    let bundle = src.path().join("bundle.json");
+    pie_episodes::EpisodeStore::new(src.path().to_path_buf()).export(&bundle).unwrap();
+
+    let dst = TempDir::new().unwrap();
+    let import = |force: bool| {
+        let mut cmd = Command::new(pie_control);
+        cmd.args(["episode-import", "--repo-root", dst.path().to_str().unwrap(), "--bundle", bundle.to_str().unwrap()]);
+        if force {
+            cmd.arg("--force");
+        }
+        cmd.assert()
+    };
+    import(false).success().stdout(predicate::str::contains("\"imported\":2"));
+    import(false).failure().stderr(predicate::str::contains("store is not empty"));
+    import(true).success();
+
+    let query = |root: &std::path::Path| {
+        Command::new(pie_control)
+            .args(["episode-query", "--repo-root", root.to_str().unwrap(), "--all"])
+            .assert()
+            .success()
+            .get_output()
+            .stdout
+            .clone()
+    };
+    assert_eq!(query(src.path()), query(dst.path()));
+
+    let tampered = dst.path().join("tampered.json");
+    fs::write(&tampered, fs::read_to_string(&bundle).unwrap().replace("Stage 7B.1 test", "edited")).unwrap();
+    let fresh = TempDir::new().unwrap();
+    Command::new(pie_control)
+        .args(["episode-import", "--repo-root", fresh.path().to_str().unwrap(), "--bundle", tampered.to_str().unwrap()])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("hash mismatch"));
+}
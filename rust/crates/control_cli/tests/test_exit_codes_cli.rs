@@ -0,0 +1,133 @@
+use assert_cmd::prelude::*;
+use pie_audit_log::AuditAppender;
+use pie_audit_spec::*;
+use predicates::prelude::*;
+use std::fs;
+use std::process::Command;
+use tempfile::TempDir;
+
+fn pie_control(args: &[&str]) -> assert_cmd::assert::Assert {
+    Command::new(assert_cmd::cargo::cargo_bin!("pie-control")).args(args).assert()
+}
+
+#[test]
+fn io_and_bad_input_errors_have_distinct_codes() {
+    let dir = TempDir::new().unwrap();
+    let missing = dir.path().join("missing.json");
+    pie_control(&["hash", "--file", missing.to_str().unwrap()]).code(2);
+
+    let garbage = dir.path().join("garbage.json");
+    fs::write(&garbage, "{ not json").unwrap();
+    pie_control(&["hash", "--file", garbage.to_str().unwrap()]).code(3);
+    pie_control(&["validate", "--kind", "model_request", "--file", garbage.to_str().unwrap()]).code(3);
+
+    // Usage errors are bad input too (clap's default would be 2, which would collide with I/O).
+    pie_control(&["no-such-command"]).code(3);
+    pie_control(&["--help"]).code(0);
+}
+
+#[test]
+fn broken_audit_chain_exits_with_integrity_code() {
+    let dir = TempDir::new().unwrap();
+    let log = dir.path().join("audit.jsonl");
+    let mut app = AuditAppender::open(&log).unwrap();
+    for tick in 1..=2 {
+        app.append(AuditEvent::EpisodeDeleted(EpisodeDeleted {
+            schema_version: 1,
+            run_id: RunId("run_exit".into()),
+            tick_id: TickId(tick),
+            ts: 0.0,
+            episode_id: uuid::Uuid::nil(),
+            episode_hash: "sha256:aa".into(),
+            reason: "test".into(),
+            unmirrored_remote_id: None,
+        }))
+        .unwrap();
+    }
+    pie_control(&["verify-audit", "--audit-log", log.to_str().unwrap()]).code(0);
+
+    let text = fs::read_to_string(&log).unwrap();
+    fs::write(&log, text.replace("\"reason\":\"test\"", "\"reason\":\"edited\"")).unwrap();
+    pie_control(&["verify-audit", "--audit-log", log.to_str().unwrap()]).code(5);
+    pie_control(&["audit-report", "--audit-log", log.to_str().unwrap()]).code(5);
+}
+
+/// redact-only on a minimal openai request; returns (repo, call_dir, audit_log).
+fn redacted_call() -> (TempDir, std::path::PathBuf, std::path::PathBuf) {
+    let repo = TempDir::new().unwrap();
+    let root = repo.path();
+    let audit = root.join("audit.jsonl");
+    let req = root.join("model_request.json");
+    fs::write(
+        &req,
+        serde_json::json!({
+            "schema_version": 1,
+            "run_id": "run_exit",
+            "tick_id": 1,
+            "role": "planner",
+            "provider": "openai",
+            "model": "gpt",
+            "prompt": {
+                "format": "chat",
+                "messages": [{ "role": "user", "content": "hello" }],
+                "max_output_tokens": 16,
+                "temperature": 0.2,
+                "top_p": 1.0,
+                "stop": []
+            }
+        })
+        .to_string(),
+    )
+    .unwrap();
+    let out = Command::new(assert_cmd::cargo::cargo_bin!("pie-control"))
+        .args(["redact-only", "--repo-root", root.to_str().unwrap(), "--request-json", req.to_str().unwrap()])
+        .args(["--audit-log", audit.to_str().unwrap()])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let call_id = serde_json::from_slice::<serde_json::Value>(&out).unwrap()["call_id"].as_str().unwrap().to_string();
+    let call_dir = root.join("runtime").join("artifacts").join("models").join("run_exit").join(call_id);
+    (repo, call_dir, audit)
+}
+
+#[test]
+fn rejected_dispatch_inputs_are_bad_input_or_integrity_not_provider_failures() {
+    let (repo, call_dir, audit) = redacted_call();
+    let root = repo.path().to_str().unwrap();
+    let post = call_dir.join("request_post.json");
+    let dispatch = |extra: &[&str]| {
+        Command::new(assert_cmd::cargo::cargo_bin!("pie-control"))
+            .env_remove("OPENAI_BASE_URL")
+            .env_remove("OPENAI_API_KEY")
+            .args(["dispatch", "--repo-root", root, "--sanitized-json", post.to_str().unwrap()])
+            .args(["--audit-log", audit.to_str().unwrap()])
+            .args(extra)
+            .assert()
+    };
+
+    dispatch(&["--call-id", "not-a-uuid", "--base-url", "http://127.0.0.1:9"])
+        .code(3)
+        .stderr(predicate::str::contains("invalid call_id"));
+    // Missing key for the public OpenAI endpoint.
+    dispatch(&["--call-id", call_dir.file_name().unwrap().to_str().unwrap()])
+        .code(3)
+        .stderr(predicate::str::contains("OPENAI_API_KEY is required"));
+
+    let tampered = fs::read_to_string(&post).unwrap().replace("\"hello\"", "\"edited\"");
+    fs::write(&post, tampered).unwrap();
+    dispatch(&["--call-id", call_dir.file_name().unwrap().to_str().unwrap(), "--base-url", "http://127.0.0.1:9"])
+        .code(5)
+        .stderr(predicate::str::contains("integrity mismatch"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn provider_failure_exits_with_provider_code() {
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    let server = MockServer::start().await;
+    Mock::given(method("GET")).and(path("/v1/models")).respond_with(ResponseTemplate::new(503)).mount(&server).await;
+    pie_control(&["provider-models", "--base-url", &server.uri(), "--api-key", "k"]).code(6);
+}
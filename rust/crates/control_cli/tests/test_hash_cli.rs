@@ -0,0 +1,50 @@
+use assert_cmd::prelude::*;
+use std::fs;
+use std::process::Command;
+use tempfile::TempDir;
+
+fn hash(args: &[&str]) -> String {
+    let out = Command::new(assert_cmd::cargo::cargo_bin!("pie-control"))
+        .arg("hash")
+        .args(args)
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    String::from_utf8(out).unwrap().trim().to_string()
+}
+
+#[test]
+fn hash_is_canonical_unless_raw() {
+    let dir = TempDir::new().unwrap();
+    let p = dir.path().join("artifact.json");
+    // Key order and whitespace differ from the canonical form {"a":1,"b":[true,null],"c":{"x":"y"}}
+    fs::write(&p, "{ \"c\": {\"x\": \"y\"}, \"b\": [true, null], \"a\": 1 }\n").unwrap();
+    let file = p.to_str().unwrap();
+
+    assert_eq!(
+        hash(&["--file", file]),
+        "sha256:52b91ed3ded0789467a61e5bb912382f6f877d3e7f146745568f09d66e8ebf75"
+    );
+    assert_eq!(
+        hash(&["--file", file, "--raw"]),
+        "sha256:908927d6234c8f3afd43d14e0afd4c2a18e3ede0d28abee72e5213bf16822f4b"
+    );
+
+    let b3 = hash(&["--file", file, "--algo", "blake3"]);
+    assert_eq!(b3, pie_common::blake3_bytes(br#"{"a":1,"b":[true,null],"c":{"x":"y"}}"#));
+}
+
+#[test]
+fn hash_rejects_non_json_without_raw() {
+    let dir = TempDir::new().unwrap();
+    let p = dir.path().join("notes.txt");
+    fs::write(&p, "not json").unwrap();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("pie-control"))
+        .args(["hash", "--file", p.to_str().unwrap()])
+        .assert()
+        .failure();
+    assert!(hash(&["--file", p.to_str().unwrap(), "--raw"]).starts_with("sha256:"));
+}
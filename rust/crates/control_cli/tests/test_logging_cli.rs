@@ -0,0 +1,65 @@
+use assert_cmd::prelude::*;
+use predicates::prelude::*;
+use serde_json::json;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use tempfile::TempDir;
+
+/// A repo with a `.env` and a model request; returns the redact-only args.
+fn redact_args(root: &Path) -> Vec<String> {
+    fs::write(root.join(".env"), "PIE_LOGGING_TEST=1\n").unwrap();
+    let req = root.join("req.json");
+    let body = json!({
+        "schema_version": 1,
+        "run_id": "run_logging",
+        "tick_id": 1,
+        "role": "planner",
+        "provider": "openai",
+        "model": "gpt",
+        "prompt": {
+            "format": "chat",
+            "messages": [{ "role": "user", "content": "hello" }],
+            "max_output_tokens": 64,
+            "temperature": 0.2,
+            "top_p": 1.0,
+            "stop": []
+        },
+        "context": {}
+    });
+    fs::write(&req, body.to_string()).unwrap();
+    ["redact-only", "--repo-root", root.to_str().unwrap(), "--request-json", req.to_str().unwrap()]
+        .into_iter()
+        .map(String::from)
+        .chain(["--audit-log".into(), root.join("audit.jsonl").to_str().unwrap().into()])
+        .collect()
+}
+
+#[test]
+fn quiet_success_writes_nothing_to_stderr() {
+    let repo = TempDir::new().unwrap();
+    Command::new(assert_cmd::cargo::cargo_bin!("pie-control"))
+        .arg("--quiet")
+        .args(redact_args(repo.path()))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"call_id\""))
+        .stderr(predicate::str::is_empty());
+}
+
+#[test]
+fn env_loading_is_reported_at_debug_level() {
+    let repo = TempDir::new().unwrap();
+    let args = redact_args(repo.path());
+    Command::new(assert_cmd::cargo::cargo_bin!("pie-control"))
+        .args(&args)
+        .assert()
+        .success()
+        .stderr(predicate::str::is_empty());
+    Command::new(assert_cmd::cargo::cargo_bin!("pie-control"))
+        .arg("-vv")
+        .args(&args)
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("DEBUG").and(predicate::str::contains("loaded env from")));
+}
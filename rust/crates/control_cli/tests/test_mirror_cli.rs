@@ -0,0 +1,195 @@
+use assert_cmd::prelude::*;
+use pie_episodes::{Episode, EpisodeStore, RunId, TickId};
+use serde_json::json;
+use std::fs;
+use std::process::Command;
+use tempfile::TempDir;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn seed_store(repo: &TempDir, n: u64) {
+    let store = EpisodeStore::new(repo.path().to_path_buf());
+    for tick in 0..n {
+        let ep = Episode::new(RunId("run_demo".into()), TickId(tick), "main", vec![], "t", "s", vec![], 0.0).unwrap();
+        store.append(&ep).unwrap();
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn episode_mirror_all_mirrors_every_episode() {
+    let repo = TempDir::new().unwrap();
+    seed_store(&repo, 2);
+    let audit = repo.path().join("runtime").join("logs").join("audit_rust.jsonl");
+    fs::create_dir_all(audit.parent().unwrap()).unwrap();
+
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/memory/add"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "id": "mem" })))
+        .expect(2)
+        .mount(&server)
+        .await;
+
+    let out = Command::new(assert_cmd::cargo::cargo_bin!("pie-control"))
+        .args([
+            "episode-mirror-all",
+            "--repo-root",
+            repo.path().to_str().unwrap(),
+            "--audit-log",
+            audit.to_str().unwrap(),
+            "--base-url",
+            &server.uri(),
+            "--api-key",
+            "k",
+        ])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let summary: serde_json::Value = serde_json::from_slice(&out).unwrap();
+    assert_eq!(summary["mirrored"], 2);
+    assert_eq!(summary["failed"], 0);
+
+    let log = fs::read_to_string(&audit).unwrap();
+    assert_eq!(log.matches("\"EpisodeMirrorAttempted\"").count(), 2);
+    assert_eq!(log.matches("\"EpisodeMirrored\"").count(), 2);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn episode_mirror_twice_adds_remote_memory_once() {
+    let repo = TempDir::new().unwrap();
+    seed_store(&repo, 1);
+    let store = EpisodeStore::new(repo.path().to_path_buf());
+    let episode_id = store.load_index().unwrap().entries[0].episode_id.to_string();
+    let audit = repo.path().join("audit.jsonl");
+
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/memory/add"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "id": "mem_1" })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let mirror = || {
+        Command::new(assert_cmd::cargo::cargo_bin!("pie-control"))
+            .args([
+                "episode-mirror",
+                "--repo-root",
+                repo.path().to_str().unwrap(),
+                "--episode-id",
+                &episode_id,
+                "--audit-log",
+                audit.to_str().unwrap(),
+                "--base-url",
+                &server.uri(),
+            ])
+            .assert()
+            .success()
+            .get_output()
+            .stdout
+            .clone()
+    };
+    let first: serde_json::Value = serde_json::from_slice(&mirror()).unwrap();
+    assert_eq!(first["remote_id"], "mem_1");
+    let second: serde_json::Value = serde_json::from_slice(&mirror()).unwrap();
+    assert_eq!(second["status"], "AlreadyMirrored");
+    assert_eq!(second["remote_id"], "mem_1");
+
+    let state = pie_openmemory_mirror::MirrorState::load(&pie_openmemory_mirror::MirrorState::path_for(&store)).unwrap();
+    assert_eq!(state.episodes.len(), 1);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn episode_delete_with_unmirror_removes_remote_memory() {
+    let repo = TempDir::new().unwrap();
+    seed_store(&repo, 1);
+    let store = EpisodeStore::new(repo.path().to_path_buf());
+    let episode_id = store.load_index().unwrap().entries[0].episode_id.to_string();
+    let audit = repo.path().join("audit.jsonl");
+
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/memory/add"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "id": "mem_1" })))
+        .mount(&server)
+        .await;
+    Mock::given(method("DELETE"))
+        .and(path("/memory/mem_1"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let root = repo.path().to_str().unwrap();
+    let pie_control = assert_cmd::cargo::cargo_bin!("pie-control");
+    Command::new(pie_control)
+        .args(["episode-mirror", "--repo-root", root, "--episode-id", &episode_id])
+        .args(["--audit-log", audit.to_str().unwrap(), "--base-url", &server.uri()])
+        .assert()
+        .success();
+    let out = Command::new(pie_control)
+        .args(["episode-delete", "--repo-root", root, "--episode-id", &episode_id, "--reason", "gdpr request"])
+        .args(["--audit-log", audit.to_str().unwrap(), "--unmirror", "--base-url", &server.uri()])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let deleted: serde_json::Value = serde_json::from_slice(&out).unwrap();
+    assert_eq!(deleted["unmirrored_remote_id"], "mem_1");
+
+    let state = pie_openmemory_mirror::MirrorState::load(&pie_openmemory_mirror::MirrorState::path_for(&store)).unwrap();
+    assert!(state.episodes.is_empty());
+    assert!(fs::read_to_string(&audit).unwrap().contains("\"unmirrored_remote_id\":\"mem_1\""));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn episode_mirror_all_skips_superseded_and_does_not_record_failures() {
+    let repo = TempDir::new().unwrap();
+    seed_store(&repo, 2);
+    let store = EpisodeStore::new(repo.path().to_path_buf());
+    let old_id = store.load_index().unwrap().entries[0].episode_id;
+    let newer = Episode::new(RunId("run_demo".into()), TickId(2), "main", vec![], "t2", "s2", vec![], 0.0).unwrap();
+    store.supersede(old_id, newer).unwrap();
+    let audit = repo.path().join("audit.jsonl");
+    let state_path = pie_openmemory_mirror::MirrorState::path_for(&store);
+
+    let mirror_all = |uri: &str, extra: &[&str]| -> serde_json::Value {
+        let out = Command::new(assert_cmd::cargo::cargo_bin!("pie-control"))
+            .args(["episode-mirror-all", "--repo-root", repo.path().to_str().unwrap()])
+            .args(["--audit-log", audit.to_str().unwrap(), "--base-url", uri, "--api-key", "k"])
+            .args(extra)
+            .assert()
+            .success()
+            .get_output()
+            .stdout
+            .clone();
+        serde_json::from_slice(&out).unwrap()
+    };
+    let counts = |s: &serde_json::Value| (s["mirrored"].clone(), s["failed"].clone(), s["skipped"].clone());
+
+    // A rejected add is a failure and leaves no mirror state behind.
+    let rejecting = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/memory/add"))
+        .respond_with(ResponseTemplate::new(400).set_body_json(json!({ "error": "bad" })))
+        .expect(2)
+        .mount(&rejecting)
+        .await;
+    assert_eq!(counts(&mirror_all(&rejecting.uri(), &[])), (json!(0), json!(2), json!(0)));
+    assert!(pie_openmemory_mirror::MirrorState::load(&state_path).unwrap().episodes.is_empty());
+
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/memory/add"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "id": "mem" })))
+        .expect(3)
+        .mount(&server)
+        .await;
+    // The superseded version is left out by default...
+    assert_eq!(counts(&mirror_all(&server.uri(), &[])), (json!(2), json!(0), json!(0)));
+    // ...and mirrored only on request; the current two are already there.
+    assert_eq!(counts(&mirror_all(&server.uri(), &["--include-superseded"])), (json!(1), json!(0), json!(2)));
+}
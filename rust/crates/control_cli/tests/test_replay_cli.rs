@@ -0,0 +1,795 @@
+use assert_cmd::prelude::*;
+use predicates::prelude::*;
+use serde_json::json;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tempfile::TempDir;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn write_model_request(dir: &Path, provider: &str, temperature: f64) -> PathBuf {
+    let p = dir.join("model_request.json");
+    let body = json!({
+        "schema_version": 1,
+        "run_id": "run_replay",
+        "tick_id": 1,
+        "role": "planner",
+        "provider": provider,
+        "model": "gpt",
+        "prompt": {
+            "format": "chat",
+            "messages": [{ "role": "user", "content": "hello" }],
+            "max_output_tokens": 64,
+            "temperature": temperature,
+            "top_p": 1.0,
+            "stop": []
+        },
+        "context": { "working_memory": { "secret": "dont leak" } }
+    });
+    fs::write(&p, body.to_string()).unwrap();
+    p
+}
+
+/// redact-only; returns (call_dir, audit_log).
+fn redact(repo: &TempDir) -> (PathBuf, PathBuf) {
+    redact_with_temperature(repo, 0.2)
+}
+
+fn redact_with_temperature(repo: &TempDir, temperature: f64) -> (PathBuf, PathBuf) {
+    redact_request(repo, "openai", temperature)
+}
+
+fn redact_request(repo: &TempDir, provider: &str, temperature: f64) -> (PathBuf, PathBuf) {
+    let root = repo.path();
+    let audit = root.join("runtime").join("logs").join("audit_rust.jsonl");
+    fs::create_dir_all(audit.parent().unwrap()).unwrap();
+    let req = write_model_request(root, provider, temperature);
+    let pie_control = assert_cmd::cargo::cargo_bin!("pie-control");
+
+    let out = Command::new(pie_control)
+        .args(["redact-only", "--repo-root", root.to_str().unwrap(), "--request-json", req.to_str().unwrap()])
+        .args(["--audit-log", audit.to_str().unwrap()])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let redacted: serde_json::Value = serde_json::from_slice(&out).unwrap();
+    let call_dir = root
+        .join("runtime")
+        .join("artifacts")
+        .join("models")
+        .join("run_replay")
+        .join(redacted["call_id"].as_str().unwrap());
+    (call_dir, audit)
+}
+
+/// redact-only + dispatch-dir against a mock provider; returns (call_dir, audit_log).
+async fn redact_and_dispatch(repo: &TempDir) -> (PathBuf, PathBuf) {
+    let root = repo.path();
+    let (call_dir, audit) = redact(repo);
+    let pie_control = assert_cmd::cargo::cargo_bin!("pie-control");
+
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": "chatcmpl-1",
+            "choices": [{ "message": { "role": "assistant", "content": "hi" }, "finish_reason": "stop" }],
+            "usage": { "prompt_tokens": 3, "completion_tokens": 1 }
+        })))
+        .mount(&server)
+        .await;
+
+    Command::new(pie_control)
+        .args(["dispatch-dir", "--repo-root", root.to_str().unwrap(), "--call-dir", call_dir.to_str().unwrap()])
+        .args(["--audit-log", audit.to_str().unwrap(), "--base-url", &server.uri(), "--api-key", "k"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"status\":\"Ok\""));
+
+    (call_dir, audit)
+}
+
+fn replay(repo: &TempDir, call_dir: &Path, audit: &Path) -> assert_cmd::assert::Assert {
+    Command::new(assert_cmd::cargo::cargo_bin!("pie-control"))
+        .args(["replay", "--repo-root", repo.path().to_str().unwrap()])
+        .args(["--call-dir", call_dir.to_str().unwrap(), "--audit-log", audit.to_str().unwrap()])
+        .assert()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn replay_passes_for_untouched_call_dir() {
+    let repo = TempDir::new().unwrap();
+    let (call_dir, audit) = redact_and_dispatch(&repo).await;
+
+    let out = replay(&repo, &call_dir, &audit).success().get_output().stdout.clone();
+    let report: serde_json::Value = serde_json::from_slice(&out).unwrap();
+    assert_eq!(report["ok"], true);
+    let artifacts = report["artifacts"].as_array().unwrap();
+    assert_eq!(artifacts.len(), 5);
+    assert!(artifacts.iter().all(|a| a["ok"] == true && a["actual"] == a["expected"]));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn replay_fails_after_artifact_is_tampered() {
+    let repo = TempDir::new().unwrap();
+    let (call_dir, audit) = redact_and_dispatch(&repo).await;
+
+    let norm = call_dir.join("reply_normalized.json");
+    let tampered = fs::read_to_string(&norm).unwrap().replace("\"hi\"", "\"bye\"");
+    fs::write(&norm, tampered).unwrap();
+
+    let out = replay(&repo, &call_dir, &audit)
+        .failure()
+        .stderr(predicate::str::contains("replay failed: 1 artifact(s)"))
+        .get_output()
+        .stdout
+        .clone();
+    let report: serde_json::Value = serde_json::from_slice(&out).unwrap();
+    assert_eq!(report["ok"], false);
+    for a in report["artifacts"].as_array().unwrap() {
+        assert_eq!(a["ok"], a["artifact"] != "reply_normalized.json", "{a}");
+    }
+}
+
+#[test]
+fn dry_run_dispatch_writes_artifacts_and_keeps_chain_valid() {
+    let repo = TempDir::new().unwrap();
+    let (call_dir, audit) = redact(&repo);
+    let pie_control = assert_cmd::cargo::cargo_bin!("pie-control");
+
+    // No base URL or key: nothing is contacted.
+    Command::new(pie_control)
+        .env_remove("OPENAI_BASE_URL")
+        .env_remove("OPENAI_API_KEY")
+        .args(["dispatch-dir", "--repo-root", repo.path().to_str().unwrap(), "--call-dir", call_dir.to_str().unwrap()])
+        .args(["--audit-log", audit.to_str().unwrap(), "--dry-run"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"status\":\"Ok\""));
+
+    let raw = fs::read_to_string(call_dir.join("response_raw.json")).unwrap();
+    assert!(raw.contains("\"id\":\"dry-run:sha256:"), "{raw}");
+    assert!(call_dir.join("reply_normalized.json").exists());
+
+    let log = fs::read_to_string(&audit).unwrap();
+    assert!(log.contains("\"ModelCallDispatched\"") && log.contains("\"ModelCallCompleted\""));
+    Command::new(pie_control).args(["verify-audit", "--audit-log", audit.to_str().unwrap()]).assert().success();
+    replay(&repo, &call_dir, &audit).success();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn run_call_emits_all_four_events_on_one_verifiable_chain() {
+    let repo = TempDir::new().unwrap();
+    let root = repo.path();
+    let audit = root.join("runtime").join("logs").join("audit_rust.jsonl");
+    fs::create_dir_all(audit.parent().unwrap()).unwrap();
+    let req = write_model_request(root, "openai", 0.2);
+
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": "chatcmpl-1",
+            "choices": [{ "message": { "role": "assistant", "content": "hi" }, "finish_reason": "stop" }]
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let pie_control = assert_cmd::cargo::cargo_bin!("pie-control");
+    let out = Command::new(pie_control)
+        .args(["run-call", "--repo-root", root.to_str().unwrap(), "--request-json", req.to_str().unwrap()])
+        .args(["--audit-log", audit.to_str().unwrap(), "--base-url", &server.uri(), "--api-key", "k"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let result: serde_json::Value = serde_json::from_slice(&out).unwrap();
+    assert_eq!(result["status"], "Ok");
+
+    let log = fs::read_to_string(&audit).unwrap();
+    let types: Vec<String> = log
+        .lines()
+        .map(|l| serde_json::from_str::<serde_json::Value>(l).unwrap()["event"]["event_type"].as_str().unwrap().to_string())
+        .collect();
+    assert_eq!(types, ["ModelCallPrepared", "ModelRequestRedacted", "ModelCallDispatched", "ModelCallCompleted"]);
+    Command::new(pie_control).args(["verify-audit", "--audit-log", audit.to_str().unwrap()]).assert().success();
+
+    let call_dir = root
+        .join("runtime")
+        .join("artifacts")
+        .join("models")
+        .join("run_replay")
+        .join(result["call_id"].as_str().unwrap());
+    replay(&repo, &call_dir, &audit).success();
+}
+
+#[test]
+fn redact_only_reads_model_request_from_stdin() {
+    let repo = TempDir::new().unwrap();
+    let root = repo.path();
+    let audit = root.join("audit.jsonl");
+    let req = fs::read_to_string(write_model_request(root, "openai", 0.2)).unwrap();
+
+    assert_cmd::Command::new(assert_cmd::cargo::cargo_bin!("pie-control"))
+        .args(["redact-only", "--repo-root", root.to_str().unwrap(), "--request-json", "-"])
+        .args(["--audit-log", audit.to_str().unwrap()])
+        .write_stdin(req)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"post_hash\":\"sha256:"));
+    assert!(fs::read_to_string(&audit).unwrap().contains("\"ModelRequestRedacted\""));
+}
+
+#[test]
+fn call_diff_flags_the_diverging_request_field() {
+    let (left_repo, right_repo) = (TempDir::new().unwrap(), TempDir::new().unwrap());
+    let (left, _) = redact_with_temperature(&left_repo, 0.2);
+    let (right, _) = redact_with_temperature(&right_repo, 0.7);
+    let pie_control = assert_cmd::cargo::cargo_bin!("pie-control");
+
+    let out = Command::new(pie_control)
+        .args(["call-diff", "--left", left.to_str().unwrap(), "--right", right.to_str().unwrap()])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let report: serde_json::Value = serde_json::from_slice(&out).unwrap();
+    assert_eq!(report["identical"], false);
+    let same: Vec<_> = report["artifacts"].as_array().unwrap().iter().map(|a| a["same"].clone()).collect();
+    // request differs; no replies yet (both missing); transform logs match
+    assert_eq!(same, [json!(false), json!(true), json!(true)]);
+
+    let paths: Vec<_> = report["request_diff"].as_array().unwrap().iter().map(|d| d["path"].clone()).collect();
+    // Besides the temperature itself, only the integrity hashes derived from it differ.
+    assert_eq!(paths, [json!("integrity.post_hash"), json!("integrity.pre_hash"), json!("prompt.temperature")]);
+    assert_eq!(report["request_diff"][2]["left"], 0.2);
+    assert_eq!(report["request_diff"][2]["right"], 0.7);
+
+    Command::new(pie_control)
+        .args(["call-diff", "--left", left.to_str().unwrap(), "--right", left.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"identical\":true"))
+        .stdout(predicate::str::contains("\"request_diff\":[]"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn dispatch_stream_prints_deltas_and_writes_aggregated_artifacts() {
+    let repo = TempDir::new().unwrap();
+    let (call_dir, audit) = redact(&repo);
+    let call_id = call_dir.file_name().unwrap().to_str().unwrap().to_string();
+
+    let server = MockServer::start().await;
+    let sse = concat!(
+        "data: {\"id\":\"chatcmpl-s\",\"choices\":[{\"index\":0,\"delta\":{\"role\":\"assistant\",\"content\":\"\"}}]}\n\n",
+        "data: {\"id\":\"chatcmpl-s\",\"choices\":[{\"index\":0,\"delta\":{\"content\":\"str\"}}]}\n\n",
+        "data: {\"id\":\"chatcmpl-s\",\"choices\":[{\"index\":0,\"delta\":{\"content\":\"eamed\"},\"finish_reason\":\"stop\"}]}\n\n",
+        "data: [DONE]\n\n",
+    );
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(sse, "text/event-stream"))
+        .mount(&server)
+        .await;
+
+    let out = Command::new(assert_cmd::cargo::cargo_bin!("pie-control"))
+        .args(["dispatch", "--stream", "--repo-root", repo.path().to_str().unwrap()])
+        .args(["--sanitized-json", call_dir.join("request_post.json").to_str().unwrap(), "--call-id", &call_id])
+        .args(["--audit-log", audit.to_str().unwrap(), "--base-url", &server.uri(), "--api-key", "k"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let stdout = String::from_utf8(out).unwrap();
+    let (text, summary) = stdout.split_once('\n').unwrap();
+    assert_eq!(text, "streamed");
+    assert!(summary.contains("\"status\":\"Ok\""), "{summary}");
+
+    let norm: serde_json::Value = serde_json::from_slice(&fs::read(call_dir.join("reply_normalized.json")).unwrap()).unwrap();
+    assert_eq!(norm["content"], "streamed");
+    assert_eq!(norm["finish_reason"], "stop");
+
+    let log = fs::read_to_string(&audit).unwrap();
+    let last: serde_json::Value = serde_json::from_str(log.lines().last().unwrap()).unwrap();
+    assert_eq!(last["event"]["event_type"], "ModelStreamCompleted");
+    assert_eq!(last["event"]["delta_count"], 2);
+    assert_eq!(last["event"]["streamed_bytes"], 8);
+
+    replay(&repo, &call_dir, &audit).success();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn slow_provider_is_recorded_as_timeout_for_deadline_and_client_timeout() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_delay(std::time::Duration::from_secs(3))
+                .set_body_json(json!({ "choices": [{ "message": { "content": "late" }, "finish_reason": "stop" }] })),
+        )
+        .mount(&server)
+        .await;
+
+    for flag in ["--deadline-ms", "--timeout-ms"] {
+        let repo = TempDir::new().unwrap();
+        let (call_dir, audit) = redact(&repo);
+        let started = std::time::Instant::now();
+        Command::new(assert_cmd::cargo::cargo_bin!("pie-control"))
+            .args(["dispatch-dir", "--repo-root", repo.path().to_str().unwrap(), "--call-dir", call_dir.to_str().unwrap()])
+            .args(["--audit-log", audit.to_str().unwrap(), "--base-url", &server.uri(), "--api-key", "k", flag, "200"])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("\"status\":\"Timeout\""));
+        assert!(started.elapsed() < std::time::Duration::from_secs(3), "{flag} did not cut the call short");
+
+        let log = fs::read_to_string(&audit).unwrap();
+        let completed: serde_json::Value = serde_json::from_str(log.lines().last().unwrap()).unwrap();
+        assert_eq!(completed["event"]["event_type"], "ModelCallCompleted");
+        assert_eq!(completed["event"]["result"]["status"], "timeout", "{flag}");
+        replay(&repo, &call_dir, &audit).success();
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn provider_flag_routes_through_the_factory_and_guards_the_declared_provider() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/messages"))
+        .and(wiremock::matchers::header("x-api-key", "k"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": "msg_01",
+            "content": [{ "type": "text", "text": "hi" }],
+            "stop_reason": "end_turn",
+            "usage": { "input_tokens": 3, "output_tokens": 1 }
+        })))
+        .mount(&server)
+        .await;
+    let dispatch = |repo: &TempDir, call_dir: &Path, audit: &Path, extra: &[&str]| {
+        Command::new(assert_cmd::cargo::cargo_bin!("pie-control"))
+            .args(["dispatch-dir", "--repo-root", repo.path().to_str().unwrap(), "--call-dir", call_dir.to_str().unwrap()])
+            .args(["--audit-log", audit.to_str().unwrap(), "--base-url", &server.uri(), "--api-key", "k"])
+            .args(extra)
+            .assert()
+    };
+    let dispatched_provider = |audit: &Path| {
+        let log = fs::read_to_string(audit).unwrap();
+        log.lines()
+            .map(|l| serde_json::from_str::<serde_json::Value>(l).unwrap())
+            .find(|r| r["event"]["event_type"] == "ModelCallDispatched")
+            .map(|r| r["event"]["provider"].clone())
+            .unwrap()
+    };
+
+    // `auto` (the default) and an agreeing `--provider` both dispatch to the declared Anthropic provider.
+    for extra in [&[][..], &["--provider", "anthropic"][..]] {
+        let repo = TempDir::new().unwrap();
+        let (call_dir, audit) = redact_request(&repo, "anthropic", 0.2);
+        dispatch(&repo, &call_dir, &audit, extra).success().stdout(predicate::str::contains("\"status\":\"Ok\""));
+        assert_eq!(dispatched_provider(&audit), "anthropic");
+        replay(&repo, &call_dir, &audit).success();
+    }
+
+    let repo = TempDir::new().unwrap();
+    let (call_dir, audit) = redact_request(&repo, "anthropic", 0.2);
+    dispatch(&repo, &call_dir, &audit, &["--provider", "openai"])
+        .code(3)
+        .stderr(predicate::str::contains("conflicts with the request's declared provider anthropic"));
+    assert!(!fs::read_to_string(&audit).unwrap().contains("ModelCallDispatched"));
+
+    // Forced onto OpenAI, the call hits /v1/chat/completions (unmounted here) and is recorded as such.
+    dispatch(&repo, &call_dir, &audit, &["--provider", "openai", "--force-provider"]).success();
+    assert_eq!(dispatched_provider(&audit), "openai");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn gemini_dispatch_without_base_url_ignores_the_openai_endpoint_and_key() {
+    let server = MockServer::start().await;
+    Mock::given(wiremock::matchers::any()).respond_with(ResponseTemplate::new(500)).expect(0).mount(&server).await;
+
+    let repo = TempDir::new().unwrap();
+    let (call_dir, audit) = redact_request(&repo, "gemini", 0.2);
+    Command::new(assert_cmd::cargo::cargo_bin!("pie-control"))
+        .env("OPENAI_BASE_URL", server.uri())
+        .env("OPENAI_API_KEY", "sk-openai")
+        .env_remove("GEMINI_API_KEY")
+        .env_remove("GOOGLE_API_KEY")
+        .args(["dispatch-dir", "--repo-root", repo.path().to_str().unwrap(), "--call-dir", call_dir.to_str().unwrap()])
+        .args(["--audit-log", audit.to_str().unwrap()])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("GEMINI_API_KEY or GOOGLE_API_KEY is required for https://generativelanguage.googleapis.com"));
+    assert!(!fs::read_to_string(&audit).unwrap().contains("ModelCallDispatched"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn dispatch_retries_a_rate_limited_call_and_links_the_attempt_in_the_audit_log() {
+    let repo = TempDir::new().unwrap();
+    let (call_dir, audit) = redact(&repo);
+    let call_id = call_dir.file_name().unwrap().to_str().unwrap().to_string();
+
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "1"))
+        .up_to_n_times(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": "chatcmpl-2",
+            "choices": [{ "message": { "role": "assistant", "content": "second time" }, "finish_reason": "stop" }]
+        })))
+        .mount(&server)
+        .await;
+
+    Command::new(assert_cmd::cargo::cargo_bin!("pie-control"))
+        .args(["dispatch", "--repo-root", repo.path().to_str().unwrap(), "--max-retries", "2", "--retry-backoff-ms", "10"])
+        .args(["--sanitized-json", call_dir.join("request_post.json").to_str().unwrap(), "--call-id", &call_id])
+        .args(["--audit-log", audit.to_str().unwrap(), "--base-url", &server.uri(), "--api-key", "k"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"status\":\"Ok\"").and(predicate::str::contains("\"attempts\":2")));
+
+    let events: Vec<serde_json::Value> = fs::read_to_string(&audit)
+        .unwrap()
+        .lines()
+        .map(|l| serde_json::from_str::<serde_json::Value>(l).unwrap()["event"].clone())
+        .collect();
+    let types: Vec<&str> = events.iter().map(|e| e["event_type"].as_str().unwrap()).collect();
+    assert_eq!(types[types.len() - 3..], ["ModelCallDispatched", "ModelCallRetried", "ModelCallCompleted"]);
+    let retried = &events[events.len() - 2];
+    assert_eq!(retried["model_call"], call_id);
+    assert_eq!(retried["attempt"], 1);
+    assert_eq!(retried["status"], "rate_limited");
+    // Retry-After (1s) beats --retry-backoff-ms.
+    assert_eq!(retried["wait_ms"], 1000);
+    assert_eq!(events.last().unwrap()["result"]["status"], "ok");
+
+    replay(&repo, &call_dir, &audit).success();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn endpoint_fingerprint_command_matches_the_dispatched_event() {
+    let repo = TempDir::new().unwrap();
+    let (call_dir, audit) = redact(&repo);
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "choices": [{ "message": { "role": "assistant", "content": "hi" }, "finish_reason": "stop" }]
+        })))
+        .mount(&server)
+        .await;
+    Command::new(assert_cmd::cargo::cargo_bin!("pie-control"))
+        .args(["dispatch-dir", "--repo-root", repo.path().to_str().unwrap(), "--call-dir", call_dir.to_str().unwrap()])
+        .args(["--audit-log", audit.to_str().unwrap(), "--base-url", &server.uri(), "--api-key", "k"])
+        .assert()
+        .success();
+
+    let log = fs::read_to_string(&audit).unwrap();
+    let dispatched = log
+        .lines()
+        .map(|l| serde_json::from_str::<serde_json::Value>(l).unwrap()["event"].clone())
+        .find(|e| e["event_type"] == "ModelCallDispatched")
+        .unwrap();
+    let out = Command::new(assert_cmd::cargo::cargo_bin!("pie-control"))
+        .args(["endpoint-fingerprint", "--provider", "openai", "--base-url", &server.uri(), "--model", "gpt"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    assert_eq!(String::from_utf8(out).unwrap().trim(), dispatched["endpoint_fingerprint"]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn dispatched_event_hashes_the_wire_body_and_the_sanitized_request() {
+    let repo = TempDir::new().unwrap();
+    let (call_dir, audit) = redact(&repo);
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "choices": [{ "message": { "role": "assistant", "content": "hi" }, "finish_reason": "stop" }]
+        })))
+        .mount(&server)
+        .await;
+    Command::new(assert_cmd::cargo::cargo_bin!("pie-control"))
+        .args(["dispatch-dir", "--repo-root", repo.path().to_str().unwrap(), "--call-dir", call_dir.to_str().unwrap()])
+        .args(["--audit-log", audit.to_str().unwrap(), "--base-url", &server.uri(), "--api-key", "k"])
+        .assert()
+        .success();
+
+    let log = fs::read_to_string(&audit).unwrap();
+    let dispatched = log
+        .lines()
+        .map(|l| serde_json::from_str::<serde_json::Value>(l).unwrap()["event"].clone())
+        .find(|e| e["event_type"] == "ModelCallDispatched")
+        .unwrap();
+    // The bytes the provider received are the ones the audit record vouches for...
+    let sent = server.received_requests().await.unwrap().remove(0).body;
+    assert_eq!(dispatched["request_body_hash"], pie_common::sha256_bytes(&sent));
+    // ...and the record's post hash is that of the sanitized request they were built from.
+    let post: pie_redaction::SanitizedModelRequest =
+        serde_json::from_slice(&fs::read(call_dir.join("request_post.json")).unwrap()).unwrap();
+    assert_eq!(dispatched["request_post_hash"], post.integrity.post_hash);
+    assert_eq!(post.compute_post_hash().unwrap(), post.integrity.post_hash);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn dispatch_resume_redispatches_only_the_failed_call() {
+    let repo = TempDir::new().unwrap();
+    let (failed_dir, audit) = redact(&repo);
+    let (ok_dir, _) = redact(&repo);
+    let dispatch_dir = |call_dir: &Path, base_url: &str| {
+        Command::new(assert_cmd::cargo::cargo_bin!("pie-control"))
+            .args(["dispatch-dir", "--repo-root", repo.path().to_str().unwrap(), "--call-dir", call_dir.to_str().unwrap()])
+            .args(["--audit-log", audit.to_str().unwrap(), "--base-url", base_url, "--api-key", "k"])
+            .assert()
+            .success()
+    };
+
+    let flaky = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .respond_with(ResponseTemplate::new(500).set_body_string("upstream down"))
+        .mount(&flaky)
+        .await;
+    let healthy = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "choices": [{ "message": { "role": "assistant", "content": "recovered" }, "finish_reason": "stop" }]
+        })))
+        .mount(&healthy)
+        .await;
+    dispatch_dir(&failed_dir, &flaky.uri()).stdout(predicate::str::contains("\"status\":\"Error\""));
+    dispatch_dir(&ok_dir, &healthy.uri()).stdout(predicate::str::contains("\"status\":\"Ok\""));
+
+    let resume = |base_url: &str| -> serde_json::Value {
+        let out = Command::new(assert_cmd::cargo::cargo_bin!("pie-control"))
+            .args(["dispatch-resume", "--repo-root", repo.path().to_str().unwrap(), "--audit-log", audit.to_str().unwrap()])
+            .args(["--base-url", base_url, "--api-key", "k"])
+            .assert()
+            .success()
+            .get_output()
+            .stdout
+            .clone();
+        serde_json::from_slice(&out).unwrap()
+    };
+    let events = || -> Vec<serde_json::Value> {
+        fs::read_to_string(&audit)
+            .unwrap()
+            .lines()
+            .map(|l| serde_json::from_str::<serde_json::Value>(l).unwrap()["event"].clone())
+            .collect()
+    };
+
+    // A resume that fails again is one more attempt, not two.
+    assert_eq!(resume(&flaky.uri())["resumed"][0]["status"], "Error");
+    let retried: Vec<serde_json::Value> =
+        events().into_iter().filter(|e| e["event_type"] == "ModelCallRetried").collect();
+    assert_eq!(retried.len(), 1);
+    assert_eq!(retried[0]["attempt"], 1);
+
+    let report = resume(&healthy.uri());
+    let failed_id = failed_dir.file_name().unwrap().to_str().unwrap();
+    assert_eq!(
+        report["resumed"],
+        json!([{
+            "call_id": failed_id,
+            "previous_status": "Error",
+            "status": "Ok",
+            "response_hash": report["resumed"][0]["response_hash"],
+        }])
+    );
+
+    let events = events();
+    let tail: Vec<&str> = events[events.len() - 3..].iter().map(|e| e["event_type"].as_str().unwrap()).collect();
+    assert_eq!(tail, ["ModelCallRetried", "ModelCallDispatched", "ModelCallCompleted"]);
+    assert_eq!(events[events.len() - 3]["attempt"], 2);
+    assert_eq!(events[events.len() - 3]["status"], "error");
+    replay(&repo, &failed_dir, &audit).success();
+
+    // Nothing is left to resume.
+    Command::new(assert_cmd::cargo::cargo_bin!("pie-control"))
+        .args(["dispatch-resume", "--repo-root", repo.path().to_str().unwrap(), "--audit-log", audit.to_str().unwrap()])
+        .args(["--base-url", &healthy.uri(), "--api-key", "k"])
+        .assert()
+        .success()
+        .stdout("{\"resumed\":[]}\n");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn dispatch_resume_keeps_a_forced_provider() {
+    let repo = TempDir::new().unwrap();
+    let (call_dir, audit) = redact_request(&repo, "anthropic", 0.2);
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .respond_with(ResponseTemplate::new(500))
+        .up_to_n_times(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "choices": [{ "message": { "role": "assistant", "content": "ok" }, "finish_reason": "stop" }]
+        })))
+        .mount(&server)
+        .await;
+
+    Command::new(assert_cmd::cargo::cargo_bin!("pie-control"))
+        .args(["dispatch-dir", "--repo-root", repo.path().to_str().unwrap(), "--call-dir", call_dir.to_str().unwrap()])
+        .args(["--audit-log", audit.to_str().unwrap(), "--base-url", &server.uri(), "--api-key", "k"])
+        .args(["--provider", "openai", "--force-provider"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"status\":\"Error\""));
+
+    // The declared provider is anthropic, but the resume goes back to OpenAI's /v1/chat/completions.
+    Command::new(assert_cmd::cargo::cargo_bin!("pie-control"))
+        .args(["dispatch-resume", "--repo-root", repo.path().to_str().unwrap(), "--audit-log", audit.to_str().unwrap()])
+        .args(["--base-url", &server.uri(), "--api-key", "k"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"status\":\"Ok\""));
+    let dispatched: Vec<serde_json::Value> = fs::read_to_string(&audit)
+        .unwrap()
+        .lines()
+        .map(|l| serde_json::from_str::<serde_json::Value>(l).unwrap()["event"].clone())
+        .filter(|e| e["event_type"] == "ModelCallDispatched")
+        .collect();
+    assert_eq!(dispatched.len(), 2);
+    assert!(dispatched.iter().all(|e| e["provider"] == "openai"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn dispatch_prices_reported_usage_into_output_and_completion_event() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "choices": [{ "message": { "role": "assistant", "content": "hi" }, "finish_reason": "stop" }],
+            "usage": { "prompt_tokens": 1000, "completion_tokens": 500 }
+        })))
+        .mount(&server)
+        .await;
+
+    let prices_dir = TempDir::new().unwrap();
+    let prices = prices_dir.path().join("prices.json");
+    fs::write(&prices, json!({ "gpt": { "input_per_1k": 0.5, "output_per_1k": 1.5 } }).to_string()).unwrap();
+
+    let cases: [(&[&str], f64); 2] = [
+        (&["--prices-file", prices.to_str().unwrap()], 1.25),
+        // Explicit flags win over the table.
+        (&["--prices-file", prices.to_str().unwrap(), "--price-input-per-1k", "1", "--price-output-per-1k", "2"], 2.0),
+    ];
+    for (extra, cost) in cases {
+        let repo = TempDir::new().unwrap();
+        let (call_dir, audit) = redact(&repo);
+        let out = Command::new(assert_cmd::cargo::cargo_bin!("pie-control"))
+            .args(["dispatch-dir", "--repo-root", repo.path().to_str().unwrap(), "--call-dir", call_dir.to_str().unwrap()])
+            .args(["--audit-log", audit.to_str().unwrap(), "--base-url", &server.uri(), "--api-key", "k"])
+            .args(extra)
+            .assert()
+            .success()
+            .get_output()
+            .stdout
+            .clone();
+        let summary: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!(summary["usage"], json!({ "input_tokens": 1000, "output_tokens": 500 }));
+        assert_eq!(summary["estimated_cost_usd"], cost);
+
+        let log = fs::read_to_string(&audit).unwrap();
+        let completed: serde_json::Value = serde_json::from_str(log.lines().last().unwrap()).unwrap();
+        assert_eq!(completed["event"]["result"]["input_tokens"], 1000);
+        assert_eq!(completed["event"]["result"]["estimated_cost_usd"], cost);
+        replay(&repo, &call_dir, &audit).success();
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn cached_dispatch_serves_an_identical_request_without_calling_the_provider() {
+    let repo = TempDir::new().unwrap();
+    let (call_dir, audit) = redact(&repo);
+
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": "chatcmpl-c",
+            "choices": [{ "message": { "role": "assistant", "content": "cached" }, "finish_reason": "stop" }],
+            "usage": { "prompt_tokens": 3, "completion_tokens": 1 }
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let dispatch_to = |base_url: &str| -> serde_json::Value {
+        let out = Command::new(assert_cmd::cargo::cargo_bin!("pie-control"))
+            .args(["dispatch-dir", "--repo-root", repo.path().to_str().unwrap(), "--cache"])
+            .args(["--call-dir", call_dir.to_str().unwrap(), "--audit-log", audit.to_str().unwrap()])
+            .args(["--base-url", base_url, "--api-key", "k"])
+            .assert()
+            .success()
+            .get_output()
+            .stdout
+            .clone();
+        serde_json::from_slice(&out).unwrap()
+    };
+    let dispatch = || dispatch_to(&server.uri());
+
+    let first = dispatch();
+    assert_eq!(first["cache_hit"], false);
+    assert_eq!(first["attempts"], 1);
+    let second = dispatch();
+    assert_eq!(second["cache_hit"], true);
+    assert_eq!(second["attempts"], 0);
+    assert_eq!(second["response_hash"], first["response_hash"]);
+    assert!(second["usage"]["input_tokens"].is_null());
+
+    let completions: Vec<serde_json::Value> = fs::read_to_string(&audit)
+        .unwrap()
+        .lines()
+        .map(|l| serde_json::from_str::<serde_json::Value>(l).unwrap()["event"].clone())
+        .filter(|e| e["event_type"] == "ModelCallCompleted")
+        .collect();
+    assert_eq!(completions.len(), 2);
+    assert!(completions[0]["result"].get("cache_hit").is_none());
+    assert_eq!(completions[1]["result"]["cache_hit"], true);
+    assert_eq!(completions[1]["result"]["status"], "ok");
+    assert!(repo.path().join("runtime").join("cache").read_dir().unwrap().next().is_some());
+    // The second dispatch made no provider call.
+    server.verify().await;
+
+    // The same request re-routed to another endpoint is a miss: the cache key includes the endpoint.
+    let other = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": "chatcmpl-o",
+            "choices": [{ "message": { "role": "assistant", "content": "other" }, "finish_reason": "stop" }]
+        })))
+        .expect(1)
+        .mount(&other)
+        .await;
+    let rerouted = dispatch_to(&other.uri());
+    assert_eq!(rerouted["cache_hit"], false);
+    assert_eq!(rerouted["attempts"], 1);
+    assert_ne!(rerouted["response_hash"], first["response_hash"]);
+    other.verify().await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn provider_models_lists_ids_and_checks_a_required_model() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/v1/models"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "data": [{ "id": "gpt-4o", "object": "model" }, { "id": "gpt-4o-mini", "object": "model" }]
+        })))
+        .mount(&server)
+        .await;
+    let models = |extra: &[&str]| {
+        Command::new(assert_cmd::cargo::cargo_bin!("pie-control"))
+            .args(["provider-models", "--base-url", &server.uri(), "--api-key", "k"])
+            .args(extra)
+            .assert()
+    };
+
+    let out = models(&[]).success().get_output().stdout.clone();
+    let listed: serde_json::Value = serde_json::from_slice(&out).unwrap();
+    assert_eq!(listed["models"], json!(["gpt-4o", "gpt-4o-mini"]));
+
+    models(&["--model", "gpt-4o-mini"]).success().stdout(predicate::str::contains("\"found\":true"));
+    models(&["--model", "gpt-5"]).code(3).stdout(predicate::str::contains("\"found\":false"));
+}
@@ -0,0 +1,34 @@
+use assert_cmd::prelude::*;
+use std::process::Command;
+
+fn schema(kind: &str) -> serde_json::Value {
+    let out = Command::new(assert_cmd::cargo::cargo_bin!("pie-control"))
+        .args(["schema", "--type", kind])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    serde_json::from_slice(&out).unwrap()
+}
+
+#[test]
+fn model_request_schema_describes_the_prompt() {
+    let s = schema("model_request");
+    assert_eq!(s["title"], "ModelRequest");
+    assert!(s["properties"]["prompt"].is_object());
+    let required: Vec<&str> = s["required"].as_array().unwrap().iter().map(|v| v.as_str().unwrap()).collect();
+    assert!(required.contains(&"prompt"));
+    // `context` defaults to null, so it is optional.
+    assert!(!required.contains(&"context"));
+}
+
+#[test]
+fn every_schema_type_is_generated() {
+    for kind in ["sanitized", "episode_append", "audit_event"] {
+        let s = schema(kind);
+        assert!(s["$schema"].as_str().unwrap().contains("json-schema.org"), "{kind}");
+    }
+    let events = schema("audit_event").to_string();
+    assert!(events.contains("\"EpisodeDeleted\""));
+}
@@ -0,0 +1,122 @@
+use assert_cmd::prelude::*;
+use predicates::prelude::*;
+use serde_json::{json, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tempfile::TempDir;
+
+fn model_request() -> Value {
+    json!({
+        "schema_version": 1,
+        "run_id": "run_validate",
+        "tick_id": 1,
+        "role": "planner",
+        "provider": "openai",
+        "model": "gpt",
+        "prompt": {
+            "format": "chat",
+            "messages": [{ "role": "system", "content": "sys" }, { "role": "user", "content": "hello" }],
+            "max_output_tokens": 64,
+            "temperature": 0.2,
+            "top_p": 1.0,
+            "stop": []
+        },
+        "context": {}
+    })
+}
+
+fn write(dir: &Path, name: &str, body: &Value) -> PathBuf {
+    let p = dir.join(name);
+    fs::write(&p, body.to_string()).unwrap();
+    p
+}
+
+/// Run `validate` and return (success, parsed report).
+fn validate(kind: &str, file: &Path) -> (bool, Value) {
+    let out = Command::new(assert_cmd::cargo::cargo_bin!("pie-control"))
+        .args(["validate", "--kind", kind, "--file", file.to_str().unwrap()])
+        .output()
+        .unwrap();
+    (out.status.success(), serde_json::from_slice(&out.stdout).unwrap())
+}
+
+fn error_paths(report: &Value) -> Vec<String> {
+    report["errors"].as_array().unwrap().iter().map(|e| e["path"].as_str().unwrap().to_string()).collect()
+}
+
+#[test]
+fn validate_accepts_well_formed_model_request() {
+    let dir = TempDir::new().unwrap();
+    let (ok, report) = validate("model_request", &write(dir.path(), "req.json", &model_request()));
+    assert!(ok, "{report}");
+    assert_eq!(report, json!({ "kind": "model_request", "ok": true, "errors": [] }));
+}
+
+#[test]
+fn validate_reports_field_paths_for_broken_invariants() {
+    let dir = TempDir::new().unwrap();
+
+    let mut req = model_request();
+    req["prompt"]["messages"][1]["role"] = json!("narrator");
+    req["prompt"]["temperature"] = json!(3.5);
+    req["prompt"]["top_p"] = json!(0.0);
+    req["prompt"]["max_output_tokens"] = json!(0);
+    let (ok, report) = validate("model_request", &write(dir.path(), "bad.json", &req));
+    assert!(!ok);
+    assert_eq!(report["ok"], false);
+    assert_eq!(
+        error_paths(&report),
+        ["prompt.messages[1].role", "prompt.max_output_tokens", "prompt.temperature", "prompt.top_p"]
+    );
+
+    let mut empty = model_request();
+    empty["prompt"]["messages"] = json!([]);
+    let (_, report) = validate("model_request", &write(dir.path(), "empty.json", &empty));
+    assert_eq!(error_paths(&report), ["prompt.messages"]);
+
+    let mut missing = model_request();
+    missing.as_object_mut().unwrap().remove("prompt");
+    let (ok, report) = validate("model_request", &write(dir.path(), "missing.json", &missing));
+    assert!(!ok);
+    assert!(report["errors"][0]["message"].as_str().unwrap().contains("missing field `prompt`"));
+}
+
+#[test]
+fn validate_sanitized_checks_the_integrity_seal() {
+    let repo = TempDir::new().unwrap();
+    let root = repo.path();
+    let audit = root.join("audit.jsonl");
+    let req = write(root, "req.json", &model_request());
+
+    let out = Command::new(assert_cmd::cargo::cargo_bin!("pie-control"))
+        .args(["redact-only", "--repo-root", root.to_str().unwrap(), "--request-json", req.to_str().unwrap()])
+        .args(["--audit-log", audit.to_str().unwrap()])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let redacted: Value = serde_json::from_slice(&out).unwrap();
+    let post = root
+        .join("runtime/artifacts/models/run_validate")
+        .join(redacted["call_id"].as_str().unwrap())
+        .join("request_post.json");
+
+    let (ok, report) = validate("sanitized", &post);
+    assert!(ok, "{report}");
+
+    // An unsealed ModelRequest is not a sanitized request.
+    let (ok, _) = validate("sanitized", &req);
+    assert!(!ok);
+
+    let mut tampered: Value = serde_json::from_slice(&fs::read(&post).unwrap()).unwrap();
+    tampered["prompt"]["temperature"] = json!(0.9);
+    let tampered = write(root, "tampered.json", &tampered);
+    Command::new(assert_cmd::cargo::cargo_bin!("pie-control"))
+        .args(["validate", "--kind", "sanitized", "--file", tampered.to_str().unwrap()])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("\"path\":\"integrity.post_hash\""))
+        .stderr(predicate::str::contains("validation failed: 1 issue(s)"));
+}
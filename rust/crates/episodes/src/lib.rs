@@ -4,6 +4,8 @@
 //!   runtime/memory/episodes/
 //!     episodes.jsonl   (append-only)
 //!     index.json       (deterministic index, rewritten canonically)
+//!     tags.json        (tag -> line_no postings, derived from index.json)
+//!     episodes.lock    (advisory lock held by writers)
 //!
 //! NOTE:
 //! - This store is authoritative.
@@ -11,9 +13,11 @@
 
 use pie_common::{canonical_json_bytes, sha256_canonical_json, CanonError};
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
 use std::fs;
-use std::io::{BufRead, BufReader, Write};
-use std::path::{PathBuf};
+use std::io::{BufRead, BufReader, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 use thiserror::Error;
 use uuid::Uuid;
 
@@ -52,10 +56,20 @@ pub struct Episode {
     pub artifacts: Vec<ArtifactRef>,
     /// unix seconds or monotonic seconds; caller decides. Stored verbatim.
     pub created_ts: f64,
+    /// Episode this one replaces (set by `EpisodeStore::supersede`). Covered by `hash`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub supersedes: Option<Uuid>,
+    /// Hash of the episode on the previous episodes.jsonl line (None for the first).
+    /// Set at append time on chained (schema_version 2) episodes; covered by `hash`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prev_hash: Option<String>,
     /// sha256 of canonical JSON excluding this `hash` field.
     pub hash: String,
 }
 
+/// Schema version of episodes carrying `prev_hash` (hash-chained).
+pub const CHAINED_SCHEMA_VERSION: u8 = 2;
+
 // Internal struct used only for hash computation (excludes `hash`)
 #[derive(Debug, Clone, Serialize)]
 struct EpisodeUnsigned<'a> {
@@ -69,11 +83,17 @@ struct EpisodeUnsigned<'a> {
     summary: &'a str,
     artifacts: &'a [ArtifactRef],
     created_ts: f64,
+    // Omitted when None so hashes of pre-supersede episodes are unchanged.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    supersedes: Option<&'a Uuid>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    prev_hash: Option<&'a str>,
 }
 
 impl Episode {
     /// Create an episode with deterministic hashing.
-    /// Caller is responsible for enforcing summary/title length caps at policy layer.
+    /// Title/summary length caps are enforced on append by `EpisodeStore::with_size_caps`.
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         run_id: RunId,
         tick_id: TickId,
@@ -100,6 +120,8 @@ impl Episode {
             summary: &summary,
             artifacts: &artifacts,
             created_ts,
+            supersedes: None,
+            prev_hash: None,
         };
 
         let hash = sha256_canonical_json(&unsigned)?;
@@ -115,12 +137,13 @@ impl Episode {
             summary,
             artifacts,
             created_ts,
+            supersedes: None,
+            prev_hash: None,
             hash,
         })
     }
 
-    /// Recompute expected hash and verify integrity.
-    pub fn verify_hash(&self) -> Result<(), EpisodeError> {
+    fn compute_hash(&self) -> Result<String, EpisodeError> {
         let unsigned = EpisodeUnsigned {
             schema_version: self.schema_version,
             episode_id: &self.episode_id,
@@ -132,8 +155,15 @@ impl Episode {
             summary: &self.summary,
             artifacts: &self.artifacts,
             created_ts: self.created_ts,
+            supersedes: self.supersedes.as_ref(),
+            prev_hash: self.prev_hash.as_deref(),
         };
-        let expected = sha256_canonical_json(&unsigned)?;
+        Ok(sha256_canonical_json(&unsigned)?)
+    }
+
+    /// Recompute expected hash and verify integrity.
+    pub fn verify_hash(&self) -> Result<(), EpisodeError> {
+        let expected = self.compute_hash()?;
         if expected != self.hash {
             return Err(EpisodeError::HashMismatch {
                 expected,
@@ -158,6 +188,42 @@ pub struct EpisodeIndexEntry {
     pub hash: String,
     /// Line number in episodes.jsonl (0-based). Deterministic, stable on append.
     pub line_no: u64,
+    /// Byte offset of the line's first byte, for direct seeks.
+    /// None for entries written before offsets were recorded (loads fall back to a line scan).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub byte_offset: Option<u64>,
+    /// Older episode this entry replaces.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub supersedes: Option<Uuid>,
+    /// Newer episode that replaced this one. Superseded entries are hidden from `query` by default.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub superseded_by: Option<Uuid>,
+    /// Tombstoned by `EpisodeStore::delete`. Never returned by `query`; the line stays on disk
+    /// until `compact`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub deleted: bool,
+}
+
+/// One line of tombstones.jsonl: the durable record of a deletion.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Tombstone {
+    pub episode_id: Uuid,
+    pub reason: String,
+    pub ts: f64,
+    /// The deleted episode's hash and chain link, so `verify_chain` can bridge the gap
+    /// left once `compact` removes the line.
+    pub episode_hash: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prev_hash: Option<String>,
+}
+
+/// Result of `EpisodeStore::compact`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CompactReport {
+    pub kept: u64,
+    pub removed: u64,
+    pub bytes_before: u64,
+    pub bytes_after: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -166,6 +232,86 @@ pub struct EpisodeIndex {
     pub entries: Vec<EpisodeIndexEntry>,
 }
 
+/// Inverted tag index: tag -> ascending line_nos of the index entries carrying it.
+///
+/// Derived data, rewritten with index.json. `entries` records the index length it was built
+/// from; a missing or mismatched tags.json is rebuilt in memory on read.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct TagIndex {
+    pub entries: u64,
+    pub postings: BTreeMap<String, Vec<u64>>,
+}
+
+impl TagIndex {
+    pub fn build(idx: &EpisodeIndex) -> Self {
+        let mut postings: BTreeMap<String, Vec<u64>> = BTreeMap::new();
+        for e in &idx.entries {
+            for t in &e.tags {
+                postings.entry(t.clone()).or_default().push(e.line_no);
+            }
+        }
+        for list in postings.values_mut() {
+            list.sort_unstable();
+            list.dedup();
+        }
+        Self { entries: idx.entries.len() as u64, postings }
+    }
+
+    /// Line numbers satisfying the filter's tag constraints (ascending), or None when it has none.
+    /// Other filter fields are left to `EpisodeFilter::matches`.
+    pub fn candidates(&self, filter: &EpisodeFilter<'_>) -> Option<Vec<u64>> {
+        if filter.tags_all.is_empty() && filter.tags_any.is_empty() {
+            return None;
+        }
+        let empty = vec![];
+        let posting = |t: &String| self.postings.get(t).unwrap_or(&empty);
+
+        let mut lists: Vec<&Vec<u64>> = filter.tags_all.iter().map(posting).collect();
+        lists.sort_by_key(|l| l.len());
+        let mut out: Option<Vec<u64>> = lists.first().map(|l| {
+            l.iter().copied().filter(|n| lists[1..].iter().all(|o| o.binary_search(n).is_ok())).collect()
+        });
+
+        if !filter.tags_any.is_empty() {
+            let mut any: Vec<u64> = filter.tags_any.iter().flat_map(|t| posting(t).iter().copied()).collect();
+            any.sort_unstable();
+            any.dedup();
+            out = Some(match out {
+                Some(all) => all.into_iter().filter(|n| any.binary_search(n).is_ok()).collect(),
+                None => any,
+            });
+        }
+        out
+    }
+}
+
+/// Result of `EpisodeStore::verify_store`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct StoreReport {
+    /// Index entries examined.
+    pub entries: u64,
+    /// Lines present in episodes.jsonl.
+    pub lines: u64,
+    /// Entries whose line parsed, self-verified, and matched the index hash.
+    pub verified: u64,
+    /// First problem found, in index order (None = store is consistent).
+    pub first_discrepancy: Option<StoreDiscrepancy>,
+}
+
+impl StoreReport {
+    pub fn is_ok(&self) -> bool {
+        self.first_discrepancy.is_none()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct StoreDiscrepancy {
+    pub line_no: u64,
+    /// None for lines that have no index entry.
+    pub episode_id: Option<Uuid>,
+    pub reason: String,
+}
+
 #[derive(Debug, Error)]
 pub enum EpisodeError {
     #[error("io error: {0}")]
@@ -178,15 +324,192 @@ pub enum EpisodeError {
     HashMismatch { expected: String, got: String },
     #[error("store corruption: {0}")]
     Corrupt(String),
+    #[error("episode chain broken at line {line_no}: expected prev_hash {expected:?}, got {got:?}")]
+    ChainBroken { line_no: u64, expected: Option<String>, got: Option<String> },
+    #[error("store is not empty (use force to replace it)")]
+    StoreNotEmpty,
+    #[error("invalid query cursor: {0}")]
+    InvalidCursor(String),
+    #[error("episode not found: {0}")]
+    NotFound(Uuid),
+    #[error("episodes not found: {0:?}")]
+    MissingEpisodes(Vec<Uuid>),
+    #[error("episode {0} is already superseded by {1}")]
+    AlreadySuperseded(Uuid, Uuid),
+    #[error("episode store is locked by another writer: {0}")]
+    Locked(PathBuf),
+    #[error("episode {field} too large: {len} chars (max {max})")]
+    TooLarge { field: &'static str, len: usize, max: usize },
+}
+
+/// Aggregates from `EpisodeStore::stats`. Maps are ordered so the JSON is deterministic.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct StoreStats {
+    /// Every indexed episode, including superseded and deleted ones.
+    pub total_episodes: u64,
+    pub superseded: u64,
+    /// Tombstoned but not yet compacted away.
+    pub deleted: u64,
+    pub per_thread: BTreeMap<String, u64>,
+    pub per_tag: BTreeMap<String, u64>,
+    /// None when the store is empty.
+    pub min_tick: Option<TickId>,
+    pub max_tick: Option<TickId>,
+    /// Size of episodes.jsonl on disk.
+    pub total_bytes: u64,
+}
+
+/// Portable backup of a whole store: every episodes.jsonl line plus the index.
+/// `bundle_hash` covers both (canonical JSON of everything except itself).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EpisodeBundle {
+    pub schema_version: u8,
+    pub episodes: Vec<Episode>,
+    pub index: EpisodeIndex,
+    pub bundle_hash: String,
+}
+
+#[derive(Serialize)]
+struct BundleUnsigned<'a> {
+    schema_version: u8,
+    episodes: &'a [Episode],
+    index: &'a EpisodeIndex,
+}
+
+/// Filters for `EpisodeStore::query`. All set filters must match.
+/// `Default` matches every current (non-superseded) episode.
+#[derive(Debug, Clone, Default)]
+pub struct EpisodeFilter<'a> {
+    pub thread_id: Option<&'a str>,
+    pub run_id: Option<&'a str>,
+    /// Must include all of these tags.
+    pub tags_all: &'a [String],
+    /// Must include at least one of these, when non-empty.
+    pub tags_any: &'a [String],
+    /// Inclusive.
+    pub since_tick: Option<TickId>,
+    /// false = only the latest version of each episode.
+    pub include_superseded: bool,
 }
 
+impl EpisodeFilter<'_> {
+    pub fn matches(&self, e: &EpisodeIndexEntry) -> bool {
+        if e.deleted {
+            return false;
+        }
+        if !self.include_superseded && e.superseded_by.is_some() {
+            return false;
+        }
+        if let Some(t) = self.thread_id {
+            if e.thread_id != t {
+                return false;
+            }
+        }
+        if let Some(r) = self.run_id {
+            if e.run_id.0 != r {
+                return false;
+            }
+        }
+        if let Some(st) = self.since_tick {
+            if e.tick_id < st {
+                return false;
+            }
+        }
+        // tags_all must be subset of entry tags
+        for want in self.tags_all {
+            if !e.tags.iter().any(|x| x == want) {
+                return false;
+            }
+        }
+        if !self.tags_any.is_empty() && !self.tags_any.iter().any(|want| e.tags.contains(want)) {
+            return false;
+        }
+        true
+    }
+}
+
+/// Result order for `EpisodeStore::query`, by tick_id then line_no.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    /// Newest first; applied before `limit`, so `Some(n)` yields the latest n episodes.
+    Desc,
+}
+
+impl std::str::FromStr for SortOrder {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "asc" => Ok(Self::Asc),
+            "desc" => Ok(Self::Desc),
+            other => Err(format!("unknown sort order {other:?} (expected asc or desc)")),
+        }
+    }
+}
+
+/// Opaque continuation point for `EpisodeStore::query_page`: the last `(tick_id, line_no)` returned.
+/// Round-trips through `to_token`/`from_token` for callers that pass it over the wire.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct QueryCursor {
+    tick_id: TickId,
+    line_no: u64,
+}
+
+impl QueryCursor {
+    fn of(e: &EpisodeIndexEntry) -> Self {
+        Self { tick_id: e.tick_id, line_no: e.line_no }
+    }
+
+    pub fn to_token(&self) -> String {
+        format!("{}.{}", self.tick_id.0, self.line_no)
+    }
+
+    pub fn from_token(token: &str) -> Result<Self, EpisodeError> {
+        let bad = || EpisodeError::InvalidCursor(token.to_string());
+        let (tick, line) = token.split_once('.').ok_or_else(bad)?;
+        Ok(Self {
+            tick_id: TickId(tick.parse().map_err(|_| bad())?),
+            line_no: line.parse().map_err(|_| bad())?,
+        })
+    }
+}
+
+/// How long a writer waits for `episodes.lock` before giving up with `EpisodeError::Locked`.
+const LOCK_TIMEOUT: Duration = Duration::from_secs(10);
+const LOCK_RETRY: Duration = Duration::from_millis(5);
+
+/// Exclusive advisory lock on the store; released when dropped (closing the file unlocks it).
+struct StoreLock(#[allow(dead_code)] fs::File);
+
 pub struct EpisodeStore {
     repo_root: PathBuf,
+    max_title_chars: Option<usize>,
+    max_summary_chars: Option<usize>,
 }
 
 impl EpisodeStore {
     pub fn new(repo_root: impl Into<PathBuf>) -> Self {
-        Self { repo_root: repo_root.into() }
+        Self { repo_root: repo_root.into(), max_title_chars: None, max_summary_chars: None }
+    }
+
+    /// Reject appends whose title/summary exceed these many chars (`None` = uncapped).
+    pub fn with_size_caps(mut self, max_title_chars: Option<usize>, max_summary_chars: Option<usize>) -> Self {
+        self.max_title_chars = max_title_chars;
+        self.max_summary_chars = max_summary_chars;
+        self
+    }
+
+    fn check_size_caps(&self, ep: &Episode) -> Result<(), EpisodeError> {
+        let fields = [("title", &ep.title, self.max_title_chars), ("summary", &ep.summary, self.max_summary_chars)];
+        for (field, text, cap) in fields {
+            let len = text.chars().count();
+            if let Some(max) = cap.filter(|max| len > *max) {
+                return Err(EpisodeError::TooLarge { field, len, max });
+            }
+        }
+        Ok(())
     }
 
     pub fn base_dir(&self) -> PathBuf {
@@ -201,6 +524,35 @@ impl EpisodeStore {
         self.base_dir().join("index.json")
     }
 
+    pub fn tombstones_path(&self) -> PathBuf {
+        self.base_dir().join("tombstones.jsonl")
+    }
+
+    pub fn tag_index_path(&self) -> PathBuf {
+        self.base_dir().join("tags.json")
+    }
+
+    pub fn lock_path(&self) -> PathBuf {
+        self.base_dir().join("episodes.lock")
+    }
+
+    /// Take the writer lock, polling until `LOCK_TIMEOUT`. Every mutation (read line count,
+    /// append, rewrite index) runs under it so concurrent processes can't interleave.
+    fn lock(&self) -> Result<StoreLock, EpisodeError> {
+        self.ensure_dirs()?;
+        let p = self.lock_path();
+        let f = fs::OpenOptions::new().create(true).truncate(false).write(true).open(&p)?;
+        let deadline = Instant::now() + LOCK_TIMEOUT;
+        loop {
+            match f.try_lock() {
+                Ok(()) => return Ok(StoreLock(f)),
+                Err(fs::TryLockError::WouldBlock) if Instant::now() < deadline => std::thread::sleep(LOCK_RETRY),
+                Err(fs::TryLockError::WouldBlock) => return Err(EpisodeError::Locked(p)),
+                Err(fs::TryLockError::Error(e)) => return Err(e.into()),
+            }
+        }
+    }
+
     pub fn ensure_dirs(&self) -> Result<(), EpisodeError> {
         fs::create_dir_all(self.base_dir())?;
         Ok(())
@@ -219,9 +571,20 @@ impl EpisodeStore {
 
     fn write_index(&self, idx: &EpisodeIndex) -> Result<(), EpisodeError> {
         self.ensure_dirs()?;
-        let bytes = canonical_json_bytes(idx)?;
-        fs::write(self.index_path(), bytes)?;
-        Ok(())
+        write_atomic(&self.index_path(), &canonical_json_bytes(idx)?)?;
+        write_atomic(&self.tag_index_path(), &canonical_json_bytes(&TagIndex::build(idx))?)
+    }
+
+    /// tags.json for `idx`, rebuilt (not persisted) if missing or built from a different index.
+    pub fn load_tag_index(&self, idx: &EpisodeIndex) -> Result<TagIndex, EpisodeError> {
+        let p = self.tag_index_path();
+        if p.exists() {
+            let tags: TagIndex = serde_json::from_slice(&fs::read(p)?)?;
+            if tags.entries == idx.entries.len() as u64 {
+                return Ok(tags);
+            }
+        }
+        Ok(TagIndex::build(idx))
     }
 
     fn current_line_count(&self) -> Result<u64, EpisodeError> {
@@ -235,27 +598,44 @@ impl EpisodeStore {
     }
 
     /// Append an episode (authoritative).
-    /// - Verifies episode hash
+    /// - Verifies episode hash and the configured title/summary caps
+    /// - Links it to the previous line (`prev_hash`, schema v2) when the `hash-chain` feature is on
     /// - Appends JSONL line
     /// - Updates index deterministically
-    pub fn append(&self, ep: &Episode) -> Result<(), EpisodeError> {
-        self.ensure_dirs()?;
+    ///
+    /// Returns the episode as stored; with chaining its `hash` differs from the input's.
+    /// Holds the store lock for the whole read-count/append/index-update sequence.
+    pub fn append(&self, ep: &Episode) -> Result<Episode, EpisodeError> {
+        let _lock = self.lock()?;
+        self.append_locked(ep)
+    }
+
+    fn append_locked(&self, ep: &Episode) -> Result<Episode, EpisodeError> {
         ep.verify_hash()?;
+        self.check_size_caps(ep)?;
+
+        let mut idx = self.load_index()?;
+        let mut ep = ep.clone();
+        if cfg!(feature = "hash-chain") {
+            ep.schema_version = CHAINED_SCHEMA_VERSION;
+            ep.prev_hash = idx.entries.last().map(|e| e.hash.clone());
+            ep.hash = ep.compute_hash()?;
+        }
 
         let line_no = self.current_line_count()?;
-        let ep_bytes = canonical_json_bytes(ep)?;
+        let ep_bytes = canonical_json_bytes(&ep)?;
 
         // Append to JSONL
         let mut f = fs::OpenOptions::new()
             .create(true)
             .append(true)
             .open(self.episodes_path())?;
+        let byte_offset = f.metadata()?.len();
         f.write_all(&ep_bytes)?;
         f.write_all(b"\n")?;
         f.flush()?;
 
         // Update index
-        let mut idx = self.load_index()?;
         if idx.schema_version == 0 {
             idx.schema_version = 1;
         }
@@ -267,91 +647,580 @@ impl EpisodeStore {
             tags: ep.tags.clone(),
             hash: ep.hash.clone(),
             line_no,
+            byte_offset: Some(byte_offset),
+            supersedes: ep.supersedes,
+            superseded_by: None,
+            deleted: false,
         });
         self.write_index(&idx)?;
-        Ok(())
+        Ok(ep)
+    }
+
+    /// Append a corrected version of `old_id` without touching history.
+    ///
+    /// `new_episode` is re-hashed with `supersedes = old_id`, appended, and the old index entry
+    /// is marked `superseded_by`, so default queries return only the latest version.
+    pub fn supersede(&self, old_id: Uuid, new_episode: Episode) -> Result<Episode, EpisodeError> {
+        let _lock = self.lock()?;
+        let idx = self.load_index()?;
+        let old = idx
+            .entries
+            .iter()
+            .find(|e| e.episode_id == old_id)
+            .ok_or(EpisodeError::NotFound(old_id))?;
+        if let Some(newer) = old.superseded_by {
+            return Err(EpisodeError::AlreadySuperseded(old_id, newer));
+        }
+
+        let mut ep = new_episode;
+        ep.supersedes = Some(old_id);
+        ep.hash = ep.compute_hash()?;
+        let ep = self.append_locked(&ep)?;
+
+        let mut idx = self.load_index()?;
+        for e in idx.entries.iter_mut().filter(|e| e.episode_id == old_id) {
+            e.superseded_by = Some(ep.episode_id);
+        }
+        self.write_index(&idx)?;
+        Ok(ep)
     }
 
     /// Deterministic query (Stage 7B later can add richer options, but this covers 7A baseline)
     ///
-    /// Filters:
-    /// - thread_id (optional)
-    /// - tags (must include all provided tags)
-    /// - since_tick (inclusive)
-    /// - limit (max results)
+    /// Filters: see `EpisodeFilter`; `limit` caps the result count (`None` = no cap,
+    /// `Some(0)` = no results).
     ///
     /// Ordering:
-    /// - by tick_id asc, then line_no asc (stable)
+    /// - by tick_id, then line_no (stable); `order` picks the direction
     pub fn query(
         &self,
-        thread_id: Option<&str>,
-        tags_all: &[String],
-        since_tick: Option<TickId>,
-        limit: usize,
+        filter: &EpisodeFilter<'_>,
+        limit: Option<usize>,
+        order: SortOrder,
     ) -> Result<Vec<EpisodeIndexEntry>, EpisodeError> {
-        let idx = self.load_index()?;
-        let mut out: Vec<EpisodeIndexEntry> = idx
-            .entries
+        let mut out = self.matching_sorted(filter)?;
+        if order == SortOrder::Desc {
+            out.reverse();
+        }
+        if let Some(limit) = limit {
+            out.truncate(limit);
+        }
+        Ok(out)
+    }
+
+    /// One page of `query` results strictly after `after`, plus the cursor for the next page
+    /// (None once the results are exhausted). Same tick-then-line order, so cursors stay valid
+    /// across appends.
+    pub fn query_page(
+        &self,
+        filter: &EpisodeFilter<'_>,
+        after: Option<QueryCursor>,
+        limit: usize,
+    ) -> Result<(Vec<EpisodeIndexEntry>, Option<QueryCursor>), EpisodeError> {
+        let mut rest: Vec<EpisodeIndexEntry> = self
+            .matching_sorted(filter)?
             .into_iter()
-            .filter(|e| {
-                if let Some(t) = thread_id {
-                    if e.thread_id != t {
-                        return false;
-                    }
-                }
-                if let Some(st) = since_tick {
-                    if e.tick_id < st {
-                        return false;
-                    }
-                }
-                // tags_all must be subset of entry tags
-                for want in tags_all {
-                    if !e.tags.iter().any(|x| x == want) {
-                        return false;
-                    }
-                }
-                true
-            })
+            .filter(|e| after.is_none_or(|c| QueryCursor::of(e) > c))
             .collect();
+        let more = rest.len() > limit;
+        rest.truncate(limit);
+        let next = if more { rest.last().map(QueryCursor::of) } else { None };
+        Ok((rest, next))
+    }
+
+    /// Tag-filtered queries intersect tags.json postings and look entries up by line_no
+    /// (entries are kept in line order) instead of scanning the whole index.
+    fn matching_sorted(&self, filter: &EpisodeFilter<'_>) -> Result<Vec<EpisodeIndexEntry>, EpisodeError> {
+        let idx = self.load_index()?;
+        let fast = self.load_tag_index(&idx)?.candidates(filter).and_then(|lines| {
+            lines
+                .into_iter()
+                .map(|n| idx.entries.binary_search_by_key(&n, |e| e.line_no).ok().map(|i| &idx.entries[i]))
+                .collect::<Option<Vec<_>>>()
+        });
+        let mut out: Vec<EpisodeIndexEntry> = match fast {
+            Some(found) => found.into_iter().filter(|e| filter.matches(e)).cloned().collect(),
+            None => idx.entries.into_iter().filter(|e| filter.matches(e)).collect(),
+        };
 
         out.sort_by(|a, b| {
             a.tick_id
                 .cmp(&b.tick_id)
                 .then_with(|| a.line_no.cmp(&b.line_no))
         });
+        Ok(out)
+    }
 
-        if out.len() > limit {
-            out.truncate(limit);
+    /// Case-insensitive substring search over title + summary of current (non-superseded) episodes.
+    ///
+    /// `match_all` = every term must appear; otherwise any term suffices. No stemming or ranking:
+    /// results keep the usual tick-then-line order so they are reproducible. Empty `terms` match nothing.
+    pub fn search(
+        &self,
+        terms: &[String],
+        match_all: bool,
+        limit: usize,
+    ) -> Result<Vec<EpisodeIndexEntry>, EpisodeError> {
+        let terms: Vec<String> = terms.iter().map(|t| t.to_lowercase()).filter(|t| !t.is_empty()).collect();
+        if terms.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let mut out = vec![];
+        for entry in self.matching_sorted(&EpisodeFilter::default())? {
+            if out.len() >= limit {
+                break;
+            }
+            let ep = self.load_episode_by_entry(&entry)?;
+            let text = format!("{}\n{}", ep.title, ep.summary).to_lowercase();
+            let hit = if match_all {
+                terms.iter().all(|t| text.contains(t.as_str()))
+            } else {
+                terms.iter().any(|t| text.contains(t.as_str()))
+            };
+            if hit {
+                out.push(entry);
+            }
         }
         Ok(out)
     }
 
+    /// Tombstone an episode: append to tombstones.jsonl and flag the index entry.
+    /// Content is only physically removed by `compact`. Deleting twice is a no-op.
+    pub fn delete(&self, episode_id: Uuid, reason: &str, ts: f64) -> Result<(), EpisodeError> {
+        let _lock = self.lock()?;
+        let mut idx = self.load_index()?;
+        let entry = idx
+            .entries
+            .iter_mut()
+            .find(|e| e.episode_id == episode_id)
+            .ok_or(EpisodeError::NotFound(episode_id))?;
+        if entry.deleted {
+            return Ok(());
+        }
+
+        let ep = self.load_episode_by_entry(entry)?;
+        let tomb = Tombstone {
+            episode_id,
+            reason: reason.to_string(),
+            ts,
+            episode_hash: ep.hash,
+            prev_hash: ep.prev_hash,
+        };
+        let mut f = fs::OpenOptions::new().create(true).append(true).open(self.tombstones_path())?;
+        f.write_all(&canonical_json_bytes(&tomb)?)?;
+        f.write_all(b"\n")?;
+        f.flush()?;
+
+        entry.deleted = true;
+        self.write_index(&idx)
+    }
+
+    /// Walk episodes.jsonl confirming the `prev_hash` chain, analogous to the audit log's
+    /// `verify_log`. Returns the head hash (None for an empty store).
+    ///
+    /// Leading un-chained (schema v1) episodes from older stores are accepted; once a chained
+    /// episode appears every later line must link to its predecessor. A link may skip lines
+    /// only through tombstoned episodes, so compaction verifies but silent removal does not.
+    pub fn verify_chain(&self) -> Result<Option<String>, EpisodeError> {
+        let tombs = self.load_tombstones()?;
+        let by_hash: BTreeMap<&str, &Tombstone> = tombs.iter().map(|t| (t.episode_hash.as_str(), t)).collect();
+
+        let mut prev: Option<String> = None;
+        let mut chained = false;
+        for (line_no, ep) in self.iter().enumerate() {
+            let ep = ep?;
+            let broken = || EpisodeError::ChainBroken {
+                line_no: line_no as u64,
+                expected: prev.clone(),
+                got: ep.prev_hash.clone(),
+            };
+
+            if ep.schema_version < CHAINED_SCHEMA_VERSION {
+                if chained {
+                    return Err(broken());
+                }
+            } else {
+                chained = true;
+                // Bridge over deleted-and-compacted predecessors via their tombstones.
+                let mut link = ep.prev_hash.clone();
+                let mut hops = 0;
+                while link != prev {
+                    let t = link.as_deref().and_then(|h| by_hash.get(h)).ok_or_else(broken)?;
+                    link = t.prev_hash.clone();
+                    hops += 1;
+                    if hops > tombs.len() {
+                        return Err(broken());
+                    }
+                }
+            }
+            prev = Some(ep.hash);
+        }
+        Ok(prev)
+    }
+
+    pub fn load_tombstones(&self) -> Result<Vec<Tombstone>, EpisodeError> {
+        let p = self.tombstones_path();
+        if !p.exists() {
+            return Ok(vec![]);
+        }
+        let mut out = vec![];
+        for line in BufReader::new(fs::File::open(p)?).lines() {
+            out.push(serde_json::from_str(&line?)?);
+        }
+        Ok(out)
+    }
+
+    /// Rewrite episodes.jsonl without tombstoned (or unindexed) lines.
+    ///
+    /// Every retained line is re-verified against its index entry; line_no/byte_offset are
+    /// reassigned. Each file is swapped in with `write_atomic` (episodes first), so readers never
+    /// see a half-written file; a crash between the two swaps leaves a stale index that
+    /// `verify_store` will flag.
+    pub fn compact(&self) -> Result<CompactReport, EpisodeError> {
+        let _lock = self.lock()?;
+        let idx = self.load_index()?;
+        let p = self.episodes_path();
+        let lines: Vec<String> = if p.exists() {
+            BufReader::new(fs::File::open(&p)?).lines().collect::<Result<_, _>>()?
+        } else {
+            vec![]
+        };
+        let bytes_before = if p.exists() { fs::metadata(&p)?.len() } else { 0 };
+
+        let mut data = Vec::new();
+        let mut entries = Vec::new();
+        for entry in idx.entries.into_iter().filter(|e| !e.deleted) {
+            let line = lines
+                .get(entry.line_no as usize)
+                .ok_or_else(|| EpisodeError::Corrupt(format!("missing line {}", entry.line_no)))?;
+            parse_and_check(line, &entry)?;
+            let byte_offset = data.len() as u64;
+            data.extend_from_slice(line.as_bytes());
+            data.push(b'\n');
+            entries.push(EpisodeIndexEntry { line_no: entries.len() as u64, byte_offset: Some(byte_offset), ..entry });
+        }
+
+        let report = CompactReport {
+            kept: entries.len() as u64,
+            removed: lines.len() as u64 - entries.len() as u64,
+            bytes_before,
+            bytes_after: data.len() as u64,
+        };
+
+        let new_idx = EpisodeIndex { schema_version: idx.schema_version.max(1), entries };
+        write_atomic(&p, &data)?;
+        self.write_index(&new_idx)?;
+        Ok(report)
+    }
+
+    /// Store summary computed from the index plus the file size (no episode lines are read).
+    pub fn stats(&self) -> Result<StoreStats, EpisodeError> {
+        let idx = self.load_index()?;
+        let mut st = StoreStats { total_episodes: idx.entries.len() as u64, ..Default::default() };
+        for e in &idx.entries {
+            if e.superseded_by.is_some() {
+                st.superseded += 1;
+            }
+            if e.deleted {
+                st.deleted += 1;
+            }
+            *st.per_thread.entry(e.thread_id.clone()).or_default() += 1;
+            for t in &e.tags {
+                *st.per_tag.entry(t.clone()).or_default() += 1;
+            }
+            st.min_tick = Some(st.min_tick.map_or(e.tick_id, |m| m.min(e.tick_id)));
+            st.max_tick = Some(st.max_tick.map_or(e.tick_id, |m| m.max(e.tick_id)));
+        }
+        let p = self.episodes_path();
+        if p.exists() {
+            st.total_bytes = fs::metadata(p)?.len();
+        }
+        Ok(st)
+    }
+
     /// Load a full episode by index entry.
-    /// This is deterministic because we reference by line_no and verify the hash.
+    /// This is deterministic because we reference by line_no/byte_offset and verify the hash.
+    /// With a byte_offset this is a single seek + line read; older entries scan to line_no.
     pub fn load_episode_by_entry(&self, entry: &EpisodeIndexEntry) -> Result<Episode, EpisodeError> {
         let p = self.episodes_path();
         if !p.exists() {
             return Err(EpisodeError::Corrupt("episodes.jsonl missing".into()));
         }
         let f = fs::File::open(p)?;
-        let reader = BufReader::new(f);
-        let line = reader
-            .lines()
-            .nth(entry.line_no as usize)
-            .ok_or_else(|| EpisodeError::Corrupt(format!("missing line {}", entry.line_no)))??;
+        let mut reader = BufReader::new(f);
+        let line = match entry.byte_offset {
+            Some(off) => {
+                reader.seek(SeekFrom::Start(off))?;
+                let mut line = String::new();
+                if reader.read_line(&mut line)? == 0 {
+                    return Err(EpisodeError::Corrupt(format!("missing line {} at offset {}", entry.line_no, off)));
+                }
+                line.truncate(line.trim_end_matches('\n').len());
+                line
+            }
+            None => reader
+                .lines()
+                .nth(entry.line_no as usize)
+                .ok_or_else(|| EpisodeError::Corrupt(format!("missing line {}", entry.line_no)))??,
+        };
 
-        let ep: Episode = serde_json::from_str(&line)?;
-        ep.verify_hash()?;
-        if ep.hash != entry.hash {
-            return Err(EpisodeError::HashMismatch {
-                expected: entry.hash.clone(),
-                got: ep.hash.clone(),
-            });
+        parse_and_check(&line, entry)
+    }
+
+    /// Load several episodes in one pass over episodes.jsonl (entries visited in line order),
+    /// verifying each hash. Results follow the order of `ids`; ids missing from the index are
+    /// reported together as `MissingEpisodes`.
+    pub fn load_many(&self, ids: &[Uuid]) -> Result<Vec<Episode>, EpisodeError> {
+        let idx = self.load_index()?;
+        let by_id: BTreeMap<Uuid, &EpisodeIndexEntry> = idx.entries.iter().map(|e| (e.episode_id, e)).collect();
+        let missing: Vec<Uuid> = ids.iter().filter(|id| !by_id.contains_key(id)).copied().collect();
+        if !missing.is_empty() {
+            return Err(EpisodeError::MissingEpisodes(missing));
         }
-        Ok(ep)
+
+        let mut wanted: Vec<&EpisodeIndexEntry> = ids.iter().map(|id| by_id[id]).collect();
+        wanted.sort_by_key(|e| e.line_no);
+        wanted.dedup_by_key(|e| e.line_no);
+
+        let mut loaded: BTreeMap<Uuid, Episode> = BTreeMap::new();
+        let mut next = wanted.iter().peekable();
+        if !wanted.is_empty() {
+            let f = fs::File::open(self.episodes_path())?;
+            for (line_no, line) in BufReader::new(f).lines().enumerate() {
+                let Some(entry) = next.peek() else { break };
+                if entry.line_no == line_no as u64 {
+                    loaded.insert(entry.episode_id, parse_and_check(&line?, entry)?);
+                    next.next();
+                }
+            }
+        }
+        if let Some(entry) = next.next() {
+            return Err(EpisodeError::Corrupt(format!("missing line {}", entry.line_no)));
+        }
+        Ok(ids.iter().map(|id| loaded[id].clone()).collect())
+    }
+
+    /// Walk the whole index against episodes.jsonl.
+    ///
+    /// Every entry must point at the next line (line numbers contiguous from 0), that line must
+    /// parse, pass `Episode::verify_hash`, and carry the id and hash the index recorded.
+    /// Lines with no index entry are also reported. Only I/O failures are errors; integrity
+    /// problems are returned in the report.
+    pub fn verify_store(&self) -> Result<StoreReport, EpisodeError> {
+        let idx = self.load_index()?;
+        let p = self.episodes_path();
+        let lines: Vec<String> = if p.exists() {
+            BufReader::new(fs::File::open(p)?).lines().collect::<Result<_, _>>()?
+        } else {
+            vec![]
+        };
+
+        let mut report = StoreReport {
+            entries: idx.entries.len() as u64,
+            lines: lines.len() as u64,
+            verified: 0,
+            first_discrepancy: None,
+        };
+        let mut note = |line_no: u64, episode_id: Option<Uuid>, reason: String| {
+            if report.first_discrepancy.is_none() {
+                report.first_discrepancy = Some(StoreDiscrepancy { line_no, episode_id, reason });
+            }
+        };
+
+        let mut verified = 0;
+        for (pos, entry) in idx.entries.iter().enumerate() {
+            if entry.line_no != pos as u64 {
+                note(entry.line_no, Some(entry.episode_id), format!("non-contiguous line_no (expected {pos})"));
+                continue;
+            }
+            let Some(line) = lines.get(pos) else {
+                note(entry.line_no, Some(entry.episode_id), "line missing from episodes.jsonl".into());
+                continue;
+            };
+            match parse_and_check(line, entry) {
+                Ok(_) => verified += 1,
+                Err(e) => note(entry.line_no, Some(entry.episode_id), e.to_string()),
+            }
+        }
+        if lines.len() > idx.entries.len() {
+            note(idx.entries.len() as u64, None, "line has no index entry".into());
+        }
+
+        report.verified = verified;
+        Ok(report)
+    }
+
+    /// Recreate index.json (and tags.json) from episodes.jsonl, e.g. after the index was lost.
+    ///
+    /// Every line must parse and self-verify. `superseded_by` is re-derived from the episodes'
+    /// `supersedes` links and `deleted` from tombstones.jsonl. Returns the number of entries.
+    pub fn rebuild_index(&self) -> Result<u64, EpisodeError> {
+        let _lock = self.lock()?;
+        let deleted: BTreeSet<Uuid> = self.load_tombstones()?.into_iter().map(|t| t.episode_id).collect();
+
+        let mut entries: Vec<EpisodeIndexEntry> = Vec::new();
+        let mut byte_offset = 0u64;
+        let p = self.episodes_path();
+        if p.exists() {
+            for (line_no, line) in BufReader::new(fs::File::open(p)?).lines().enumerate() {
+                let line = line?;
+                let ep: Episode = serde_json::from_str(&line)?;
+                ep.verify_hash()?;
+                entries.push(EpisodeIndexEntry {
+                    episode_id: ep.episode_id,
+                    run_id: ep.run_id,
+                    tick_id: ep.tick_id,
+                    thread_id: ep.thread_id,
+                    tags: ep.tags,
+                    hash: ep.hash,
+                    line_no: line_no as u64,
+                    byte_offset: Some(byte_offset),
+                    supersedes: ep.supersedes,
+                    superseded_by: None,
+                    deleted: deleted.contains(&ep.episode_id),
+                });
+                byte_offset += line.len() as u64 + 1;
+            }
+        }
+
+        let links: Vec<(Uuid, Uuid)> =
+            entries.iter().filter_map(|e| e.supersedes.map(|old| (old, e.episode_id))).collect();
+        for (old, new) in links {
+            for e in entries.iter_mut().filter(|e| e.episode_id == old) {
+                e.superseded_by = Some(new);
+            }
+        }
+
+        let count = entries.len() as u64;
+        self.write_index(&EpisodeIndex { schema_version: 1, entries })?;
+        Ok(count)
+    }
+
+    /// Stream every line of episodes.jsonl in file order through one open handle, verifying
+    /// each episode's hash as it is yielded. Tombstoned lines are included until `compact`.
+    /// A missing file yields nothing.
+    pub fn iter(&self) -> impl Iterator<Item = Result<Episode, EpisodeError>> {
+        let (lines, open_err) = match fs::File::open(self.episodes_path()) {
+            Ok(f) => (Some(BufReader::new(f).lines()), None),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => (None, None),
+            Err(e) => (None, Some(Err(e.into()))),
+        };
+        open_err.into_iter().chain(lines.into_iter().flatten().map(|line| {
+            let ep: Episode = serde_json::from_str(&line?)?;
+            ep.verify_hash()?;
+            Ok(ep)
+        }))
+    }
+
+    fn read_all_episodes(&self) -> Result<Vec<Episode>, EpisodeError> {
+        self.iter().collect()
+    }
+
+    /// Write every episode plus the index to one canonical JSON bundle at `out`.
+    /// Returns the bundle hash (also embedded in the file).
+    pub fn export(&self, out: &Path) -> Result<String, EpisodeError> {
+        let episodes = self.read_all_episodes()?;
+        let index = self.load_index()?;
+        let unsigned = BundleUnsigned { schema_version: 1, episodes: &episodes, index: &index };
+        let bundle_hash = sha256_canonical_json(&unsigned)?;
+        let bundle = EpisodeBundle { schema_version: 1, episodes, index, bundle_hash: bundle_hash.clone() };
+        if let Some(parent) = out.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(out, canonical_json_bytes(&bundle)?)?;
+        Ok(bundle_hash)
+    }
+
+    /// Populate this store from an `export` bundle.
+    ///
+    /// Verifies the bundle hash, every episode hash, and that each index entry points at the
+    /// episode it names. Refuses a non-empty store unless `force` (which replaces its contents).
+    /// Returns the number of episodes imported.
+    pub fn import(&self, bundle: &Path, force: bool) -> Result<u64, EpisodeError> {
+        let b: EpisodeBundle = serde_json::from_slice(&fs::read(bundle)?)?;
+        let unsigned = BundleUnsigned { schema_version: b.schema_version, episodes: &b.episodes, index: &b.index };
+        let expected = sha256_canonical_json(&unsigned)?;
+        if expected != b.bundle_hash {
+            return Err(EpisodeError::HashMismatch { expected, got: b.bundle_hash });
+        }
+        for ep in &b.episodes {
+            ep.verify_hash()?;
+        }
+        for e in &b.index.entries {
+            let ep = b
+                .episodes
+                .get(e.line_no as usize)
+                .ok_or_else(|| EpisodeError::Corrupt(format!("bundle index points past line {}", e.line_no)))?;
+            if ep.episode_id != e.episode_id || ep.hash != e.hash {
+                return Err(EpisodeError::Corrupt(format!(
+                    "bundle index entry {} does not match line {}",
+                    e.episode_id, e.line_no
+                )));
+            }
+        }
+
+        let _lock = self.lock()?;
+        if !force && (!self.load_index()?.entries.is_empty() || self.current_line_count()? > 0) {
+            return Err(EpisodeError::StoreNotEmpty);
+        }
+
+        // Rewrite lines canonically and recompute offsets against the bytes actually written.
+        let mut data = Vec::new();
+        let mut offsets = Vec::with_capacity(b.episodes.len());
+        for ep in &b.episodes {
+            offsets.push(data.len() as u64);
+            data.extend(canonical_json_bytes(ep)?);
+            data.push(b'\n');
+        }
+        let mut index = b.index;
+        for e in index.entries.iter_mut() {
+            e.byte_offset = Some(offsets[e.line_no as usize]);
+        }
+        self.ensure_dirs()?;
+        write_atomic(&self.episodes_path(), &data)?;
+        self.write_index(&index)?;
+        Ok(b.episodes.len() as u64)
     }
 }
 
+fn write_synced(path: &Path, bytes: &[u8]) -> Result<(), EpisodeError> {
+    let mut f = fs::File::create(path)?;
+    f.write_all(bytes)?;
+    f.sync_all()?;
+    Ok(())
+}
+
+/// Replace `path` by writing `<path>.tmp` and renaming it over the original (atomic on the
+/// same filesystem), so a crash mid-write never leaves a truncated file behind.
+fn write_atomic(path: &Path, bytes: &[u8]) -> Result<(), EpisodeError> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+    write_synced(&tmp, bytes)?;
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+/// Parse one episodes.jsonl line and check it against its index entry.
+fn parse_and_check(line: &str, entry: &EpisodeIndexEntry) -> Result<Episode, EpisodeError> {
+    let ep: Episode = serde_json::from_str(line)?;
+    ep.verify_hash()?;
+    if ep.hash != entry.hash {
+        return Err(EpisodeError::HashMismatch {
+            expected: entry.hash.clone(),
+            got: ep.hash.clone(),
+        });
+    }
+    if ep.episode_id != entry.episode_id {
+        return Err(EpisodeError::Corrupt(format!(
+            "line {} holds episode {}, index expects {}",
+            entry.line_no, ep.episode_id, entry.episode_id
+        )));
+    }
+    Ok(ep)
+}
+
 // ----------------------------
 // Tests
 // ----------------------------
@@ -432,7 +1301,16 @@ mod tests {
 
         // query by thread + tag
         let q = store
-            .query(Some("main"), &vec!["role:planner".into()], Some(TickId(1)), 10)
+            .query(
+                &EpisodeFilter {
+                    thread_id: Some("main"),
+                    tags_all: &["role:planner".into()],
+                    since_tick: Some(TickId(1)),
+                    ..Default::default()
+                },
+                Some(10),
+                SortOrder::Asc,
+            )
             .unwrap();
         assert_eq!(q.len(), 2);
         assert!(q[0].tick_id <= q[1].tick_id);
@@ -441,4 +1319,520 @@ mod tests {
         let full = store.load_episode_by_entry(&q[0]).unwrap();
         assert_eq!(full.thread_id, "main");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn verify_store_reports_first_corrupted_line() {
+        let (_td, store) = store_in_tmp();
+        for (tick, summary) in [(1, "first"), (2, "second"), (3, "third")] {
+            let ep = Episode::new(RunId("r".into()), TickId(tick), "main", vec![], "t", summary, vec![], 1.0).unwrap();
+            store.append(&ep).unwrap();
+        }
+
+        let clean = store.verify_store().unwrap();
+        assert!(clean.is_ok());
+        assert_eq!((clean.entries, clean.lines, clean.verified), (3, 3, 3));
+
+        let text = fs::read_to_string(store.episodes_path()).unwrap();
+        fs::write(store.episodes_path(), text.replace("\"second\"", "\"tampered\"")).unwrap();
+
+        let report = store.verify_store().unwrap();
+        assert!(!report.is_ok());
+        assert_eq!(report.verified, 2);
+        let d = report.first_discrepancy.unwrap();
+        assert_eq!(d.line_no, 1);
+        assert!(d.reason.contains("hash mismatch"), "{}", d.reason);
+    }
+
+    #[test]
+    fn late_episode_loads_by_byte_offset() {
+        let (_td, store) = store_in_tmp();
+        for tick in 0..50 {
+            let ep = Episode::new(RunId("r".into()), TickId(tick), "main", vec![], "t", format!("summary {tick}"), vec![], 1.0)
+                .unwrap();
+            store.append(&ep).unwrap();
+        }
+
+        let idx = store.load_index().unwrap();
+        let entry = &idx.entries[47];
+        let expected_offset: u64 = fs::read_to_string(store.episodes_path())
+            .unwrap()
+            .lines()
+            .take(47)
+            .map(|l| l.len() as u64 + 1)
+            .sum();
+        assert_eq!(entry.byte_offset, Some(expected_offset));
+
+        let ep = store.load_episode_by_entry(entry).unwrap();
+        assert_eq!(ep.summary, "summary 47");
+        assert_eq!(ep.episode_id, entry.episode_id);
+
+        // Entries from older indexes (no offset) still load via the line scan
+        let legacy = EpisodeIndexEntry { byte_offset: None, ..entry.clone() };
+        assert_eq!(store.load_episode_by_entry(&legacy).unwrap().summary, "summary 47");
+    }
+
+    #[test]
+    fn supersede_links_new_version_and_hides_old() {
+        let (_td, store) = store_in_tmp();
+        let old = Episode::new(RunId("r".into()), TickId(1), "main", vec![], "t", "wrong summary", vec![], 1.0).unwrap();
+        store.append(&old).unwrap();
+
+        let fix = Episode::new(RunId("r".into()), TickId(1), "main", vec![], "t", "corrected summary", vec![], 2.0).unwrap();
+        let new = store.supersede(old.episode_id, fix).unwrap();
+        assert_eq!(new.supersedes, Some(old.episode_id));
+        new.verify_hash().unwrap();
+
+        let latest =
+            store.query(&EpisodeFilter { thread_id: Some("main"), ..Default::default() }, Some(10), SortOrder::Asc).unwrap();
+        assert_eq!(latest.len(), 1);
+        assert_eq!(latest[0].episode_id, new.episode_id);
+
+        // Follow the link back to the original, which is still intact on disk
+        let all = store
+            .query(
+                &EpisodeFilter { thread_id: Some("main"), include_superseded: true, ..Default::default() },
+                Some(10),
+                SortOrder::Asc,
+            ).unwrap();
+        let prev = all.iter().find(|e| Some(e.episode_id) == latest[0].supersedes).unwrap();
+        assert_eq!(prev.superseded_by, Some(new.episode_id));
+        assert_eq!(store.load_episode_by_entry(prev).unwrap().summary, "wrong summary");
+        assert!(store.verify_store().unwrap().is_ok());
+
+        let again = Episode::new(RunId("r".into()), TickId(1), "main", vec![], "t", "x", vec![], 3.0).unwrap();
+        assert!(matches!(store.supersede(old.episode_id, again), Err(EpisodeError::AlreadySuperseded(..))));
+    }
+
+    #[test]
+    fn tags_all_vs_tags_any_on_same_dataset() {
+        let (_td, store) = store_in_tmp();
+        for (tick, tags) in [
+            (3, vec!["role:critic", "status:ok"]),
+            (1, vec!["role:planner", "status:ok"]),
+            (2, vec!["role:executor", "status:ok"]),
+            (4, vec!["role:planner", "status:failed"]),
+        ] {
+            let tags = tags.into_iter().map(String::from).collect();
+            let ep = Episode::new(RunId("r".into()), TickId(tick), "main", tags, "t", "s", vec![], 1.0).unwrap();
+            store.append(&ep).unwrap();
+        }
+        let ticks = |v: Vec<EpisodeIndexEntry>| v.iter().map(|e| e.tick_id.0).collect::<Vec<_>>();
+
+        let all_of = ["role:planner".to_string(), "status:ok".to_string()];
+        let any_of = ["role:planner".to_string(), "role:critic".to_string()];
+
+        let all =
+            store.query(&EpisodeFilter { tags_all: &all_of, ..Default::default() }, Some(10), SortOrder::Asc).unwrap();
+        assert_eq!(ticks(all), vec![1]);
+
+        let any =
+            store.query(&EpisodeFilter { tags_any: &any_of, ..Default::default() }, Some(10), SortOrder::Asc).unwrap();
+        assert_eq!(ticks(any), vec![1, 3, 4]);
+
+        let ok = ["status:ok".to_string()];
+        let both =
+            store.query(&EpisodeFilter { tags_all: &ok, tags_any: &any_of, ..Default::default() }, Some(10), SortOrder::Asc).unwrap();
+        assert_eq!(ticks(both), vec![1, 3]);
+    }
+
+    #[test]
+    fn run_scoped_query_returns_only_that_run() {
+        let (_td, store) = store_in_tmp();
+        for (run, tick) in [("run_a", 1), ("run_b", 1), ("run_a", 2), ("run_b", 3)] {
+            let ep = Episode::new(RunId(run.into()), TickId(tick), "main", vec![], "t", "s", vec![], 1.0).unwrap();
+            store.append(&ep).unwrap();
+        }
+
+        let a =
+            store.query(&EpisodeFilter { run_id: Some("run_a"), ..Default::default() }, Some(10), SortOrder::Asc).unwrap();
+        assert_eq!(a.len(), 2);
+        assert!(a.iter().all(|e| e.run_id.0 == "run_a"));
+
+        let b_late = store
+            .query(
+                &EpisodeFilter { run_id: Some("run_b"), since_tick: Some(TickId(2)), ..Default::default() },
+                Some(10),
+                SortOrder::Asc,
+            )
+            .unwrap();
+        assert_eq!(b_late.len(), 1);
+        assert_eq!(b_late[0].tick_id, TickId(3));
+    }
+
+    #[test]
+    fn query_limit_none_zero_and_cap() {
+        let (_td, store) = store_in_tmp();
+        for tick in 0..30 {
+            let ep = Episode::new(RunId("r".into()), TickId(tick), "main", vec![], "t", "s", vec![], 1.0).unwrap();
+            store.append(&ep).unwrap();
+        }
+        let f = EpisodeFilter::default();
+        assert_eq!(store.query(&f, None, SortOrder::Asc).unwrap().len(), 30);
+        assert!(store.query(&f, Some(0), SortOrder::Asc).unwrap().is_empty());
+        let capped = store.query(&f, Some(3), SortOrder::Asc).unwrap();
+        assert_eq!(capped.iter().map(|e| e.tick_id.0).collect::<Vec<_>>(), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn desc_order_with_limit_one_returns_newest() {
+        let (_td, store) = store_in_tmp();
+        for tick in [4, 9, 2, 7] {
+            let ep = Episode::new(RunId("r".into()), TickId(tick), "main", vec![], "t", "s", vec![], 1.0).unwrap();
+            store.append(&ep).unwrap();
+        }
+        let f = EpisodeFilter::default();
+        let newest = store.query(&f, Some(1), SortOrder::Desc).unwrap();
+        assert_eq!(newest.len(), 1);
+        assert_eq!(newest[0].tick_id, TickId(9));
+        assert_eq!(store.query(&f, Some(1), SortOrder::Asc).unwrap()[0].tick_id, TickId(2));
+        assert_eq!("desc".parse::<SortOrder>(), Ok(SortOrder::Desc));
+    }
+
+    #[test]
+    fn query_page_walks_25_episodes_in_pages_of_10() {
+        let (_td, store) = store_in_tmp();
+        // Ticks appended out of order so paging must follow tick-then-line, not file order.
+        for i in 0..25u64 {
+            let ep = Episode::new(RunId("r".into()), TickId((i * 7) % 25), "main", vec![], "t", "s", vec![], 1.0).unwrap();
+            store.append(&ep).unwrap();
+        }
+        let filter = EpisodeFilter::default();
+
+        let mut seen = vec![];
+        let mut sizes = vec![];
+        let mut after = None;
+        loop {
+            let (page, next) = store.query_page(&filter, after, 10).unwrap();
+            sizes.push(page.len());
+            seen.extend(page);
+            match next {
+                // Cursors survive a round-trip through their token form
+                Some(c) => after = Some(QueryCursor::from_token(&c.to_token()).unwrap()),
+                None => break,
+            }
+        }
+
+        assert_eq!(sizes, vec![10, 10, 5]);
+        let all = store.query(&filter, Some(100), SortOrder::Asc).unwrap();
+        let ids = |v: &[EpisodeIndexEntry]| v.iter().map(|e| e.episode_id).collect::<Vec<_>>();
+        assert_eq!(ids(&seen), ids(&all));
+        assert!(matches!(QueryCursor::from_token("nope"), Err(EpisodeError::InvalidCursor(_))));
+    }
+
+    #[test]
+    fn stats_aggregate_over_seeded_store() {
+        let (_td, store) = store_in_tmp();
+        assert_eq!(store.stats().unwrap(), StoreStats::default());
+
+        for (tick, thread, tags) in [
+            (5, "main", vec!["role:planner", "status:ok"]),
+            (2, "main", vec!["role:critic", "status:ok"]),
+            (9, "side", vec!["status:failed"]),
+        ] {
+            let tags = tags.into_iter().map(String::from).collect();
+            let ep = Episode::new(RunId("r".into()), TickId(tick), thread, tags, "t", "s", vec![], 1.0).unwrap();
+            store.append(&ep).unwrap();
+        }
+
+        let st = store.stats().unwrap();
+        assert_eq!(st.total_episodes, 3);
+        assert_eq!(st.superseded, 0);
+        assert_eq!(st.per_thread.get("main"), Some(&2));
+        assert_eq!(st.per_thread.get("side"), Some(&1));
+        assert_eq!(st.per_tag.get("status:ok"), Some(&2));
+        assert_eq!(st.per_tag.get("role:critic"), Some(&1));
+        assert_eq!((st.min_tick, st.max_tick), (Some(TickId(2)), Some(TickId(9))));
+        assert_eq!(st.total_bytes, fs::metadata(store.episodes_path()).unwrap().len());
+    }
+
+    #[test]
+    fn search_finds_term_in_one_of_three_episodes() {
+        let (_td, store) = store_in_tmp();
+        for (tick, title, summary) in [
+            (1, "Planner tick", "decided to refactor the parser"),
+            (2, "Executor tick", "ran the Migration for accounts"),
+            (3, "Critic tick", "reviewed parser changes"),
+        ] {
+            let ep = Episode::new(RunId("r".into()), TickId(tick), "main", vec![], title, summary, vec![], 1.0).unwrap();
+            store.append(&ep).unwrap();
+        }
+
+        let hits = store.search(&["migration".into()], true, 10).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].tick_id, TickId(2));
+
+        let any = store.search(&["PARSER".into(), "migration".into()], false, 10).unwrap();
+        assert_eq!(any.iter().map(|e| e.tick_id.0).collect::<Vec<_>>(), vec![1, 2, 3]);
+
+        let all = store.search(&["parser".into(), "critic".into()], true, 10).unwrap();
+        assert_eq!(all.len(), 1);
+        assert!(store.search(&[], false, 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn export_import_round_trip_preserves_queries() {
+        let (src_td, src) = store_in_tmp();
+        let mut first = None;
+        for tick in 1..=3 {
+            let ep = Episode::new(RunId("r".into()), TickId(tick), "main", vec!["k:v".into()], "t", format!("s{tick}"), vec![], 1.0)
+                .unwrap();
+            src.append(&ep).unwrap();
+            first.get_or_insert(ep.episode_id);
+        }
+        let fix = Episode::new(RunId("r".into()), TickId(1), "main", vec!["k:v".into()], "t", "fixed", vec![], 2.0).unwrap();
+        src.supersede(first.unwrap(), fix).unwrap();
+
+        let bundle = src_td.path().join("backup/episodes.bundle.json");
+        let bundle_hash = src.export(&bundle).unwrap();
+        assert!(bundle_hash.starts_with("sha256:"));
+
+        let (_dst_td, dst) = store_in_tmp();
+        assert_eq!(dst.import(&bundle, false).unwrap(), 4);
+
+        let everything = EpisodeFilter { include_superseded: true, ..Default::default() };
+        for f in [EpisodeFilter::default(), everything] {
+            let a = src.query(&f, Some(100), SortOrder::Asc).unwrap();
+            let b = dst.query(&f, Some(100), SortOrder::Asc).unwrap();
+            let key = |v: &[EpisodeIndexEntry]| v.iter().map(|e| (e.episode_id, e.hash.clone(), e.superseded_by)).collect::<Vec<_>>();
+            assert_eq!(key(&a), key(&b));
+        }
+        assert!(dst.verify_store().unwrap().is_ok());
+
+        // Non-empty target needs force; a tampered bundle is rejected outright
+        assert!(matches!(dst.import(&bundle, false), Err(EpisodeError::StoreNotEmpty)));
+        assert_eq!(dst.import(&bundle, true).unwrap(), 4);
+        let text = fs::read_to_string(&bundle).unwrap();
+        fs::write(&bundle, text.replace("\"s2\"", "\"sX\"")).unwrap();
+        let (_td3, fresh) = store_in_tmp();
+        assert!(matches!(fresh.import(&bundle, false), Err(EpisodeError::HashMismatch { .. })));
+    }
+
+    #[test]
+    fn rebuild_index_recreates_lost_index() {
+        let (_td, store) = store_in_tmp();
+        let mut ids = vec![];
+        for tick in 1..=3 {
+            let ep = Episode::new(RunId("r".into()), TickId(tick), "main", vec![format!("t:{tick}")], "t", "s", vec![], 1.0)
+                .unwrap();
+            ids.push(store.append(&ep).unwrap().episode_id);
+        }
+        let fix = Episode::new(RunId("r".into()), TickId(4), "main", vec![], "t", "fixed", vec![], 1.0).unwrap();
+        store.supersede(ids[0], fix).unwrap();
+        store.delete(ids[1], "gdpr request", 9.0).unwrap();
+
+        let before = canonical_json_bytes(&store.load_index().unwrap()).unwrap();
+        fs::remove_file(store.index_path()).unwrap();
+        fs::remove_file(store.tag_index_path()).unwrap();
+
+        assert_eq!(store.rebuild_index().unwrap(), 4);
+        assert_eq!(canonical_json_bytes(&store.load_index().unwrap()).unwrap(), before);
+        assert!(store.tag_index_path().exists());
+        assert!(store.verify_store().unwrap().is_ok());
+    }
+
+    #[test]
+    fn compact_drops_deleted_lines_and_keeps_queries() {
+        let (_td, store) = store_in_tmp();
+        let mut ids = vec![];
+        for tick in 1..=5 {
+            let ep = Episode::new(RunId("r".into()), TickId(tick), "main", vec![], "t", format!("episode {tick}"), vec![], 1.0)
+                .unwrap();
+            store.append(&ep).unwrap();
+            ids.push(ep.episode_id);
+        }
+        store.delete(ids[1], "gdpr request", 9.0).unwrap();
+        store.delete(ids[3], "gdpr request", 9.0).unwrap();
+        assert!(matches!(store.delete(Uuid::new_v4(), "x", 0.0), Err(EpisodeError::NotFound(_))));
+
+        let before = store.query(&EpisodeFilter::default(), Some(100), SortOrder::Asc).unwrap();
+        assert_eq!(before.len(), 3);
+        let size_before = fs::metadata(store.episodes_path()).unwrap().len();
+
+        let report = store.compact().unwrap();
+        assert_eq!((report.kept, report.removed), (3, 2));
+        assert_eq!(report.bytes_before, size_before);
+        let size_after = fs::metadata(store.episodes_path()).unwrap().len();
+        assert!(size_after < size_before);
+        assert_eq!(report.bytes_after, size_after);
+        assert!(!fs::read_to_string(store.episodes_path()).unwrap().contains("episode 2"));
+
+        let after = store.query(&EpisodeFilter::default(), Some(100), SortOrder::Asc).unwrap();
+        let key = |v: &[EpisodeIndexEntry]| v.iter().map(|e| (e.episode_id, e.hash.clone())).collect::<Vec<_>>();
+        assert_eq!(key(&before), key(&after));
+        assert_eq!(after.iter().map(|e| e.line_no).collect::<Vec<_>>(), vec![0, 1, 2]);
+        assert_eq!(store.load_episode_by_entry(&after[2]).unwrap().summary, "episode 5");
+        assert!(store.verify_store().unwrap().is_ok());
+    }
+
+    #[test]
+    #[cfg(feature = "hash-chain")]
+    fn verify_chain_detects_removed_middle_episode() {
+        let (_td, store) = store_in_tmp();
+        let mut stored = vec![];
+        for tick in 1..=4 {
+            let ep = Episode::new(RunId("r".into()), TickId(tick), "main", vec![], "t", format!("e{tick}"), vec![], 1.0).unwrap();
+            stored.push(store.append(&ep).unwrap());
+        }
+        assert_eq!(stored[0].prev_hash, None);
+        assert_eq!(stored[2].prev_hash.as_deref(), Some(stored[1].hash.as_str()));
+        assert_eq!(store.verify_chain().unwrap().as_deref(), Some(stored[3].hash.as_str()));
+
+        // Deleting through the store and compacting keeps the chain verifiable
+        store.delete(stored[1].episode_id, "test", 0.0).unwrap();
+        store.compact().unwrap();
+        assert!(store.verify_chain().is_ok());
+
+        // Silently dropping a line does not
+        let text = fs::read_to_string(store.episodes_path()).unwrap();
+        let kept: Vec<&str> = text.lines().enumerate().filter(|(i, _)| *i != 1).map(|(_, l)| l).collect();
+        fs::write(store.episodes_path(), kept.join("\n") + "\n").unwrap();
+        let err = store.verify_chain().unwrap_err();
+        assert!(matches!(err, EpisodeError::ChainBroken { line_no: 1, .. }), "got {err:?}");
+    }
+
+    #[test]
+    fn concurrent_appends_keep_index_consistent() {
+        let (td, _store) = store_in_tmp();
+        let handles: Vec<_> = (0..2)
+            .map(|worker| {
+                // Separate stores (and file handles) per thread, as two processes would have.
+                let store = EpisodeStore::new(td.path().to_path_buf());
+                std::thread::spawn(move || {
+                    for i in 0..20 {
+                        let ep = Episode::new(RunId(format!("w{worker}")), TickId(i), "main", vec![], "t", "s", vec![], 1.0)
+                            .unwrap();
+                        store.append(&ep).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for h in handles {
+            h.join().unwrap();
+        }
+
+        let store = EpisodeStore::new(td.path().to_path_buf());
+        let idx = store.load_index().unwrap();
+        let line_nos: Vec<u64> = idx.entries.iter().map(|e| e.line_no).collect();
+        assert_eq!(line_nos, (0..40).collect::<Vec<u64>>());
+        assert!(store.verify_store().unwrap().is_ok());
+    }
+
+    #[test]
+    fn tag_index_queries_match_brute_force_scan() {
+        let (_td, store) = store_in_tmp();
+        let tags = ["a", "b", "c", "d", "e"];
+        // Small LCG so the tag mix is varied but reproducible.
+        let mut seed: u64 = 7;
+        let mut ids = vec![];
+        for _ in 0..400 {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            let picked: Vec<String> =
+                tags.iter().enumerate().filter(|(b, _)| seed >> (33 + b) & 1 == 1).map(|(_, t)| t.to_string()).collect();
+            let ep = Episode::new(RunId("r".into()), TickId(seed % 50), "main", picked, "t", "s", vec![], 1.0).unwrap();
+            ids.push(store.append(&ep).unwrap().episode_id);
+        }
+        store.delete(ids[3], "test", 0.0).unwrap();
+        store.delete(ids[100], "test", 0.0).unwrap();
+
+        let idx = store.load_index().unwrap();
+        let s = |v: &[&str]| v.iter().map(|t| t.to_string()).collect::<Vec<_>>();
+        let cases = [
+            (s(&["a"]), s(&[])),
+            (s(&["a", "c"]), s(&[])),
+            (s(&["b", "d", "e"]), s(&[])),
+            (s(&[]), s(&["c", "e"])),
+            (s(&["a"]), s(&["b", "d"])),
+            (s(&["missing"]), s(&[])),
+        ];
+        for (all, any) in &cases {
+            let filter = EpisodeFilter { tags_all: all, tags_any: any, since_tick: Some(TickId(10)), ..Default::default() };
+            let mut brute: Vec<&EpisodeIndexEntry> = idx.entries.iter().filter(|e| filter.matches(e)).collect();
+            brute.sort_by_key(|e| (e.tick_id, e.line_no));
+            let fast = store.query(&filter, None, SortOrder::Asc).unwrap();
+            let want: Vec<Uuid> = brute.iter().map(|e| e.episode_id).collect();
+            let got: Vec<Uuid> = fast.iter().map(|e| e.episode_id).collect();
+            assert_eq!(got, want, "all={all:?} any={any:?}");
+            assert_eq!(want.is_empty(), all.contains(&"missing".to_string()));
+        }
+        assert!(store.tag_index_path().exists());
+    }
+
+    #[test]
+    fn iter_streams_every_episode_in_file_order() {
+        let (_td, store) = store_in_tmp();
+        assert_eq!(store.iter().count(), 0);
+        for tick in [3, 1, 2, 5, 4] {
+            let ep = Episode::new(RunId("r".into()), TickId(tick), "main", vec![], "t", "s", vec![], 1.0).unwrap();
+            store.append(&ep).unwrap();
+        }
+        let ticks: Vec<u64> = store.iter().map(|ep| ep.unwrap().tick_id.0).collect();
+        assert_eq!(ticks, vec![3, 1, 2, 5, 4]);
+
+        // A tampered line surfaces as an error item rather than ending the stream early
+        let text = fs::read_to_string(store.episodes_path()).unwrap().replacen("\"summary\":\"s\"", "\"summary\":\"x\"", 1);
+        fs::write(store.episodes_path(), text).unwrap();
+        let results: Vec<_> = store.iter().collect();
+        assert_eq!(results.len(), 5);
+        assert!(matches!(results[0], Err(EpisodeError::HashMismatch { .. })));
+    }
+
+    #[test]
+    fn index_writes_go_through_temp_file_and_rename() {
+        let (_td, store) = store_in_tmp();
+        let ep = Episode::new(RunId("r".into()), TickId(1), "main", vec![], "t", "s", vec![], 1.0).unwrap();
+        store.append(&ep).unwrap();
+
+        // A leftover temp file from an interrupted write is ignored by readers and replaced
+        let tmp = store.base_dir().join("index.json.tmp");
+        fs::write(&tmp, b"{\"schema_version\":1,\"entr").unwrap();
+        assert_eq!(store.load_index().unwrap().entries.len(), 1);
+
+        let ep = Episode::new(RunId("r".into()), TickId(2), "main", vec![], "t", "s", vec![], 1.0).unwrap();
+        store.append(&ep).unwrap();
+        assert!(!tmp.exists());
+        assert!(!store.base_dir().join("tags.json.tmp").exists());
+        assert_eq!(store.load_index().unwrap().entries.len(), 2);
+        assert!(store.verify_store().unwrap().is_ok());
+
+        store.compact().unwrap();
+        assert!(!store.base_dir().join("episodes.jsonl.tmp").exists());
+        assert!(store.verify_store().unwrap().is_ok());
+    }
+
+    #[test]
+    fn size_caps_reject_oversized_title_and_summary() {
+        let td = TempDir::new().unwrap();
+        let store = EpisodeStore::new(td.path().to_path_buf()).with_size_caps(Some(8), Some(16));
+        let mk = |title: &str, summary: &str| {
+            Episode::new(RunId("r".into()), TickId(1), "main", vec![], title, summary, vec![], 1.0).unwrap()
+        };
+
+        let err = store.append(&mk("a title that is long", "ok")).unwrap_err();
+        assert!(matches!(err, EpisodeError::TooLarge { field: "title", len: 20, max: 8 }), "got {err:?}");
+        let err = store.append(&mk("ok", &"é".repeat(17))).unwrap_err();
+        assert!(matches!(err, EpisodeError::TooLarge { field: "summary", len: 17, max: 16 }), "got {err:?}");
+
+        // Exactly at the cap (counted in chars, not bytes) is accepted
+        store.append(&mk("12345678", &"é".repeat(16))).unwrap();
+        assert_eq!(store.load_index().unwrap().entries.len(), 1);
+    }
+
+    #[test]
+    fn load_many_reads_requested_episodes_in_one_call() {
+        let (_td, store) = store_in_tmp();
+        let mut ids = vec![];
+        for tick in 0..5 {
+            let ep = Episode::new(RunId("r".into()), TickId(tick), "main", vec![], "t", format!("e{tick}"), vec![], 1.0).unwrap();
+            ids.push(store.append(&ep).unwrap().episode_id);
+        }
+
+        let want = [ids[4], ids[0], ids[2]];
+        let got = store.load_many(&want).unwrap();
+        assert_eq!(got.iter().map(|e| e.episode_id).collect::<Vec<_>>(), want);
+        assert_eq!(got.iter().map(|e| e.summary.as_str()).collect::<Vec<_>>(), ["e4", "e0", "e2"]);
+
+        let ghost = Uuid::new_v4();
+        let err = store.load_many(&[ids[1], ghost]).unwrap_err();
+        assert!(matches!(err, EpisodeError::MissingEpisodes(ref m) if m == &vec![ghost]), "got {err:?}");
+    }
+}
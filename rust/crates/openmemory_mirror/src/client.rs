@@ -1,6 +1,8 @@
-use crate::payload::{AddMemoryRequest, AddMemoryResponse, QueryMemoryParsed, QueryMemoryRequest, QueryHitRef};
-use reqwest::Client;
+use crate::payload::{AddMemoryRequest, AddMemoryResponse, HealthStatus, QueryMemoryParsed, QueryMemoryRequest, QueryHitRef};
+use pie_episodes::Episode;
+use reqwest::{Client, RequestBuilder, Response, StatusCode};
 use reqwest::header::{AUTHORIZATION, HeaderMap, HeaderValue, CONTENT_TYPE};
+use std::time::{Duration, Instant};
 use thiserror::Error;
 use serde_json::Value as JsonValue;
 
@@ -8,14 +10,128 @@ use serde_json::Value as JsonValue;
 pub enum OpenMemoryError {
     #[error("http error: {0}")]
     Http(#[from] reqwest::Error),
+    /// Non-2xx reply; `body` is kept for diagnostics.
+    #[error("http status {status}: {body}")]
+    HttpStatus { status: u16, body: String },
+    /// 2xx reply with an unexpected shape (or an unusable header value).
     #[error("invalid response: {0}")]
     InvalidResponse(String),
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("json error: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("canonical json error: {0}")]
+    Canon(#[from] pie_common::CanonError),
+}
+
+/// Retry schedule for OpenMemory calls. Mirroring is best-effort and non-authoritative, so
+/// unlike the providers crate we do retry here: 429, 5xx, connect errors and timeouts are
+/// retried with exponential backoff plus jitter; any other 4xx fails on the first attempt.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    /// Total attempts including the first (1 = no retries).
+    pub max_attempts: u32,
+    pub base_delay_ms: u64,
+    pub max_delay_ms: u64,
+    /// No new attempt starts once this much time has passed since the first.
+    pub deadline_ms: u64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self { max_attempts: 3, base_delay_ms: 200, max_delay_ms: 2_000, deadline_ms: 10_000 }
+    }
+}
+
+impl RetryPolicy {
+    pub fn none() -> Self {
+        Self { max_attempts: 1, ..Self::default() }
+    }
+
+    /// Delay before retry number `retry` (1-based): base * 2^(retry-1), capped, plus up to 50% jitter.
+    fn delay(&self, retry: u32) -> Duration {
+        let exp = self.base_delay_ms.saturating_mul(1u64 << (retry - 1).min(20)).min(self.max_delay_ms);
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.subsec_nanos() as u64)
+            .unwrap_or(0);
+        Duration::from_millis(exp + nanos % (exp / 2 + 1))
+    }
+}
+
+fn retryable_status(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+fn retryable_error(e: &reqwest::Error) -> bool {
+    e.is_connect() || e.is_timeout()
+}
+
+/// Endpoint paths, appended to the base url. Override for deployments that mount the API
+/// under a prefix or rename routes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OpenMemoryPaths {
+    pub add: String,
+    pub add_batch: String,
+    pub query: String,
+    /// `{id}` is replaced with the remote memory id (also for `delete`).
+    pub get: String,
+    pub delete: String,
+    pub health: String,
+}
+
+impl Default for OpenMemoryPaths {
+    fn default() -> Self {
+        Self {
+            add: "/memory/add".to_string(),
+            add_batch: "/memory/add/batch".to_string(),
+            query: "/memory/query".to_string(),
+            get: "/memory/{id}".to_string(),
+            delete: "/memory/{id}".to_string(),
+            health: "/health".to_string(),
+        }
+    }
+}
+
+/// Which header(s) carry the api key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AuthMode {
+    /// `Authorization: Bearer <key>` only.
+    Bearer,
+    /// `x-api-key: <key>` only.
+    ApiKey,
+    /// Both headers (matches the original Python client).
+    #[default]
+    Both,
 }
 
 pub struct OpenMemoryClient {
     base_url: String,
     api_key: Option<String>,
     client: Client,
+    retry: RetryPolicy,
+    paths: OpenMemoryPaths,
+    auth_mode: AuthMode,
+    /// Reject responses that don't match the expected schema instead of parsing tolerantly.
+    strict: bool,
+}
+
+/// Apply a per-call timeout override, if any (reqwest lets it supersede the client's).
+fn timed(rb: RequestBuilder, timeout: Option<Duration>) -> RequestBuilder {
+    match timeout {
+        Some(t) => rb.timeout(t),
+        None => rb,
+    }
+}
+
+/// Non-2xx responses become `HttpStatus` errors.
+async fn check_status(resp: Response) -> Result<Response, OpenMemoryError> {
+    if resp.status().is_success() {
+        return Ok(resp);
+    }
+    let status = resp.status().as_u16();
+    let body = resp.text().await.unwrap_or_default();
+    Err(OpenMemoryError::HttpStatus { status, body })
 }
 
 impl OpenMemoryClient {
@@ -23,58 +139,277 @@ impl OpenMemoryClient {
         let client = Client::builder()
             .timeout(std::time::Duration::from_millis(timeout_ms))
             .build()?;
-        Ok(Self { base_url, api_key, client })
+        Ok(Self {
+            base_url,
+            api_key,
+            client,
+            retry: RetryPolicy::default(),
+            paths: OpenMemoryPaths::default(),
+            auth_mode: AuthMode::default(),
+            strict: false,
+        })
+    }
+
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    pub fn with_paths(mut self, paths: OpenMemoryPaths) -> Self {
+        self.paths = paths;
+        self
+    }
+
+    /// Strict mode: a query response must be `[..]` or `{matches|memories|results|items|data: [..]}`
+    /// whose items all carry a string `id|memory_id` and `content|text`; an add response must
+    /// carry a non-empty `id`. Otherwise `InvalidResponse` names the offending shape, rather than
+    /// tolerant mode's silent zero hits.
+    pub fn with_strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Send only the auth header the backend expects (default `Both`).
+    pub fn with_auth_mode(mut self, mode: AuthMode) -> Self {
+        self.auth_mode = mode;
+        self
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url.trim_end_matches('/'), path)
+    }
+
+    /// Send the request built by `build`, retrying per `self.retry`. Returns the first response
+    /// that is not retried (callers check its status); transport errors that exhaust the
+    /// retries are returned as-is.
+    async fn send_with_retry(&self, build: impl Fn() -> RequestBuilder) -> Result<Response, OpenMemoryError> {
+        let started = Instant::now();
+        let deadline = started + Duration::from_millis(self.retry.deadline_ms);
+        let mut attempt = 1;
+        loop {
+            let retry_delay = (attempt < self.retry.max_attempts).then(|| self.retry.delay(attempt));
+            let can_retry = retry_delay.is_some_and(|d| Instant::now() + d < deadline);
+            match build().send().await {
+                Ok(resp) if can_retry && retryable_status(resp.status()) => {}
+                Ok(resp) => return Ok(resp),
+                Err(e) if can_retry && retryable_error(&e) => {}
+                Err(e) => return Err(e.into()),
+            }
+            if let Some(d) = retry_delay {
+                tokio::time::sleep(d).await;
+            }
+            attempt += 1;
+        }
     }
 
     fn build_headers(&self) -> Result<HeaderMap, OpenMemoryError> {
         let mut headers = HeaderMap::new();
         headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
 
-        // Match proven Python behavior: send BOTH auth headers if key exists.
+        // Default matches proven Python behavior: send BOTH auth headers if key exists.
         if let Some(k) = &self.api_key {
-            let v = format!("Bearer {}", k);
-            headers.insert(
-                AUTHORIZATION,
-                HeaderValue::from_str(&v).map_err(|e| OpenMemoryError::InvalidResponse(e.to_string()))?,
-            );
-            headers.insert(
-                "x-api-key",
-                HeaderValue::from_str(k).map_err(|e| OpenMemoryError::InvalidResponse(e.to_string()))?,
-            );
+            if matches!(self.auth_mode, AuthMode::Bearer | AuthMode::Both) {
+                let v = format!("Bearer {}", k);
+                headers.insert(
+                    AUTHORIZATION,
+                    HeaderValue::from_str(&v).map_err(|e| OpenMemoryError::InvalidResponse(e.to_string()))?,
+                );
+            }
+            if matches!(self.auth_mode, AuthMode::ApiKey | AuthMode::Both) {
+                headers.insert(
+                    "x-api-key",
+                    HeaderValue::from_str(k).map_err(|e| OpenMemoryError::InvalidResponse(e.to_string()))?,
+                );
+            }
         }
         Ok(headers)
     }
 
-        pub async fn add_memory(&self, req: &AddMemoryRequest) -> Result<AddMemoryResponse, OpenMemoryError> {
-        let url = format!("{}/memory/add", self.base_url.trim_end_matches('/'));
+    pub async fn add_memory(&self, req: &AddMemoryRequest) -> Result<AddMemoryResponse, OpenMemoryError> {
+        self.add_memory_inner(req, None).await
+    }
 
-        let headers = self.build_headers()?;
+    /// `add_memory` with `timeout` replacing the client-wide timeout for this call only.
+    pub async fn add_memory_with_timeout(
+        &self,
+        req: &AddMemoryRequest,
+        timeout: Duration,
+    ) -> Result<AddMemoryResponse, OpenMemoryError> {
+        self.add_memory_inner(req, Some(timeout)).await
+    }
+
+    async fn add_memory_inner(
+        &self,
+        req: &AddMemoryRequest,
+        timeout: Option<Duration>,
+    ) -> Result<AddMemoryResponse, OpenMemoryError> {
+        let url = self.url(&self.paths.add);
 
-        let resp = self.client.post(url).headers(headers).json(req).send().await?;
-        if !resp.status().is_success() {
-            let status = resp.status();
-            let body = resp.text().await.unwrap_or_default();
-            return Err(OpenMemoryError::InvalidResponse(format!("status={} body={}", status, body)));
+        let mut headers = self.build_headers()?;
+        if let Some(key) = &req.idempotency_key {
+            headers.insert(
+                "idempotency-key",
+                HeaderValue::from_str(key).map_err(|e| OpenMemoryError::InvalidResponse(e.to_string()))?,
+            );
         }
 
-        Ok(resp.json::<AddMemoryResponse>().await?)
+        let resp = self
+            .send_with_retry(|| timed(self.client.post(&url).headers(headers.clone()).json(req), timeout))
+            .await?;
+        let resp = check_status(resp).await?;
+        let out = resp.json::<AddMemoryResponse>().await?;
+        if self.strict && out.id.is_empty() {
+            return Err(OpenMemoryError::InvalidResponse("add response has an empty id".into()));
+        }
+        Ok(out)
     }
 
-    pub async fn query_memory(&self, req: &QueryMemoryRequest) -> Result<QueryMemoryParsed, OpenMemoryError> {
-        let url = format!("{}/memory/query", self.base_url.trim_end_matches('/'));
+    /// Add several memories in one call (`POST /memory/add/batch` with `{"memories": [...]}`).
+    /// Responses are returned in input order; a count mismatch is an `InvalidResponse`.
+    /// Backends without the endpoint (404/405) get one `add_memory` per request instead.
+    /// Per-request idempotency keys only travel on that sequential fallback, so the batch POST
+    /// itself is sent once and never retried: a retry after a server-side success would store
+    /// every memory twice.
+    pub async fn add_memory_batch(&self, reqs: &[AddMemoryRequest]) -> Result<Vec<AddMemoryResponse>, OpenMemoryError> {
+        if reqs.is_empty() {
+            return Ok(vec![]);
+        }
+        let url = self.url(&self.paths.add_batch);
         let headers = self.build_headers()?;
+        let body = serde_json::json!({ "memories": reqs });
 
-        let resp = self.client.post(url).headers(headers).json(req).send().await?;
-        if !resp.status().is_success() {
-            let status = resp.status();
-            let body = resp.text().await.unwrap_or_default();
-            return Err(OpenMemoryError::InvalidResponse(format!("status={} body={}", status, body)));
+        let resp = self.client.post(&url).headers(headers).json(&body).send().await?;
+        if matches!(resp.status(), StatusCode::NOT_FOUND | StatusCode::METHOD_NOT_ALLOWED) {
+            let mut out = Vec::with_capacity(reqs.len());
+            for req in reqs {
+                out.push(self.add_memory(req).await?);
+            }
+            return Ok(out);
         }
+        let raw: JsonValue = check_status(resp).await?.json().await?;
+        // Accept a bare array or {results|memories|items: [...]}.
+        let items = ["results", "memories", "items"]
+            .iter()
+            .find_map(|k| raw.get(k))
+            .unwrap_or(&raw)
+            .clone();
+        let out: Vec<AddMemoryResponse> = serde_json::from_value(items)
+            .map_err(|e| OpenMemoryError::InvalidResponse(format!("batch add response: {e}")))?;
+        if out.len() != reqs.len() {
+            return Err(OpenMemoryError::InvalidResponse(format!(
+                "batch add returned {} results for {} requests",
+                out.len(),
+                reqs.len()
+            )));
+        }
+        Ok(out)
+    }
 
+    pub async fn query_memory(&self, req: &QueryMemoryRequest) -> Result<QueryMemoryParsed, OpenMemoryError> {
+        self.query_memory_inner(req, None).await
+    }
+
+    /// `query_memory` with `timeout` replacing the client-wide timeout for this call only
+    /// (e.g. a long bulk query on a client tuned for quick probes).
+    pub async fn query_memory_with_timeout(
+        &self,
+        req: &QueryMemoryRequest,
+        timeout: Duration,
+    ) -> Result<QueryMemoryParsed, OpenMemoryError> {
+        self.query_memory_inner(req, Some(timeout)).await
+    }
+
+    async fn query_memory_inner(
+        &self,
+        req: &QueryMemoryRequest,
+        timeout: Option<Duration>,
+    ) -> Result<QueryMemoryParsed, OpenMemoryError> {
+        let url = self.url(&self.paths.query);
+        let headers = self.build_headers()?;
+
+        let resp = self
+            .send_with_retry(|| timed(self.client.post(&url).headers(headers.clone()).json(req), timeout))
+            .await?;
+        let resp = check_status(resp).await?;
         let raw: JsonValue = resp.json().await?;
+        if self.strict {
+            validate_query_shape(&raw).map_err(OpenMemoryError::InvalidResponse)?;
+        }
         let hits = extract_hit_refs(&raw);
         Ok(QueryMemoryParsed { raw, hits })
     }
+
+    /// Readiness probe: `GET /health` with the usual auth headers, sent once (no retries) so it
+    /// reflects the backend right now. Non-2xx (401/403 for a rejected key) is an `HttpStatus`
+    /// error; a 2xx body reporting a bad status yields `healthy: false`.
+    pub async fn health(&self) -> Result<HealthStatus, OpenMemoryError> {
+        let url = self.url(&self.paths.health);
+        let headers = self.build_headers()?;
+
+        let resp = self.client.get(&url).headers(headers).send().await?;
+        let resp = check_status(resp).await?;
+        Ok(HealthStatus::from_body(&resp.text().await?))
+    }
+
+    /// Mirror one local episode (payload from `AddMemoryRequest::from_episode`).
+    pub async fn mirror_episode(&self, ep: &Episode, user_id: Option<String>) -> Result<AddMemoryResponse, OpenMemoryError> {
+        self.add_memory(&AddMemoryRequest::from_episode(ep, user_id)).await
+    }
+
+    /// Fetch one memory by remote id as raw JSON (e.g. to confirm a mirror landed). The body
+    /// includes the memory content, so callers must not log it.
+    pub async fn get_memory(&self, id: &str) -> Result<JsonValue, OpenMemoryError> {
+        let url = self.url(&self.paths.get.replace("{id}", id));
+        let headers = self.build_headers()?;
+
+        let resp = self.send_with_retry(|| self.client.get(&url).headers(headers.clone())).await?;
+        let resp = check_status(resp).await?;
+        Ok(resp.json().await?)
+    }
+
+    /// Remove a mirrored memory. A 404 means it is already gone and counts as success.
+    pub async fn delete_memory(&self, remote_id: &str) -> Result<(), OpenMemoryError> {
+        let url = self.url(&self.paths.delete.replace("{id}", remote_id));
+        let headers = self.build_headers()?;
+
+        let resp = self.send_with_retry(|| self.client.delete(&url).headers(headers.clone())).await?;
+        if resp.status() == StatusCode::NOT_FOUND {
+            return Ok(());
+        }
+        check_status(resp).await?;
+        Ok(())
+    }
+}
+
+/// Compact description of a JSON value's shape for error messages (never includes values).
+fn shape_of(v: &JsonValue) -> String {
+    match v {
+        JsonValue::Null => "null".into(),
+        JsonValue::Bool(_) => "bool".into(),
+        JsonValue::Number(_) => "number".into(),
+        JsonValue::String(_) => "string".into(),
+        JsonValue::Array(a) => format!("array[{}]", a.len()),
+        JsonValue::Object(o) => format!("object{{{}}}", o.keys().cloned().collect::<Vec<_>>().join(",")),
+    }
+}
+
+/// Strict-mode check of a query response; see `OpenMemoryClient::with_strict`.
+fn validate_query_shape(raw: &JsonValue) -> Result<(), String> {
+    let items = match raw {
+        JsonValue::Array(a) => a,
+        JsonValue::Object(o) => ["matches", "memories", "results", "items", "data"]
+            .iter()
+            .find_map(|k| o.get(*k).and_then(|v| v.as_array()))
+            .ok_or_else(|| format!("query response has no result list: {}", shape_of(raw)))?,
+        other => return Err(format!("query response is {}", shape_of(other))),
+    };
+    for (i, it) in items.iter().enumerate() {
+        let str_field = |a: &str, b: &str| it.get(a).or_else(|| it.get(b)).is_some_and(|v| v.is_string());
+        if !str_field("id", "memory_id") || !str_field("content", "text") {
+            return Err(format!("query hit {i} lacks string id/content: {}", shape_of(it)));
+        }
+    }
+    Ok(())
 }
 
 fn extract_hit_refs(raw: &JsonValue) -> Vec<QueryHitRef> {
@@ -125,9 +460,389 @@ fn extract_hit_refs(raw: &JsonValue) -> Vec<QueryHitRef> {
             .or_else(|| o.get("salience"))
             .and_then(|v| v.as_f64());
 
+        let tags = o.get("tags")
+            .and_then(|v| v.as_array())
+            .map(|a| a.iter().filter_map(|t| t.as_str().map(str::to_string)).collect())
+            .unwrap_or_default();
+
+        let created_ts = o.get("created_ts")
+            .or_else(|| o.get("created_at"))
+            .and_then(|v| v.as_f64().or_else(|| v.as_str().and_then(|s| s.parse().ok())));
+
+        let metadata = o.get("metadata").filter(|v| !v.is_null()).map(|v| {
+            let mut v = v.clone();
+            if let Some(m) = v.as_object_mut() {
+                m.remove("content");
+                m.remove("text");
+            }
+            v
+        });
+
         // IMPORTANT: never return content, only hash it.
         let content_hash = pie_common::sha256_bytes(content.as_bytes());
-        out.push(QueryHitRef { id, score, content_hash });
+        out.push(QueryHitRef { id, score, content_hash, tags, created_ts, metadata });
     }
     out
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use wiremock::matchers::{body_partial_json, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn fast_retry(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy { max_attempts, base_delay_ms: 1, max_delay_ms: 5, deadline_ms: 5_000 }
+    }
+
+    fn add_req() -> AddMemoryRequest {
+        AddMemoryRequest { content: "hello".into(), tags: vec![], metadata: None, user_id: None, idempotency_key: None }
+    }
+
+    #[tokio::test]
+    async fn add_memory_retries_transient_503_then_succeeds() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/memory/add"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(2)
+            .with_priority(1)
+            .expect(2)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/memory/add"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "id": "mem_1" })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = OpenMemoryClient::new(server.uri(), None, 2_000).unwrap().with_retry(fast_retry(3));
+        let resp = client.add_memory(&add_req()).await.unwrap();
+        assert_eq!(resp.id, "mem_1");
+    }
+
+    #[tokio::test]
+    async fn non_retryable_4xx_fails_on_first_attempt() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/memory/add"))
+            .respond_with(ResponseTemplate::new(400).set_body_string("bad"))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = OpenMemoryClient::new(server.uri(), None, 2_000).unwrap().with_retry(fast_retry(5));
+        let err = client.add_memory(&add_req()).await.unwrap_err();
+        assert!(matches!(err, OpenMemoryError::HttpStatus { status: 400, ref body } if body == "bad"), "got {err}");
+    }
+
+    #[tokio::test]
+    async fn unauthorized_and_unavailable_map_to_http_status() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/memory/add"))
+            .respond_with(ResponseTemplate::new(401).set_body_string("no key"))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/memory/query"))
+            .respond_with(ResponseTemplate::new(503))
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let client = OpenMemoryClient::new(server.uri(), None, 2_000).unwrap().with_retry(fast_retry(2));
+        let err = client.add_memory(&add_req()).await.unwrap_err();
+        assert!(matches!(err, OpenMemoryError::HttpStatus { status: 401, .. }), "got {err}");
+
+        // 503 is retried, then surfaces with its status once attempts run out
+        let req = QueryMemoryRequest { query: "q".into(), k: None, user_id: None, min_score: None, offset: None };
+        let err = client.query_memory(&req).await.unwrap_err();
+        assert!(matches!(err, OpenMemoryError::HttpStatus { status: 503, .. }), "got {err}");
+    }
+
+    #[tokio::test]
+    async fn delete_memory_hits_configured_path_and_tolerates_404() {
+        let server = MockServer::start().await;
+        Mock::given(method("DELETE"))
+            .and(path("/memory/mem_1"))
+            .and(header("x-api-key", "k"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("DELETE"))
+            .and(path("/api/memories/gone"))
+            .respond_with(ResponseTemplate::new(404))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = OpenMemoryClient::new(server.uri(), Some("k".into()), 2_000).unwrap();
+        client.delete_memory("mem_1").await.unwrap();
+
+        let client = client.with_paths(OpenMemoryPaths { delete: "/api/memories/{id}".into(), ..Default::default() });
+        client.delete_memory("gone").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn mirror_episode_sends_idempotency_key_derived_from_hash() {
+        use pie_episodes::{RunId, TickId};
+        let ep = Episode::new(RunId("r".into()), TickId(1), "main", vec![], "t", "s", vec![], 0.0).unwrap();
+        let key = crate::payload::episode_idempotency_key(&ep.hash);
+        assert_eq!(key, format!("pieBot-episode-{}", &ep.hash["sha256:".len()..]));
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/memory/add"))
+            .and(header("Idempotency-Key", key.as_str()))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "id": "mem_1" })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = OpenMemoryClient::new(server.uri(), None, 2_000).unwrap();
+        client.mirror_episode(&ep, None).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn query_sends_offset_only_when_set() {
+        let mut req = QueryMemoryRequest { query: "q".into(), k: Some(5), user_id: None, min_score: None, offset: None };
+        assert!(serde_json::to_value(&req).unwrap().get("offset").is_none());
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/memory/query"))
+            .and(body_partial_json(json!({ "query": "q", "k": 5, "offset": 10 })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "matches": [] })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        req.offset = Some(10);
+        let client = OpenMemoryClient::new(server.uri(), None, 2_000).unwrap();
+        assert!(client.query_memory(&req).await.unwrap().hits.is_empty());
+    }
+
+    #[tokio::test]
+    async fn health_parses_healthy_and_unhealthy_bodies() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/health"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "status": "ok" })))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/v2/health"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "status": "degraded", "db": "down" })))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/locked/health"))
+            .respond_with(ResponseTemplate::new(401))
+            .mount(&server)
+            .await;
+
+        let client = OpenMemoryClient::new(server.uri(), None, 2_000).unwrap();
+        assert_eq!(client.health().await.unwrap(), HealthStatus { healthy: true, status: Some("ok".into()) });
+
+        let client = client.with_paths(OpenMemoryPaths { health: "/v2/health".into(), ..Default::default() });
+        let h = client.health().await.unwrap();
+        assert!(!h.healthy);
+        assert_eq!(h.status.as_deref(), Some("degraded"));
+
+        let client = client.with_paths(OpenMemoryPaths { health: "/locked/health".into(), ..Default::default() });
+        let err = client.health().await.unwrap_err();
+        assert!(matches!(err, OpenMemoryError::HttpStatus { status: 401, .. }), "got {err}");
+    }
+
+    #[tokio::test]
+    async fn custom_paths_route_add_and_query_under_prefix() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/om/api/v1/memories"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "id": "mem_1" })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/om/api/v1/search"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!([{ "id": "mem_1", "content": "x" }])))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let paths = OpenMemoryPaths {
+            add: "/om/api/v1/memories".into(),
+            query: "/om/api/v1/search".into(),
+            ..Default::default()
+        };
+        // Trailing slash on the base url is still tolerated.
+        let client = OpenMemoryClient::new(format!("{}/", server.uri()), None, 2_000).unwrap().with_paths(paths);
+        assert_eq!(client.add_memory(&add_req()).await.unwrap().id, "mem_1");
+        let req = QueryMemoryRequest { query: "q".into(), k: None, user_id: None, min_score: None, offset: None };
+        assert_eq!(client.query_memory(&req).await.unwrap().hits.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn batch_add_posts_three_memories_in_one_call() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/memory/add/batch"))
+            .and(body_partial_json(json!({ "memories": [{ "content": "a" }, { "content": "b" }, { "content": "c" }] })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "results": [{ "id": "m_a" }, { "id": "m_b" }, { "id": "m_c" }]
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/memory/add"))
+            .respond_with(ResponseTemplate::new(500))
+            .expect(0)
+            .mount(&server)
+            .await;
+
+        let reqs: Vec<AddMemoryRequest> =
+            ["a", "b", "c"].iter().map(|c| AddMemoryRequest { content: c.to_string(), ..add_req() }).collect();
+        let client = OpenMemoryClient::new(server.uri(), None, 2_000).unwrap();
+        let out = client.add_memory_batch(&reqs).await.unwrap();
+        assert_eq!(out.iter().map(|r| r.id.as_str()).collect::<Vec<_>>(), ["m_a", "m_b", "m_c"]);
+    }
+
+    #[tokio::test]
+    async fn batch_add_is_not_retried() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/memory/add/batch"))
+            .respond_with(ResponseTemplate::new(503))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = OpenMemoryClient::new(server.uri(), None, 2_000).unwrap().with_retry(RetryPolicy {
+            base_delay_ms: 1,
+            max_delay_ms: 1,
+            ..RetryPolicy::default()
+        });
+        assert!(client.add_memory_batch(&[add_req(), add_req()]).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn batch_add_falls_back_to_sequential_without_endpoint() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/memory/add/batch"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/memory/add"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "id": "m" })))
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let client = OpenMemoryClient::new(server.uri(), None, 2_000).unwrap();
+        assert_eq!(client.add_memory_batch(&[add_req(), add_req()]).await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn get_memory_returns_raw_object() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/memory/mem_1"))
+            .and(header("authorization", "Bearer k"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "id": "mem_1", "content": "hi", "tags": ["a"] })))
+            .mount(&server)
+            .await;
+
+        let client = OpenMemoryClient::new(server.uri(), Some("k".into()), 2_000).unwrap();
+        let got = client.get_memory("mem_1").await.unwrap();
+        assert_eq!(got["id"], "mem_1");
+        assert_eq!(got["tags"], json!(["a"]));
+
+        let err = client.get_memory("missing").await.unwrap_err();
+        assert!(matches!(err, OpenMemoryError::HttpStatus { status: 404, .. }), "got {err}");
+    }
+
+    #[tokio::test]
+    async fn strict_mode_rejects_shape_tolerant_mode_reads_as_empty() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/memory/query"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "hits": [{ "id": "m" }], "took_ms": 3 })))
+            .mount(&server)
+            .await;
+        let req = QueryMemoryRequest { query: "q".into(), k: None, user_id: None, min_score: None, offset: None };
+
+        let tolerant = OpenMemoryClient::new(server.uri(), None, 2_000).unwrap();
+        assert!(tolerant.query_memory(&req).await.unwrap().hits.is_empty());
+
+        let strict = OpenMemoryClient::new(server.uri(), None, 2_000).unwrap().with_strict(true);
+        let err = strict.query_memory(&req).await.unwrap_err();
+        match err {
+            OpenMemoryError::InvalidResponse(msg) => assert!(msg.contains("object{hits,took_ms}"), "got {msg}"),
+            other => panic!("expected InvalidResponse, got {other}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn per_call_timeout_overrides_generous_client_default() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/memory/query"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!([])).set_delay(Duration::from_millis(500)))
+            .mount(&server)
+            .await;
+        let req = QueryMemoryRequest { query: "q".into(), k: None, user_id: None, min_score: None, offset: None };
+
+        let client = OpenMemoryClient::new(server.uri(), None, 10_000).unwrap().with_retry(RetryPolicy::none());
+        let started = Instant::now();
+        let err = client.query_memory_with_timeout(&req, Duration::from_millis(50)).await.unwrap_err();
+        assert!(matches!(err, OpenMemoryError::Http(ref e) if e.is_timeout()), "got {err}");
+        assert!(started.elapsed() < Duration::from_millis(400));
+
+        // Without the override the client default applies and the slow reply arrives
+        assert!(client.query_memory(&req).await.unwrap().hits.is_empty());
+    }
+
+    #[test]
+    fn auth_mode_selects_headers() {
+        let headers = |mode| {
+            let client = OpenMemoryClient::new("http://x".into(), Some("k".into()), 1_000).unwrap().with_auth_mode(mode);
+            let h = client.build_headers().unwrap();
+            (h.get(AUTHORIZATION).map(|v| v.to_str().unwrap().to_string()), h.get("x-api-key").is_some())
+        };
+        assert_eq!(headers(AuthMode::Both), (Some("Bearer k".into()), true));
+        assert_eq!(headers(AuthMode::Bearer), (Some("Bearer k".into()), false));
+        assert_eq!(headers(AuthMode::ApiKey), (None, true));
+
+        let keyless = OpenMemoryClient::new("http://x".into(), None, 1_000).unwrap().build_headers().unwrap();
+        assert_eq!(keyless.len(), 1); // content-type only
+    }
+
+    #[test]
+    fn hit_refs_carry_tags_timestamp_and_content_free_metadata() {
+        let raw = json!({ "matches": [{
+            "id": "mem_1",
+            "content": "secret text",
+            "score": 0.9,
+            "tags": ["role:planner", 7],
+            "created_at": "1700000000.5",
+            "metadata": { "episode_id": "e1", "content": "secret text" }
+        }, {
+            "memory_id": "mem_2",
+            "text": "other"
+        }]});
+        let hits = extract_hit_refs(&raw);
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[0].tags, vec!["role:planner"]);
+        assert_eq!(hits[0].created_ts, Some(1_700_000_000.5));
+        assert_eq!(hits[0].metadata, Some(json!({ "episode_id": "e1" })));
+        assert_eq!(hits[0].content_hash, pie_common::sha256_bytes(b"secret text"));
+        assert!(hits[1].tags.is_empty() && hits[1].created_ts.is_none() && hits[1].metadata.is_none());
+    }
+}
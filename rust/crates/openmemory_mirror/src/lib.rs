@@ -1,8 +1,10 @@
 pub mod payload;
 pub mod client;
+pub mod state;
 
-pub use client::{OpenMemoryClient, OpenMemoryError};
+pub use client::{AuthMode, OpenMemoryClient, OpenMemoryError, OpenMemoryPaths, RetryPolicy};
+pub use state::{MirrorRecord, MirrorState};
 pub use payload::{
-    AddMemoryRequest, AddMemoryResponse,
-    QueryMemoryRequest, QueryMemoryParsed, QueryHitRef,
+    episode_idempotency_key, mirror_content, AddMemoryRequest, AddMemoryResponse,
+    HealthStatus, QueryMemoryRequest, QueryMemoryParsed, QueryHitRef,
 };
\ No newline at end of file
@@ -1,5 +1,6 @@
+use pie_episodes::Episode;
 use serde::{Deserialize, Serialize};
-use serde_json::Value as JsonValue;
+use serde_json::{json, Value as JsonValue};
 
 // OpenMemory Backend API:
 // POST /memory/add
@@ -14,6 +15,50 @@ pub struct AddMemoryRequest {
     pub metadata: Option<JsonValue>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub user_id: Option<String>,
+    /// Sent as the `Idempotency-Key` header (not in the body) so the backend can drop
+    /// retried or re-run adds of the same memory.
+    #[serde(skip)]
+    pub idempotency_key: Option<String>,
+}
+
+/// Deterministic idempotency key for mirroring the episode with this hash.
+pub fn episode_idempotency_key(episode_hash: &str) -> String {
+    format!("pieBot-episode-{}", episode_hash.trim_start_matches("sha256:"))
+}
+
+/// Mirrored content for an episode: title + summary (keeps it readable in OpenMemory dashboards).
+pub fn mirror_content(ep: &Episode) -> String {
+    let mut content = String::new();
+    if !ep.title.trim().is_empty() {
+        content.push_str(ep.title.trim());
+        content.push_str("\n\n");
+    }
+    content.push_str(ep.summary.trim());
+    content
+}
+
+impl AddMemoryRequest {
+    /// The mirror payload for a local episode. `user_id` defaults to the episode's thread_id.
+    pub fn from_episode(ep: &Episode, user_id: Option<String>) -> Self {
+        // Metadata: keep it tight and explicit.
+        let metadata = json!({
+            "source": "pieBot",
+            "episode_id": ep.episode_id,
+            "episode_hash": ep.hash,
+            "run_id": ep.run_id,
+            "tick_id": ep.tick_id,
+            "thread_id": ep.thread_id,
+            "tags": ep.tags,
+            "created_ts": ep.created_ts,
+        });
+        Self {
+            content: mirror_content(ep),
+            tags: ep.tags.clone(),
+            metadata: Some(metadata),
+            user_id: user_id.or_else(|| Some(ep.thread_id.clone())),
+            idempotency_key: Some(episode_idempotency_key(&ep.hash)),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -42,16 +87,56 @@ pub struct QueryMemoryRequest {
     pub user_id: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub min_score: Option<f64>,
+    /// Skip this many ranked results (paging past the top-k). Omitted when None.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offset: Option<u32>,
 }
 
 /// OpenMemory query responses vary across deployments. We keep:
 /// - raw json for artifact storage
-/// - a ref-only view for safe output (ids + scores + content hash)
+/// - a ref-only view for safe output (ids + scores + content hash + descriptive fields)
 #[derive(Debug, Clone)]
 pub struct QueryHitRef {
     pub id: String,
     pub score: Option<f64>,
     pub content_hash: String, // sha256:... of content/text bytes
+    pub tags: Vec<String>,
+    /// created_ts | created_at, when numeric (or a numeric string).
+    pub created_ts: Option<f64>,
+    /// Hit metadata with any `content`/`text` keys removed.
+    pub metadata: Option<JsonValue>,
+}
+
+/// Result of `OpenMemoryClient::health`.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct HealthStatus {
+    pub healthy: bool,
+    /// The backend's own status string, when it reports one.
+    pub status: Option<String>,
+}
+
+impl HealthStatus {
+    /// Interpret a 2xx health body. Accepts `{status: "ok"|"healthy"|"up"|"pass"}`,
+    /// `{ok|healthy: bool}`, a bare status string, or an empty body (2xx alone = healthy).
+    pub fn from_body(body: &str) -> Self {
+        const GOOD: [&str; 5] = ["ok", "healthy", "up", "pass", "green"];
+        let good = |s: &str| GOOD.contains(&s.trim().to_ascii_lowercase().as_str());
+        match serde_json::from_str::<JsonValue>(body) {
+            Ok(JsonValue::Object(o)) => {
+                let status = o.get("status").and_then(|v| v.as_str()).map(str::to_string);
+                let flag = o.get("ok").or_else(|| o.get("healthy")).and_then(|v| v.as_bool());
+                let healthy = match (&status, flag) {
+                    (_, Some(f)) => f,
+                    (Some(s), None) => good(s),
+                    (None, None) => true,
+                };
+                Self { healthy, status }
+            }
+            Ok(JsonValue::String(s)) => Self { healthy: good(&s), status: Some(s) },
+            _ if body.trim().is_empty() => Self { healthy: true, status: None },
+            _ => Self { healthy: good(body), status: Some(body.trim().to_string()) },
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -0,0 +1,112 @@
+//! Local record of what has already been mirrored, so re-running a mirror is idempotent.
+//!
+//! Stored at runtime/memory/episodes/mirror_state.json, keyed by episode hash. Like the
+//! mirror itself this is non-authoritative: deleting it only means episodes get re-sent.
+//!
+//! Each record also keeps the hash of the exact content string sent, which is what a remote
+//! query hit's `content_hash` covers, so hits can be mapped back to local episodes.
+
+use crate::client::OpenMemoryError;
+use crate::payload::mirror_content;
+use pie_common::{canonical_json_bytes, sha256_bytes};
+use pie_episodes::{Episode, EpisodeStore};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MirrorRecord {
+    pub target: String,
+    pub remote_id: String,
+    pub ts: f64,
+    /// Absent in records written before hit correlation existed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub episode_id: Option<Uuid>,
+    /// sha256 of the mirrored content (`mirror_content`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_hash: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct MirrorState {
+    pub schema_version: u8,
+    /// episode_hash -> where it was mirrored.
+    pub episodes: BTreeMap<String, MirrorRecord>,
+}
+
+impl MirrorState {
+    pub fn path_for(store: &EpisodeStore) -> PathBuf {
+        store.base_dir().join("mirror_state.json")
+    }
+
+    /// Missing file = nothing mirrored yet.
+    pub fn load(path: &Path) -> Result<Self, OpenMemoryError> {
+        if !path.exists() {
+            return Ok(Self { schema_version: 1, episodes: BTreeMap::new() });
+        }
+        Ok(serde_json::from_slice(&fs::read(path)?)?)
+    }
+
+    /// Canonical JSON written to a temp file and renamed into place.
+    pub fn save(&self, path: &Path) -> Result<(), OpenMemoryError> {
+        let bytes = canonical_json_bytes(self)?;
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, bytes)?;
+        fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    /// The existing record if `episode_hash` was already mirrored to `target`.
+    pub fn mirrored_to(&self, episode_hash: &str, target: &str) -> Option<&MirrorRecord> {
+        self.episodes.get(episode_hash).filter(|r| r.target == target)
+    }
+
+    pub fn record(&mut self, ep: &Episode, target: &str, remote_id: &str, ts: f64) {
+        self.episodes.insert(
+            ep.hash.clone(),
+            MirrorRecord {
+                target: target.to_string(),
+                remote_id: remote_id.to_string(),
+                ts,
+                episode_id: Some(ep.episode_id),
+                content_hash: Some(sha256_bytes(mirror_content(ep).as_bytes())),
+            },
+        );
+    }
+
+    /// Drop the record for `episode_hash` (after the remote copy was removed).
+    pub fn forget(&mut self, episode_hash: &str) -> Option<MirrorRecord> {
+        self.episodes.remove(episode_hash)
+    }
+
+    /// Local episode whose mirrored content hashes to `content_hash` (a query hit's hash).
+    pub fn episode_for_content(&self, content_hash: &str) -> Option<Uuid> {
+        self.episodes
+            .values()
+            .find(|r| r.content_hash.as_deref() == Some(content_hash))
+            .and_then(|r| r.episode_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pie_episodes::{RunId, TickId};
+
+    #[test]
+    fn hit_content_hash_resolves_to_mirrored_episode() {
+        let a = Episode::new(RunId("r".into()), TickId(1), "main", vec![], "Title", "first", vec![], 0.0).unwrap();
+        let b = Episode::new(RunId("r".into()), TickId(2), "main", vec![], "", "second", vec![], 0.0).unwrap();
+        let mut state = MirrorState::default();
+        state.record(&a, "openmemory", "mem_a", 0.0);
+        state.record(&b, "openmemory", "mem_b", 0.0);
+
+        // The backend echoes the content we sent; hits hash it the same way.
+        let hit_hash = sha256_bytes("Title\n\nfirst".as_bytes());
+        assert_eq!(state.episode_for_content(&hit_hash), Some(a.episode_id));
+        assert_eq!(state.episode_for_content(&sha256_bytes(b"second")), Some(b.episode_id));
+        assert_eq!(state.episode_for_content(&sha256_bytes(b"unknown")), None);
+    }
+}
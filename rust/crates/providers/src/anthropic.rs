@@ -0,0 +1,258 @@
+//! Anthropic Messages API (`/v1/messages`) transport + normalization.
+
+use crate::{
+    apply_header_request_id, canonical_body, canonical_finish_reason, header_request_id, http_error, read_json,
+    refusal_signal, reject_unsupported_options, split_system, to_text_chat_msgs, with_canonical_json, ChatMsg,
+    Provider, ProviderError, ProviderOptions, ProviderReply, ProviderResponse, Usage,
+};
+use async_trait::async_trait;
+use pie_redaction::SanitizedModelRequest;
+use reqwest::Client;
+use serde::Serialize;
+use serde_json::Value;
+
+pub const ANTHROPIC_VERSION: &str = "2023-06-01";
+
+pub struct AnthropicProvider {
+    client: Client,
+    base_url: String,
+    api_key: Option<String>,
+}
+
+impl AnthropicProvider {
+    pub fn new(base_url: String, api_key: Option<String>) -> Self {
+        Self { client: Client::new(), base_url, api_key }
+    }
+
+    pub fn with_options(base_url: String, api_key: Option<String>, opts: &ProviderOptions) -> Result<Self, ProviderError> {
+        Ok(Self { client: opts.build_client()?, base_url, api_key })
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct AnthropicRequest<'a> {
+    model: &'a str,
+    /// Anthropic takes the system prompt here, not as a `system` role message.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    system: Option<String>,
+    messages: Vec<ChatMsg>,
+    max_tokens: u64,
+    temperature: f64,
+    top_p: f64,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    stop_sequences: Vec<String>,
+}
+
+fn build_request(req: &SanitizedModelRequest) -> Result<AnthropicRequest<'_>, ProviderError> {
+    reject_unsupported_options(&req.prompt, "anthropic")?;
+    let (system, messages) = split_system(to_text_chat_msgs(&req.prompt.messages, "anthropic")?);
+    Ok(AnthropicRequest {
+        model: &req.model.0,
+        system,
+        messages,
+        max_tokens: req.prompt.max_output_tokens,
+        temperature: req.prompt.temperature,
+        top_p: req.prompt.top_p,
+        stop_sequences: req.prompt.stop.clone(),
+    })
+}
+
+/// Normalize minimal shape: content[].text (text blocks), stop_reason, usage
+pub(crate) fn normalize(raw: Value) -> Result<ProviderResponse, ProviderError> {
+    let blocks = raw
+        .get("content")
+        .and_then(|c| c.as_array())
+        .ok_or_else(|| ProviderError::InvalidResponse("missing content[]".into()))?;
+    let content = blocks
+        .iter()
+        .filter(|b| b.get("type").and_then(|t| t.as_str()) == Some("text"))
+        .filter_map(|b| b.get("text").and_then(|t| t.as_str()))
+        .collect::<Vec<_>>()
+        .join("");
+
+    let finish_reason = raw.get("stop_reason").and_then(|v| v.as_str()).map(canonical_finish_reason);
+
+    let input_tokens = raw.get("usage").and_then(|u| u.get("input_tokens")).and_then(|v| v.as_u64());
+    let output_tokens = raw.get("usage").and_then(|u| u.get("output_tokens")).and_then(|v| v.as_u64());
+
+    let provider_request_id = raw.get("id").and_then(|v| v.as_str()).map(|s| s.to_string());
+
+    Ok(ProviderResponse {
+        raw_json: raw.clone(),
+        normalized: ProviderReply {
+            content,
+            refusal: refusal_signal(None, finish_reason.as_deref()),
+            finish_reason,
+            usage: Usage { input_tokens, output_tokens, reasoning_tokens: None },
+            provider_request_id,
+            ..Default::default()
+        },
+    })
+}
+
+#[async_trait]
+impl Provider for AnthropicProvider {
+    fn name(&self) -> &'static str {
+        "anthropic"
+    }
+
+    fn request_body(&self, req: &SanitizedModelRequest) -> Result<Option<Vec<u8>>, ProviderError> {
+        canonical_body(&build_request(req)?).map(Some)
+    }
+
+    async fn dispatch(&self, req: &SanitizedModelRequest) -> Result<ProviderResponse, ProviderError> {
+        let url = format!("{}/v1/messages", self.base_url.trim_end_matches('/'));
+        let body = build_request(req)?;
+
+        let mut r = with_canonical_json(self.client.post(url).header("anthropic-version", ANTHROPIC_VERSION), &body)?;
+        if let Some(k) = &self.api_key {
+            if !k.is_empty() {
+                r = r.header("x-api-key", k);
+            }
+        }
+        let resp = r.send().await.map_err(http_error)?;
+        let header_id = header_request_id(resp.headers());
+        let raw = read_json(resp).await?;
+
+        Ok(apply_header_request_id(normalize(raw)?, header_id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{msg, sanitized};
+    use serde_json::json;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn dispatch_normalizes_messages_response() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/messages"))
+            .and(header("x-api-key", "sk-ant"))
+            .and(header("anthropic-version", ANTHROPIC_VERSION))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "id": "msg_01",
+                "type": "message",
+                "role": "assistant",
+                "content": [{ "type": "text", "text": "hello " }, { "type": "text", "text": "world" }],
+                "stop_reason": "end_turn",
+                "usage": { "input_tokens": 12, "output_tokens": 4 }
+            })))
+            .mount(&server)
+            .await;
+
+        let p = AnthropicProvider::new(server.uri(), Some("sk-ant".into()));
+        let out = p.dispatch(&sanitized("anthropic", "claude", vec![msg("user", "hi")])).await.unwrap();
+        assert_eq!(out.normalized.content, "hello world");
+        assert_eq!(out.normalized.finish_reason.as_deref(), Some("stop"));
+        assert_eq!(out.raw_json["stop_reason"], "end_turn");
+        assert_eq!(out.normalized.usage.input_tokens, Some(12));
+        assert_eq!(out.normalized.provider_request_id.as_deref(), Some("msg_01"));
+
+        // Sent as canonical JSON, like every provider body.
+        let sent = server.received_requests().await.unwrap().remove(0).body;
+        let parsed: Value = serde_json::from_slice(&sent).unwrap();
+        assert_eq!(sent, pie_common::canonical_json_bytes(&parsed).unwrap());
+    }
+
+    #[tokio::test]
+    async fn error_bodies_and_header_request_ids_are_surfaced() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/messages"))
+            .respond_with(ResponseTemplate::new(400).set_body_json(json!({
+                "type": "error",
+                "error": { "type": "invalid_request_error", "message": "max_tokens: too large" }
+            })))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/v1/messages"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("request-id", "req_hdr")
+                    .set_body_json(json!({ "id": "msg_02", "content": [], "stop_reason": "end_turn" })),
+            )
+            .mount(&server)
+            .await;
+
+        let p = AnthropicProvider::new(server.uri(), None);
+        let req = sanitized("anthropic", "claude", vec![msg("user", "hi")]);
+        let err = p.dispatch(&req).await.unwrap_err();
+        assert!(
+            matches!(&err, ProviderError::Provider { status: 400, kind: Some(k), message, .. }
+                if k == "invalid_request_error" && message == "max_tokens: too large"),
+            "{err}"
+        );
+
+        let out = p.dispatch(&req).await.unwrap();
+        assert_eq!(out.normalized.provider_request_id.as_deref(), Some("req_hdr"));
+        // No key configured: the header is omitted rather than sent empty.
+        let sent = server.received_requests().await.unwrap();
+        assert!(sent.iter().all(|r| !r.headers.contains_key("x-api-key")));
+    }
+
+    #[test]
+    fn request_body_carries_sampling_and_stop_sequences() {
+        let mut req = sanitized("anthropic", "claude", vec![msg("user", "hi")]);
+        req.prompt.stop = vec!["END".into()];
+        let body = serde_json::to_value(build_request(&req).unwrap()).unwrap();
+        assert_eq!(
+            body,
+            json!({
+                "model": "claude",
+                "messages": [{ "role": "user", "content": "hi" }],
+                "max_tokens": 64,
+                "temperature": 0.2,
+                "top_p": 1.0,
+                "stop_sequences": ["END"]
+            })
+        );
+    }
+
+    #[test]
+    fn reply_without_content_is_an_invalid_response() {
+        let err = normalize(json!({ "stop_reason": "end_turn" })).unwrap_err();
+        assert!(matches!(err, ProviderError::InvalidResponse(_)), "{err}");
+    }
+
+    #[test]
+    fn system_messages_move_to_the_top_level_field() {
+        let req = sanitized("anthropic", "claude", vec![msg("system", "a"), msg("system", "b"), msg("user", "hi")]);
+        let body = serde_json::to_value(build_request(&req).unwrap()).unwrap();
+        assert_eq!(body["system"], "a\n\nb");
+        assert_eq!(body["messages"], json!([{ "role": "user", "content": "hi" }]));
+
+        let req = sanitized("anthropic", "claude", vec![msg("user", "hi")]);
+        let body = serde_json::to_value(build_request(&req).unwrap()).unwrap();
+        assert!(body.get("system").is_none());
+    }
+
+    #[test]
+    fn max_tokens_stop_reason_marks_truncation() {
+        let out = normalize(json!({
+            "content": [{ "type": "text", "text": "partial" }],
+            "stop_reason": "max_tokens"
+        }))
+        .unwrap();
+        assert!(out.normalized.was_truncated());
+    }
+
+    #[test]
+    fn stop_reasons_map_to_the_canonical_vocabulary() {
+        for (native, canonical) in
+            [("end_turn", "stop"), ("stop_sequence", "stop"), ("max_tokens", "length"), ("tool_use", "tool_calls")]
+        {
+            let out = normalize(json!({ "content": [], "stop_reason": native })).unwrap();
+            assert_eq!(out.normalized.finish_reason.as_deref(), Some(canonical), "{native}");
+            assert!(out.normalized.refusal.is_none());
+        }
+        let out = normalize(json!({ "content": [], "stop_reason": "refusal" })).unwrap();
+        assert_eq!(out.normalized.finish_reason.as_deref(), Some("content_filter"));
+        assert_eq!(out.normalized.refusal.as_deref(), Some("content_filter"));
+    }
+}
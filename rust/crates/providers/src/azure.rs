@@ -0,0 +1,111 @@
+//! Azure OpenAI transport. Same wire format as OpenAI-compat, but the deployment lives in the URL,
+//! `api-version` is a required query parameter, and auth is an `api-key` header (not bearer).
+
+use crate::{
+    apply_header_request_id, build_openai_request, canonical_body, header_request_id, http_error, normalize_openai,
+    read_json, with_canonical_json, Provider, ProviderError, ProviderOptions, ProviderResponse,
+};
+use async_trait::async_trait;
+use pie_redaction::SanitizedModelRequest;
+use reqwest::Client;
+
+pub struct AzureOpenAIProvider {
+    client: Client,
+    endpoint: String,
+    deployment: String,
+    api_version: String,
+    api_key: Option<String>,
+}
+
+impl AzureOpenAIProvider {
+    /// `endpoint` is the resource root, e.g. https://myres.openai.azure.com
+    pub fn new(endpoint: String, deployment: String, api_version: String, api_key: Option<String>) -> Self {
+        Self { client: Client::new(), endpoint, deployment, api_version, api_key }
+    }
+
+    pub fn with_options(
+        endpoint: String,
+        deployment: String,
+        api_version: String,
+        api_key: Option<String>,
+        opts: &ProviderOptions,
+    ) -> Result<Self, ProviderError> {
+        Ok(Self { client: opts.build_client()?, endpoint, deployment, api_version, api_key })
+    }
+
+    fn url(&self) -> String {
+        format!(
+            "{}/openai/deployments/{}/chat/completions",
+            self.endpoint.trim_end_matches('/'),
+            self.deployment
+        )
+    }
+}
+
+#[async_trait]
+impl Provider for AzureOpenAIProvider {
+    fn name(&self) -> &'static str {
+        "azure"
+    }
+
+    fn request_body(&self, req: &SanitizedModelRequest) -> Result<Option<Vec<u8>>, ProviderError> {
+        canonical_body(&build_openai_request(req)).map(Some)
+    }
+
+    async fn dispatch(&self, req: &SanitizedModelRequest) -> Result<ProviderResponse, ProviderError> {
+        let body = build_openai_request(req);
+
+        let r = self.client.post(self.url()).query(&[("api-version", self.api_version.as_str())]);
+        let mut r = with_canonical_json(r, &body)?;
+        if let Some(k) = &self.api_key {
+            if !k.is_empty() {
+                r = r.header("api-key", k);
+            }
+        }
+        let resp = r.send().await.map_err(http_error)?;
+        let header_id = header_request_id(resp.headers());
+        let raw = read_json(resp).await?;
+
+        Ok(apply_header_request_id(normalize_openai(raw)?, header_id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{msg, sanitized};
+    use serde_json::json;
+    use wiremock::matchers::{header, header_exists, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn dispatch_targets_deployment_url_with_api_key_header() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/openai/deployments/gpt4o-prod/chat/completions"))
+            .and(query_param("api-version", "2024-06-01"))
+            .and(header("api-key", "az-key"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "id": "chatcmpl-az",
+                "choices": [{ "index": 0, "message": { "role": "assistant", "content": "from azure" }, "finish_reason": "stop" }]
+            })))
+            .mount(&server)
+            .await;
+        // A bearer header would mean we fell back to the OpenAI auth scheme.
+        Mock::given(header_exists("authorization"))
+            .respond_with(ResponseTemplate::new(400))
+            .with_priority(1)
+            .mount(&server)
+            .await;
+
+        let p = AzureOpenAIProvider::new(
+            format!("{}/", server.uri()),
+            "gpt4o-prod".into(),
+            "2024-06-01".into(),
+            Some("az-key".into()),
+        );
+        let out = p.dispatch(&sanitized("azure", "gpt-4o", vec![msg("user", "hi")])).await.unwrap();
+        assert_eq!(out.normalized.content, "from azure");
+        assert_eq!(out.normalized.provider_request_id.as_deref(), Some("chatcmpl-az"));
+    }
+}
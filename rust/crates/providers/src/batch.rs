@@ -0,0 +1,49 @@
+//! Bounded-concurrency batch dispatch over any `Provider`.
+
+use crate::{Provider, ProviderError, ProviderResponse};
+use futures::stream::{self, StreamExt};
+use pie_redaction::SanitizedModelRequest;
+
+/// Dispatch every request with at most `concurrency` (min 1) in flight. Results are in input
+/// order regardless of completion order; one failure does not stop the rest.
+pub async fn dispatch_many(
+    provider: &dyn Provider,
+    reqs: &[SanitizedModelRequest],
+    concurrency: usize,
+) -> Vec<Result<ProviderResponse, ProviderError>> {
+    stream::iter(reqs).map(|r| provider.dispatch(r)).buffered(concurrency.max(1)).collect().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{msg, sanitized};
+    use crate::{MockProvider, ProviderReply};
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn results_keep_input_order_under_the_concurrency_bound() {
+        // The model id doubles as the reply, so output order is checkable.
+        let mock = MockProvider::from_fn(|r| {
+            let content = r.model.0.clone();
+            if content == "fail" {
+                return Err(ProviderError::InvalidResponse("boom".into()));
+            }
+            Ok(ProviderResponse {
+                raw_json: serde_json::json!({}),
+                normalized: ProviderReply { content, ..Default::default() },
+            })
+        })
+        .with_latency(Duration::from_millis(20));
+        let reqs: Vec<_> = ["a", "b", "fail", "d", "e", "f", "g"]
+            .iter()
+            .map(|m| sanitized("openai", m, vec![msg("user", "hi")]))
+            .collect();
+
+        let out = dispatch_many(&mock, &reqs, 3).await;
+        let contents: Vec<_> = out.iter().map(|r| r.as_ref().map(|r| r.normalized.content.as_str()).ok()).collect();
+        assert_eq!(contents, [Some("a"), Some("b"), None, Some("d"), Some("e"), Some("f"), Some("g")]);
+        assert_eq!(mock.calls(), 7);
+        assert_eq!(mock.max_in_flight(), 3);
+    }
+}
@@ -0,0 +1,543 @@
+//! AWS Bedrock runtime (`/model/{id}/invoke`) transport with SigV4 request signing.
+//! Only Anthropic models (`anthropic.*`, incl. cross-region `us.anthropic.*`) are mapped; their
+//! invoke body and reply are the Anthropic Messages shape.
+//!
+//! Credentials come from the `AWS_*` environment variables, else the ECS/EKS container
+//! endpoint, else EC2 instance metadata (IMDSv2); see `CredentialSource::from_env`.
+
+use crate::{
+    anthropic, apply_header_request_id, canonical_body, check_status, header_request_id, http_error, read_json,
+    reject_unsupported_options, split_system, to_text_chat_msgs, ChatMsg, Provider, ProviderError, ProviderOptions,
+    ProviderResponse,
+};
+use async_trait::async_trait;
+use hmac::{Hmac, Mac};
+use pie_redaction::SanitizedModelRequest;
+use reqwest::Client;
+use serde::Serialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// `anthropic_version` Bedrock expects in Anthropic invoke bodies.
+pub const BEDROCK_ANTHROPIC_VERSION: &str = "bedrock-2023-05-31";
+
+const SERVICE: &str = "bedrock";
+
+/// Default EC2 instance metadata endpoint.
+pub const IMDS_ENDPOINT: &str = "http://169.254.169.254";
+/// Host the container credentials `RELATIVE_URI` is resolved against.
+const CONTAINER_CREDENTIALS_HOST: &str = "http://169.254.170.2";
+/// Fetched credentials are refreshed this long before they expire.
+const REFRESH_MARGIN_SECS: u64 = 300;
+
+/// Static AWS credentials. Debug never prints the secret or session token.
+#[derive(Clone)]
+pub struct AwsCredentials {
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    pub session_token: Option<String>,
+}
+
+impl std::fmt::Debug for AwsCredentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "AwsCredentials({}, <secret>)", self.access_key_id)
+    }
+}
+
+impl AwsCredentials {
+    /// `AWS_ACCESS_KEY_ID` / `AWS_SECRET_ACCESS_KEY` / `AWS_SESSION_TOKEN`.
+    pub fn from_env() -> Option<Self> {
+        Some(Self {
+            access_key_id: env_var("AWS_ACCESS_KEY_ID")?,
+            secret_access_key: env_var("AWS_SECRET_ACCESS_KEY")?,
+            session_token: env_var("AWS_SESSION_TOKEN"),
+        })
+    }
+
+    /// The `{AccessKeyId, SecretAccessKey, Token, Expiration}` document served by both the
+    /// container endpoint and instance metadata; returns the expiry as Unix seconds when parseable.
+    fn from_role_document(doc: &Value) -> Result<(Self, Option<u64>), ProviderError> {
+        let field = |k: &str| doc.get(k).and_then(|v| v.as_str()).map(str::to_string);
+        let creds = Self {
+            access_key_id: field("AccessKeyId")
+                .ok_or_else(|| ProviderError::InvalidResponse("bedrock credentials: missing AccessKeyId".into()))?,
+            secret_access_key: field("SecretAccessKey")
+                .ok_or_else(|| ProviderError::InvalidResponse("bedrock credentials: missing SecretAccessKey".into()))?,
+            session_token: field("Token"),
+        };
+        Ok((creds, field("Expiration").as_deref().and_then(parse_expiration)))
+    }
+}
+
+fn env_var(k: &str) -> Option<String> {
+    std::env::var(k).ok().filter(|v| !v.is_empty())
+}
+
+/// Where a `BedrockProvider` gets its credentials.
+#[derive(Debug, Clone)]
+pub enum CredentialSource {
+    Static(AwsCredentials),
+    /// ECS/EKS container credentials endpoint, with its optional `Authorization` token.
+    Container { url: String, authorization: Option<String> },
+    /// EC2 instance metadata (IMDSv2) at `endpoint`, e.g. `IMDS_ENDPOINT`.
+    InstanceMetadata { endpoint: String },
+}
+
+impl CredentialSource {
+    /// The default chain: static `AWS_*` keys, then the container endpoint
+    /// (`AWS_CONTAINER_CREDENTIALS_FULL_URI`, or `_RELATIVE_URI` plus `AWS_CONTAINER_AUTHORIZATION_TOKEN`),
+    /// then instance metadata unless `AWS_EC2_METADATA_DISABLED=true`.
+    pub fn from_env() -> Option<Self> {
+        if let Some(creds) = AwsCredentials::from_env() {
+            return Some(Self::Static(creds));
+        }
+        let container = env_var("AWS_CONTAINER_CREDENTIALS_FULL_URI").or_else(|| {
+            env_var("AWS_CONTAINER_CREDENTIALS_RELATIVE_URI").map(|p| format!("{CONTAINER_CREDENTIALS_HOST}{p}"))
+        });
+        if let Some(url) = container {
+            return Some(Self::Container { url, authorization: env_var("AWS_CONTAINER_AUTHORIZATION_TOKEN") });
+        }
+        if env_var("AWS_EC2_METADATA_DISABLED").is_some_and(|v| v.eq_ignore_ascii_case("true")) {
+            return None;
+        }
+        let endpoint = env_var("AWS_EC2_METADATA_SERVICE_ENDPOINT").unwrap_or_else(|| IMDS_ENDPOINT.to_string());
+        Some(Self::InstanceMetadata { endpoint })
+    }
+}
+
+/// Unix seconds for an RFC 3339 UTC timestamp such as `2024-06-01T12:00:00Z`.
+fn parse_expiration(s: &str) -> Option<u64> {
+    let num = |r: std::ops::Range<usize>| s.get(r)?.parse::<i64>().ok();
+    let (y, m, d) = (num(0..4)?, num(5..7)?, num(8..10)?);
+    let (hh, mm, ss) = (num(11..13)?, num(14..16)?, num(17..19)?);
+    // Days since 1970-01-01 (inverse of the civil-date step in `amz_date`).
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * (if m > 2 { m - 3 } else { m + 9 }) + 2) / 5 + d - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146_097 + doe - 719_468;
+    u64::try_from(days * 86_400 + hh * 3600 + mm * 60 + ss).ok()
+}
+
+pub struct BedrockProvider {
+    client: Client,
+    /// Runtime endpoint root, e.g. https://bedrock-runtime.us-east-1.amazonaws.com
+    endpoint: String,
+    region: String,
+    credentials: CredentialSource,
+    /// Fetched (non-static) credentials and their expiry in Unix seconds, if known.
+    cached: Mutex<Option<(AwsCredentials, Option<u64>)>>,
+}
+
+impl BedrockProvider {
+    pub fn new(endpoint: String, region: String, credentials: AwsCredentials) -> Self {
+        Self::from_parts(Client::new(), endpoint, region, CredentialSource::Static(credentials))
+    }
+
+    pub fn with_options(
+        endpoint: String,
+        region: String,
+        credentials: AwsCredentials,
+        opts: &ProviderOptions,
+    ) -> Result<Self, ProviderError> {
+        Self::with_credential_source(endpoint, region, CredentialSource::Static(credentials), opts)
+    }
+
+    /// Credentials fetched from a container endpoint or instance metadata on first use and
+    /// refreshed shortly before they expire.
+    pub fn with_credential_source(
+        endpoint: String,
+        region: String,
+        source: CredentialSource,
+        opts: &ProviderOptions,
+    ) -> Result<Self, ProviderError> {
+        Ok(Self::from_parts(opts.build_client()?, endpoint, region, source))
+    }
+
+    fn from_parts(client: Client, endpoint: String, region: String, credentials: CredentialSource) -> Self {
+        Self { client, endpoint, region, credentials, cached: Mutex::new(None) }
+    }
+
+    /// Region from `AWS_REGION` (or `AWS_DEFAULT_REGION`) and credentials from `CredentialSource::from_env`.
+    pub fn from_env(endpoint: String, opts: &ProviderOptions) -> Result<Self, ProviderError> {
+        let region = std::env::var("AWS_REGION")
+            .or_else(|_| std::env::var("AWS_DEFAULT_REGION"))
+            .map_err(|_| ProviderError::InvalidRequest("bedrock: AWS_REGION is not set".into()))?;
+        let source = CredentialSource::from_env()
+            .ok_or_else(|| ProviderError::InvalidRequest("bedrock: AWS credentials are not set".into()))?;
+        Self::with_credential_source(endpoint, region, source, opts)
+    }
+
+    async fn credentials(&self) -> Result<AwsCredentials, ProviderError> {
+        if let CredentialSource::Static(creds) = &self.credentials {
+            return Ok(creds.clone());
+        }
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default();
+        if let Some((creds, Some(expires))) = self.cached.lock().expect("credential cache lock").as_ref() {
+            if now + REFRESH_MARGIN_SECS < *expires {
+                return Ok(creds.clone());
+            }
+        }
+        let (creds, expires) = self.fetch_credentials().await?;
+        *self.cached.lock().expect("credential cache lock") = Some((creds.clone(), expires));
+        Ok(creds)
+    }
+
+    async fn fetch_credentials(&self) -> Result<(AwsCredentials, Option<u64>), ProviderError> {
+        // Link-local metadata services: no proxy, short timeout.
+        let client = Client::builder().no_proxy().timeout(Duration::from_secs(2)).build().map_err(http_error)?;
+        let doc = match &self.credentials {
+            CredentialSource::Static(creds) => return Ok((creds.clone(), None)),
+            CredentialSource::Container { url, authorization } => {
+                let mut r = client.get(url);
+                if let Some(token) = authorization {
+                    r = r.header("authorization", token);
+                }
+                read_json(r.send().await.map_err(http_error)?).await?
+            }
+            CredentialSource::InstanceMetadata { endpoint } => {
+                let base = endpoint.trim_end_matches('/');
+                let text = |r: reqwest::RequestBuilder| async move {
+                    let resp = check_status(r.send().await.map_err(http_error)?).await?;
+                    resp.text().await.map_err(http_error)
+                };
+                let token = text(
+                    client
+                        .put(format!("{base}/latest/api/token"))
+                        .header("x-aws-ec2-metadata-token-ttl-seconds", "21600"),
+                )
+                .await?;
+                let roles_url = format!("{base}/latest/meta-data/iam/security-credentials/");
+                let roles = text(client.get(&roles_url).header("x-aws-ec2-metadata-token", &token)).await?;
+                let role = roles
+                    .lines()
+                    .next()
+                    .filter(|r| !r.is_empty())
+                    .ok_or_else(|| ProviderError::InvalidResponse("bedrock: no instance role attached".into()))?;
+                let r = client.get(format!("{roles_url}{role}")).header("x-aws-ec2-metadata-token", &token);
+                read_json(r.send().await.map_err(http_error)?).await?
+            }
+        };
+        AwsCredentials::from_role_document(&doc)
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct BedrockAnthropicBody {
+    anthropic_version: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    system: Option<String>,
+    messages: Vec<ChatMsg>,
+    max_tokens: u64,
+    temperature: f64,
+    top_p: f64,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    stop_sequences: Vec<String>,
+}
+
+/// The model id travels in the URL, so the body carries no `model`.
+fn build_body(req: &SanitizedModelRequest) -> Result<BedrockAnthropicBody, ProviderError> {
+    let model = &req.model.0;
+    if !(model.starts_with("anthropic.") || model.contains(".anthropic.")) {
+        return Err(ProviderError::InvalidRequest(format!("bedrock: unsupported model family: {model}")));
+    }
+    reject_unsupported_options(&req.prompt, "bedrock")?;
+    let (system, messages) = split_system(to_text_chat_msgs(&req.prompt.messages, "bedrock")?);
+    Ok(BedrockAnthropicBody {
+        anthropic_version: BEDROCK_ANTHROPIC_VERSION,
+        system,
+        messages,
+        max_tokens: req.prompt.max_output_tokens,
+        temperature: req.prompt.temperature,
+        top_p: req.prompt.top_p,
+        stop_sequences: req.prompt.stop.clone(),
+    })
+}
+
+/// SigV4 URI encoding: everything but unreserved characters is percent-encoded.
+fn uri_encode(s: &str, encode_slash: bool) -> String {
+    let mut out = String::with_capacity(s.len());
+    for b in s.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => out.push(b as char),
+            b'/' if !encode_slash => out.push('/'),
+            _ => out.push_str(&format!("%{b:02X}")),
+        }
+    }
+    out
+}
+
+/// `YYYYMMDD'T'HHMMSS'Z'` for seconds since the Unix epoch (UTC).
+fn amz_date(unix_secs: u64) -> String {
+    let (days, secs) = (unix_secs / 86_400, unix_secs % 86_400);
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm).
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{year:04}{month:02}{day:02}T{:02}{:02}{:02}Z", secs / 3600, secs % 3600 / 60, secs % 60)
+}
+
+fn hmac_sha256(key: &[u8], data: &str) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(data.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Where and when a request is signed.
+struct SigningScope<'a> {
+    region: &'a str,
+    service: &'a str,
+    amz_date: &'a str,
+}
+
+/// SigV4 `Authorization` header value. `headers` are the signed headers (lowercase names, host
+/// and x-amz-date included); the query string is assumed empty.
+fn authorization(
+    creds: &AwsCredentials,
+    at: &SigningScope<'_>,
+    method: &str,
+    canonical_uri: &str,
+    headers: &[(String, String)],
+    payload: &[u8],
+) -> String {
+    let SigningScope { region, service, amz_date } = *at;
+    let mut headers = headers.to_vec();
+    headers.sort();
+    let canonical_headers: String = headers.iter().map(|(k, v)| format!("{k}:{}\n", v.trim())).collect();
+    let signed_headers = headers.iter().map(|(k, _)| k.as_str()).collect::<Vec<_>>().join(";");
+    let canonical_request = format!(
+        "{method}\n{canonical_uri}\n\n{canonical_headers}\n{signed_headers}\n{}",
+        hex::encode(Sha256::digest(payload))
+    );
+
+    let date = &amz_date[..8];
+    let scope = format!("{date}/{region}/{service}/aws4_request");
+    let string_to_sign =
+        format!("AWS4-HMAC-SHA256\n{amz_date}\n{scope}\n{}", hex::encode(Sha256::digest(canonical_request.as_bytes())));
+
+    let mut key = hmac_sha256(format!("AWS4{}", creds.secret_access_key).as_bytes(), date);
+    for part in [region, service, "aws4_request"] {
+        key = hmac_sha256(&key, part);
+    }
+    let signature = hex::encode(hmac_sha256(&key, &string_to_sign));
+    format!(
+        "AWS4-HMAC-SHA256 Credential={}/{scope}, SignedHeaders={signed_headers}, Signature={signature}",
+        creds.access_key_id
+    )
+}
+
+#[async_trait]
+impl Provider for BedrockProvider {
+    fn name(&self) -> &'static str {
+        "bedrock"
+    }
+
+    fn request_body(&self, req: &SanitizedModelRequest) -> Result<Option<Vec<u8>>, ProviderError> {
+        canonical_body(&build_body(req)?).map(Some)
+    }
+
+    async fn dispatch(&self, req: &SanitizedModelRequest) -> Result<ProviderResponse, ProviderError> {
+        let body = canonical_body(&build_body(req)?)?;
+        let path = format!("/model/{}/invoke", uri_encode(&req.model.0, true));
+        let url = reqwest::Url::parse(&format!("{}{path}", self.endpoint.trim_end_matches('/')))
+            .map_err(|e| ProviderError::InvalidRequest(format!("bedrock endpoint: {e}")))?;
+        let host = match (url.host_str(), url.port()) {
+            (Some(h), Some(p)) => format!("{h}:{p}"),
+            (Some(h), None) => h.to_string(),
+            (None, _) => return Err(ProviderError::InvalidRequest("bedrock endpoint has no host".into())),
+        };
+
+        let credentials = self.credentials().await?;
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default();
+        let date = amz_date(now);
+        let mut signed = vec![
+            ("content-type".to_string(), "application/json".to_string()),
+            ("host".to_string(), host),
+            ("x-amz-date".to_string(), date.clone()),
+        ];
+        if let Some(token) = &credentials.session_token {
+            signed.push(("x-amz-security-token".to_string(), token.clone()));
+        }
+        // Non-S3 services sign the already-encoded path encoded once more.
+        let canonical_uri = uri_encode(url.path(), false);
+        let at = SigningScope { region: &self.region, service: SERVICE, amz_date: &date };
+        let auth = authorization(&credentials, &at, "POST", &canonical_uri, &signed, &body);
+
+        let mut r = self.client.post(url).header("authorization", auth).body(body);
+        for (k, v) in signed.iter().filter(|(k, _)| k != "host") {
+            r = r.header(k, v);
+        }
+        let resp = r.send().await.map_err(http_error)?;
+        let header_id = header_request_id(resp.headers());
+        let raw = read_json(resp).await?;
+
+        Ok(apply_header_request_id(anthropic::normalize(raw)?, header_id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{msg, sanitized};
+    use serde_json::json;
+    use wiremock::matchers::{body_partial_json, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn example_creds() -> AwsCredentials {
+        AwsCredentials {
+            access_key_id: "AKIDEXAMPLE".into(),
+            secret_access_key: "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY".into(),
+            session_token: None,
+        }
+    }
+
+    #[test]
+    fn signature_matches_the_aws_get_vanilla_vector() {
+        assert_eq!(amz_date(1_440_938_160), "20150830T123600Z");
+        let headers =
+            [("host".to_string(), "example.amazonaws.com".to_string()), ("x-amz-date".into(), "20150830T123600Z".into())];
+        let at = SigningScope { region: "us-east-1", service: "service", amz_date: "20150830T123600Z" };
+        let auth = authorization(&example_creds(), &at, "GET", "/", &headers, b"");
+        assert_eq!(
+            auth,
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, \
+             SignedHeaders=host;x-amz-date, Signature=5fa00fa31553b73ebf1942676e86291e8372ff2a2260956d9b8aae1d763fbf31"
+        );
+        assert!(!format!("{:?}", example_creds()).contains("EXAMPLEKEY"));
+    }
+
+    #[test]
+    fn anthropic_models_map_to_the_bedrock_messages_body() {
+        let mut req = sanitized("bedrock", "anthropic.claude-3-haiku-20240307-v1:0", vec![msg("user", "hi")]);
+        req.prompt.stop = vec!["END".into()];
+        let body = serde_json::to_value(build_body(&req).unwrap()).unwrap();
+        assert_eq!(body["anthropic_version"], BEDROCK_ANTHROPIC_VERSION);
+        assert_eq!(body["messages"], json!([{ "role": "user", "content": "hi" }]));
+        assert_eq!(body["stop_sequences"], json!(["END"]));
+        assert!(body.get("model").is_none());
+
+        let other = sanitized("bedrock", "meta.llama3-8b-instruct-v1:0", vec![msg("user", "hi")]);
+        assert!(matches!(build_body(&other), Err(ProviderError::InvalidRequest(_))));
+    }
+
+    #[tokio::test]
+    async fn dispatch_signs_the_invoke_call_and_normalizes_the_reply() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/model/anthropic.claude-3-haiku-20240307-v1%3A0/invoke"))
+            .and(header("x-amz-security-token", "tok"))
+            .and(body_partial_json(json!({ "anthropic_version": BEDROCK_ANTHROPIC_VERSION })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "id": "msg_bdrk",
+                "content": [{ "type": "text", "text": "hello" }],
+                "stop_reason": "end_turn",
+                "usage": { "input_tokens": 5, "output_tokens": 1 }
+            })))
+            .mount(&server)
+            .await;
+
+        let creds = AwsCredentials { session_token: Some("tok".into()), ..example_creds() };
+        let p = BedrockProvider::new(server.uri(), "us-east-1".into(), creds);
+        let req = sanitized("bedrock", "anthropic.claude-3-haiku-20240307-v1:0", vec![msg("user", "hi")]);
+        let out = p.dispatch(&req).await.unwrap();
+        assert_eq!(out.normalized.content, "hello");
+        assert_eq!(out.normalized.finish_reason.as_deref(), Some("stop"));
+        assert!(!out.raw_json.to_string().contains("EXAMPLEKEY"));
+
+        let sent = &server.received_requests().await.unwrap()[0];
+        let auth = sent.headers.get("authorization").unwrap().to_str().unwrap();
+        assert!(auth.starts_with("AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/"));
+        assert!(auth.contains("/us-east-1/bedrock/aws4_request"));
+        assert!(auth.contains("SignedHeaders=content-type;host;x-amz-date;x-amz-security-token"));
+    }
+
+    async fn mount_invoke(server: &MockServer, token: &str) {
+        Mock::given(method("POST"))
+            .and(path("/model/anthropic.claude-3-haiku-20240307-v1%3A0/invoke"))
+            .and(header("x-amz-security-token", token))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "content": [{ "type": "text", "text": "hello" }],
+                "stop_reason": "end_turn"
+            })))
+            .mount(server)
+            .await;
+    }
+
+    fn role_document(token: &str) -> Value {
+        json!({
+            "AccessKeyId": "ASIAROLE",
+            "SecretAccessKey": "role-secret",
+            "Token": token,
+            "Expiration": "2999-01-01T00:00:00Z"
+        })
+    }
+
+    #[tokio::test]
+    async fn instance_metadata_credentials_use_an_imdsv2_session_token() {
+        let server = MockServer::start().await;
+        Mock::given(method("PUT"))
+            .and(path("/latest/api/token"))
+            .and(header("x-aws-ec2-metadata-token-ttl-seconds", "21600"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("imds-session"))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/latest/meta-data/iam/security-credentials/"))
+            .and(header("x-aws-ec2-metadata-token", "imds-session"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("bedrock-role\n"))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/latest/meta-data/iam/security-credentials/bedrock-role"))
+            .and(header("x-aws-ec2-metadata-token", "imds-session"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(role_document("role-token")))
+            .mount(&server)
+            .await;
+        mount_invoke(&server, "role-token").await;
+
+        let source = CredentialSource::InstanceMetadata { endpoint: server.uri() };
+        let p = BedrockProvider::with_credential_source(server.uri(), "us-east-1".into(), source, &Default::default())
+            .unwrap();
+        let req = sanitized("bedrock", "anthropic.claude-3-haiku-20240307-v1:0", vec![msg("user", "hi")]);
+        // The second dispatch reuses the cached, unexpired credentials.
+        for _ in 0..2 {
+            assert_eq!(p.dispatch(&req).await.unwrap().normalized.content, "hello");
+        }
+        let sent = server.received_requests().await.unwrap();
+        let invoke = sent.iter().find(|r| r.method.as_str() == "POST").unwrap();
+        let auth = invoke.headers.get("authorization").unwrap().to_str().unwrap();
+        assert!(auth.starts_with("AWS4-HMAC-SHA256 Credential=ASIAROLE/"));
+    }
+
+    #[tokio::test]
+    async fn container_credentials_send_the_authorization_token() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v2/credentials/abc"))
+            .and(header("authorization", "ecs-token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(role_document("task-token")))
+            .mount(&server)
+            .await;
+        mount_invoke(&server, "task-token").await;
+
+        let source = CredentialSource::Container {
+            url: format!("{}/v2/credentials/abc", server.uri()),
+            authorization: Some("ecs-token".into()),
+        };
+        let p = BedrockProvider::with_credential_source(server.uri(), "us-east-1".into(), source, &Default::default())
+            .unwrap();
+        let req = sanitized("bedrock", "anthropic.claude-3-haiku-20240307-v1:0", vec![msg("user", "hi")]);
+        assert_eq!(p.dispatch(&req).await.unwrap().normalized.content, "hello");
+        assert_eq!(parse_expiration("2015-08-30T12:36:00Z"), Some(1_440_938_160));
+    }
+}
@@ -0,0 +1,58 @@
+//! Provider selection keyed on the request's `ProviderId`.
+
+use crate::{
+    AnthropicProvider, BedrockProvider, GeminiProvider, OllamaProvider, OpenAICompatProvider, Provider, ProviderError,
+    ProviderOptions,
+};
+use pie_redaction::ProviderId;
+
+/// Build the transport for `id`. Unknown providers are an error, never a silent fallback.
+pub fn build_provider(
+    id: &ProviderId,
+    base_url: String,
+    api_key: Option<String>,
+) -> Result<Box<dyn Provider>, ProviderError> {
+    build_provider_with_options(id, base_url, api_key, &ProviderOptions::default())
+}
+
+/// `build_provider` with explicit transport options (timeouts etc.).
+/// `bedrock` ignores `api_key` and signs with AWS credentials (environment, container or instance
+/// role) and the region from the environment.
+pub fn build_provider_with_options(
+    id: &ProviderId,
+    base_url: String,
+    api_key: Option<String>,
+    opts: &ProviderOptions,
+) -> Result<Box<dyn Provider>, ProviderError> {
+    match id.0.as_str() {
+        "openai" => Ok(Box::new(OpenAICompatProvider::with_options(base_url, api_key, opts)?)),
+        "anthropic" => Ok(Box::new(AnthropicProvider::with_options(base_url, api_key, opts)?)),
+        "gemini" => Ok(Box::new(GeminiProvider::with_options(base_url, api_key, opts)?)),
+        "ollama" => Ok(Box::new(OllamaProvider::with_options(base_url, opts)?)),
+        "bedrock" => Ok(Box::new(BedrockProvider::from_env(base_url, opts)?)),
+        other => Err(ProviderError::UnknownProvider(other.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn factory_selects_implementation_per_id() {
+        // bedrock reads its region and credentials from the environment; no other test in this
+        // crate does, so setting them here cannot race.
+        std::env::set_var("AWS_REGION", "us-east-1");
+        std::env::set_var("AWS_ACCESS_KEY_ID", "AKIDEXAMPLE");
+        std::env::set_var("AWS_SECRET_ACCESS_KEY", "secret");
+        for id in ["openai", "anthropic", "gemini", "ollama", "bedrock"] {
+            let p = build_provider(&ProviderId(id.into()), "http://localhost".into(), None).unwrap();
+            assert_eq!(p.name(), id);
+        }
+
+        let err = build_provider(&ProviderId("xai".into()), "http://localhost".into(), None)
+            .err()
+            .unwrap();
+        assert!(matches!(err, ProviderError::UnknownProvider(ref s) if s == "xai"));
+    }
+}
@@ -0,0 +1,307 @@
+//! Google Gemini (`:generateContent`) transport + normalization.
+
+use crate::{
+    canonical_body, canonical_finish_reason, http_error, read_json, refusal_signal, reject_unsupported_options,
+    split_system, to_text_chat_msgs, with_canonical_json, ChatMsg, Provider, ProviderError, ProviderOptions,
+    ProviderReply, ProviderResponse, Usage,
+};
+use async_trait::async_trait;
+use pie_redaction::SanitizedModelRequest;
+use reqwest::Client;
+use serde::Serialize;
+use serde_json::Value;
+
+pub struct GeminiProvider {
+    client: Client,
+    base_url: String,
+    api_key: Option<String>,
+}
+
+impl GeminiProvider {
+    /// `base_url` is the API root, e.g. https://generativelanguage.googleapis.com
+    pub fn new(base_url: String, api_key: Option<String>) -> Self {
+        Self { client: Client::new(), base_url, api_key }
+    }
+
+    pub fn with_options(base_url: String, api_key: Option<String>, opts: &ProviderOptions) -> Result<Self, ProviderError> {
+        Ok(Self { client: opts.build_client()?, base_url, api_key })
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct GeminiPart {
+    text: String,
+}
+
+#[derive(Debug, Serialize)]
+struct GeminiContent {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    role: Option<String>,
+    parts: Vec<GeminiPart>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiGenerationConfig {
+    max_output_tokens: u64,
+    temperature: f64,
+    top_p: f64,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    stop_sequences: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiRequest {
+    /// Gemini has no `system` role; the system prompt goes here instead.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    system_instruction: Option<GeminiContent>,
+    contents: Vec<GeminiContent>,
+    generation_config: GeminiGenerationConfig,
+}
+
+/// `messages` must already have the leading system prompt split off. Gemini rejects a `system`
+/// role in `contents`, and moving a later one into `systemInstruction` would reorder it, so a
+/// mid-conversation system message is an `InvalidRequest`.
+fn to_gemini_contents(messages: Vec<ChatMsg>) -> Result<Vec<GeminiContent>, ProviderError> {
+    messages
+        .into_iter()
+        .map(|m| {
+            // Gemini calls the assistant side "model".
+            let role = match m.role.as_str() {
+                "assistant" => "model".to_string(),
+                "system" => {
+                    return Err(ProviderError::InvalidRequest(
+                        "gemini only accepts system messages before the first user or assistant message".into(),
+                    ))
+                }
+                other => other.to_string(),
+            };
+            Ok(GeminiContent { role: Some(role), parts: vec![GeminiPart { text: m.content.text() }] })
+        })
+        .collect()
+}
+
+fn build_request(req: &SanitizedModelRequest) -> Result<GeminiRequest, ProviderError> {
+    reject_unsupported_options(&req.prompt, "gemini")?;
+    let (system, messages) = split_system(to_text_chat_msgs(&req.prompt.messages, "gemini")?);
+    Ok(GeminiRequest {
+        system_instruction: system.map(|text| GeminiContent { role: None, parts: vec![GeminiPart { text }] }),
+        contents: to_gemini_contents(messages)?,
+        generation_config: GeminiGenerationConfig {
+            max_output_tokens: req.prompt.max_output_tokens,
+            temperature: req.prompt.temperature,
+            top_p: req.prompt.top_p,
+            stop_sequences: req.prompt.stop.clone(),
+        },
+    })
+}
+
+/// Normalize minimal shape: candidates[0].content.parts[0].text, finishReason, usageMetadata.
+/// Blocked output arrives without text: a candidate with no `content` (SAFETY, RECITATION, ...)
+/// or no candidates at all plus `promptFeedback.blockReason`. Both normalize to empty content.
+fn normalize(raw: Value) -> Result<ProviderResponse, ProviderError> {
+    let candidate = raw.get("candidates").and_then(|c| c.get(0));
+    let block_reason = raw.get("promptFeedback").and_then(|f| f.get("blockReason")).and_then(|v| v.as_str());
+    if candidate.is_none() && block_reason.is_none() {
+        return Err(ProviderError::InvalidResponse("missing candidates[0] and promptFeedback.blockReason".into()));
+    }
+
+    let content = candidate
+        .and_then(|c0| c0.get("content"))
+        .and_then(|c| c.get("parts"))
+        .and_then(|p| p.get(0))
+        .and_then(|p0| p0.get("text"))
+        .and_then(|v| v.as_str())
+        .unwrap_or_default()
+        .to_string();
+
+    let finish_reason = match candidate.and_then(|c0| c0.get("finishReason")).and_then(|v| v.as_str()) {
+        Some(native) => Some(canonical_finish_reason(native)),
+        // The prompt itself was blocked; no candidate was generated.
+        None => block_reason.map(|_| "content_filter".to_string()),
+    };
+
+    let usage = raw.get("usageMetadata");
+    let input_tokens = usage.and_then(|u| u.get("promptTokenCount")).and_then(|v| v.as_u64());
+    let output_tokens = usage.and_then(|u| u.get("candidatesTokenCount")).and_then(|v| v.as_u64());
+
+    let provider_request_id = raw.get("responseId").and_then(|v| v.as_str()).map(|s| s.to_string());
+
+    Ok(ProviderResponse {
+        raw_json: raw.clone(),
+        normalized: ProviderReply {
+            content,
+            refusal: refusal_signal(None, finish_reason.as_deref()),
+            finish_reason,
+            usage: Usage { input_tokens, output_tokens, reasoning_tokens: None },
+            provider_request_id,
+            ..Default::default()
+        },
+    })
+}
+
+#[async_trait]
+impl Provider for GeminiProvider {
+    fn name(&self) -> &'static str {
+        "gemini"
+    }
+
+    fn request_body(&self, req: &SanitizedModelRequest) -> Result<Option<Vec<u8>>, ProviderError> {
+        canonical_body(&build_request(req)?).map(Some)
+    }
+
+    async fn dispatch(&self, req: &SanitizedModelRequest) -> Result<ProviderResponse, ProviderError> {
+        let url = format!(
+            "{}/v1beta/models/{}:generateContent",
+            self.base_url.trim_end_matches('/'),
+            req.model.0
+        );
+        let body = build_request(req)?;
+
+        let mut r = with_canonical_json(self.client.post(url), &body)?;
+        if let Some(k) = &self.api_key {
+            if !k.is_empty() {
+                r = r.query(&[("key", k)]);
+            }
+        }
+        // The key lives in the query string; strip the URL from errors so it never lands in artifacts.
+        let resp = r.send().await.map_err(|e| http_error(e.without_url()))?;
+        let raw = read_json(resp).await.map_err(|e| match e {
+            ProviderError::Http(e) => ProviderError::Http(e.without_url()),
+            ProviderError::Connect(e) => ProviderError::Connect(e.without_url()),
+            other => other,
+        })?;
+
+        normalize(raw)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{msg, sanitized};
+    use serde_json::json;
+    use wiremock::matchers::{method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn gemini_req() -> SanitizedModelRequest {
+        let mut req = sanitized("gemini", "gemini-1.5-flash", vec![msg("user", "hello"), msg("assistant", "hi")]);
+        req.prompt.stop = vec!["END".into()];
+        req
+    }
+
+    #[test]
+    fn request_maps_roles_and_generation_config() {
+        let body = serde_json::to_value(build_request(&gemini_req()).unwrap()).unwrap();
+        assert_eq!(body["contents"][0]["role"], "user");
+        assert_eq!(body["contents"][1]["role"], "model");
+        assert_eq!(body["contents"][1]["parts"][0]["text"], "hi");
+        assert_eq!(body["generationConfig"]["maxOutputTokens"], 64);
+        assert_eq!(body["generationConfig"]["topP"], 1.0);
+        assert_eq!(body["generationConfig"]["stopSequences"], json!(["END"]));
+    }
+
+    #[test]
+    fn system_messages_become_the_system_instruction() {
+        let req = sanitized("gemini", "gemini-1.5-flash", vec![msg("system", "be terse"), msg("user", "hello")]);
+        let body = serde_json::to_value(build_request(&req).unwrap()).unwrap();
+        assert_eq!(body["systemInstruction"], json!({ "parts": [{ "text": "be terse" }] }));
+        assert_eq!(body["contents"], json!([{ "role": "user", "parts": [{ "text": "hello" }] }]));
+
+        let body = serde_json::to_value(build_request(&gemini_req()).unwrap()).unwrap();
+        assert!(body.get("systemInstruction").is_none());
+    }
+
+    #[test]
+    fn mid_conversation_system_message_is_rejected() {
+        let req = sanitized(
+            "gemini",
+            "gemini-1.5-flash",
+            vec![msg("system", "be terse"), msg("user", "hello"), msg("system", "now be verbose")],
+        );
+        let err = build_request(&req).err().unwrap();
+        assert!(matches!(&err, ProviderError::InvalidRequest(m) if m.contains("system messages")), "{err}");
+    }
+
+    #[tokio::test]
+    async fn dispatch_normalizes_gemini_response() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1beta/models/gemini-1.5-flash:generateContent"))
+            .and(query_param("key", "k123"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "candidates": [{
+                    "content": { "role": "model", "parts": [{ "text": "bonjour" }] },
+                    "finishReason": "STOP"
+                }],
+                "usageMetadata": { "promptTokenCount": 7, "candidatesTokenCount": 3 },
+                "responseId": "resp-1"
+            })))
+            .mount(&server)
+            .await;
+
+        let p = GeminiProvider::new(server.uri(), Some("k123".into()));
+        let out = p.dispatch(&gemini_req()).await.unwrap();
+        assert_eq!(out.normalized.content, "bonjour");
+        assert_eq!(out.normalized.finish_reason.as_deref(), Some("stop"));
+        assert_eq!(out.raw_json["candidates"][0]["finishReason"], "STOP");
+        assert_eq!(out.normalized.usage.input_tokens, Some(7));
+        assert_eq!(out.normalized.usage.output_tokens, Some(3));
+        assert_eq!(out.normalized.provider_request_id.as_deref(), Some("resp-1"));
+    }
+
+    #[test]
+    fn max_tokens_finish_reason_marks_truncation() {
+        let out = normalize(json!({
+            "candidates": [{ "content": { "parts": [{ "text": "partial" }] }, "finishReason": "MAX_TOKENS" }]
+        }))
+        .unwrap();
+        assert!(out.normalized.was_truncated());
+    }
+
+    #[test]
+    fn finish_reasons_map_to_the_canonical_vocabulary() {
+        for (native, canonical) in [("STOP", "stop"), ("MAX_TOKENS", "length")] {
+            let out = normalize(json!({
+                "candidates": [{ "content": { "parts": [{ "text": "x" }] }, "finishReason": native }]
+            }))
+            .unwrap();
+            assert_eq!(out.normalized.finish_reason.as_deref(), Some(canonical), "{native}");
+        }
+    }
+
+    #[test]
+    fn blocked_candidate_without_content_is_a_content_filter_refusal() {
+        let out = normalize(json!({
+            "candidates": [{
+                "finishReason": "SAFETY",
+                "index": 0,
+                "safetyRatings": [{ "category": "HARM_CATEGORY_DANGEROUS_CONTENT", "probability": "HIGH", "blocked": true }]
+            }],
+            "usageMetadata": { "promptTokenCount": 9, "totalTokenCount": 9 }
+        }))
+        .unwrap();
+        assert_eq!(out.normalized.content, "");
+        assert_eq!(out.normalized.finish_reason.as_deref(), Some("content_filter"));
+        assert_eq!(out.normalized.refusal.as_deref(), Some("content_filter"));
+    }
+
+    #[test]
+    fn blocked_prompt_maps_block_reason_to_content_filter() {
+        let out = normalize(json!({
+            "promptFeedback": {
+                "blockReason": "SAFETY",
+                "safetyRatings": [{ "category": "HARM_CATEGORY_HARASSMENT", "probability": "HIGH" }]
+            },
+            "usageMetadata": { "promptTokenCount": 7, "totalTokenCount": 7 }
+        }))
+        .unwrap();
+        assert_eq!(out.normalized.content, "");
+        assert_eq!(out.normalized.finish_reason.as_deref(), Some("content_filter"));
+        assert_eq!(out.normalized.refusal.as_deref(), Some("content_filter"));
+        assert_eq!(out.normalized.usage.input_tokens, Some(7));
+
+        assert!(matches!(normalize(json!({})), Err(ProviderError::InvalidResponse(_))));
+    }
+}
@@ -5,39 +5,150 @@
 //! Input MUST be SanitizedModelRequest.
 
 use async_trait::async_trait;
-use pie_redaction::{PromptMessage, SanitizedModelRequest};
+use pie_redaction::{MessageContent, Prompt, PromptMessage, SanitizedModelRequest};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use thiserror::Error;
 
+pub mod anthropic;
+pub mod azure;
+mod batch;
+pub mod bedrock;
+pub mod factory;
+pub mod gemini;
+pub mod mock;
+pub mod ollama;
+mod sse;
+
+pub use anthropic::AnthropicProvider;
+pub use azure::AzureOpenAIProvider;
+pub use batch::dispatch_many;
+pub use bedrock::{AwsCredentials, BedrockProvider, CredentialSource};
+pub use factory::{build_provider, build_provider_with_options};
+pub use gemini::GeminiProvider;
+pub use mock::{MockProvider, DRY_RUN_ID_PREFIX};
+pub use ollama::OllamaProvider;
+pub use reqwest::header::HeaderMap;
+
+#[cfg(test)]
+mod test_support;
+
 #[derive(Debug, Error)]
 pub enum ProviderError {
     #[error("http error: {0}")]
     Http(#[from] reqwest::Error),
     #[error("invalid response: {0}")]
     InvalidResponse(String),
+    /// Non-2xx response. `kind`/`code` come from a `{"error":{...}}` body when present;
+    /// otherwise `message` is the raw body text.
+    #[error("provider error (http {status}): {message}")]
+    Provider { status: u16, kind: Option<String>, code: Option<String>, message: String },
+    #[error("rate limited (retry after {retry_after:?})")]
+    RateLimited { retry_after: Option<Duration> },
+    #[error("request timed out")]
+    Timeout,
+    /// The endpoint could not be reached (DNS, refused connection, TLS handshake).
+    #[error("connection failed: {0}")]
+    Connect(reqwest::Error),
+    #[error("unknown provider: {0}")]
+    UnknownProvider(String),
+    #[error("invalid request: {0}")]
+    InvalidRequest(String),
+    #[error("integrity mismatch: request declares {expected}, content hashes to {actual}")]
+    IntegrityMismatch { expected: String, actual: String },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChatMsg {
     pub role: String,
-    pub content: String,
+    /// A bare string, or typed text/image_url parts for vision models.
+    pub content: MessageContent,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Usage {
     pub input_tokens: Option<u64>,
     pub output_tokens: Option<u64>,
+    /// Hidden reasoning tokens; already included in `output_tokens` (and billed as output).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reasoning_tokens: Option<u64>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// A function call requested by the model. `arguments` is the provider's JSON string, unparsed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolCall {
+    pub id: String,
+    pub name: String,
+    pub arguments: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ProviderReply {
     pub content: String,
     pub finish_reason: Option<String>,
     pub usage: Usage,
     /// Raw provider request id if present (Rust control plane will hash it for audit)
     pub provider_request_id: Option<String>,
+    /// Empty for plain-text replies (and omitted from the normalized artifact).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tool_calls: Vec<ToolCall>,
+    /// Every returned choice (n > 1). `content`/`finish_reason`/`tool_calls` above mirror choices[0].
+    /// Empty for providers without a choices concept.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub choices: Vec<ProviderChoice>,
+    /// Set when the provider refused or filtered the completion: its refusal text if it sent one,
+    /// otherwise `content_filter`. `None` for an ordinary completion, however short.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refusal: Option<String>,
+    /// choices[0].logprobs as returned, when requested via `Prompt::logprobs`. Never folded into `content`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub logprobs: Option<Value>,
+}
+
+impl ProviderReply {
+    /// True when the reply was cut off by `max_output_tokens` and the caller may want to continue.
+    pub fn was_truncated(&self) -> bool {
+        self.finish_reason.as_deref() == Some("length")
+    }
+}
+
+/// Map a provider-native stop reason onto the canonical vocabulary used in
+/// `ProviderReply.finish_reason`: `stop`, `length`, `content_filter`, `tool_calls`.
+/// Unrecognised values pass through unchanged; the native value stays in `raw_json`.
+pub fn canonical_finish_reason(native: &str) -> String {
+    match native {
+        // OpenAI-compatible / Azure / Ollama, Anthropic, Gemini
+        "stop" | "end_turn" | "stop_sequence" | "STOP" => "stop",
+        "length" | "max_tokens" | "MAX_TOKENS" => "length",
+        "tool_calls" | "function_call" | "tool_use" => "tool_calls",
+        "content_filter" | "refusal" | "SAFETY" | "RECITATION" | "BLOCKLIST" | "PROHIBITED_CONTENT" | "SPII" => {
+            "content_filter"
+        }
+        other => other,
+    }
+    .to_string()
+}
+
+/// Explicit refusal text wins; a canonical `content_filter` finish reason is the fallback signal.
+pub(crate) fn refusal_signal(explicit: Option<&str>, finish_reason: Option<&str>) -> Option<String> {
+    explicit
+        .filter(|r| !r.is_empty())
+        .or(finish_reason.filter(|r| *r == "content_filter"))
+        .map(|r| r.to_string())
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProviderChoice {
+    pub index: u64,
+    pub content: String,
+    pub finish_reason: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tool_calls: Vec<ToolCall>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refusal: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -48,7 +159,259 @@ pub struct ProviderResponse {
 
 #[async_trait]
 pub trait Provider: Send + Sync {
+    /// Stable implementation name (matches the `ProviderId` the factory keys on). Defaults to the
+    /// implementing type's name so out-of-tree providers keep compiling; built-ins override it.
+    fn name(&self) -> &'static str {
+        std::any::type_name::<Self>()
+    }
+
     async fn dispatch(&self, req: &SanitizedModelRequest) -> Result<ProviderResponse, ProviderError>;
+
+    /// The exact body bytes `dispatch` sends for `req`, so callers can audit what went on the wire.
+    /// `integrity.post_hash` covers the sanitized request, not this provider-shaped body. None when
+    /// the transport does not expose its body (the default).
+    fn request_body(&self, _req: &SanitizedModelRequest) -> Result<Option<Vec<u8>>, ProviderError> {
+        Ok(None)
+    }
+
+    /// `dispatch`, abandoned with `ProviderError::Timeout` once `deadline` passes.
+    ///
+    /// Independent of the client's socket timeout: lets a scheduler enforce a hard per-tick budget.
+    /// The in-flight request is dropped (connection closed) when the deadline fires.
+    async fn dispatch_with_deadline(
+        &self,
+        req: &SanitizedModelRequest,
+        deadline: Instant,
+    ) -> Result<ProviderResponse, ProviderError> {
+        tokio::time::timeout_at(deadline.into(), self.dispatch(req))
+            .await
+            .unwrap_or(Err(ProviderError::Timeout))
+    }
+
+    /// `dispatch`, delivering reply text to `on_delta` as it arrives. Returns the same aggregated
+    /// response `dispatch` would, so artifacts and hashes do not depend on whether a call streamed.
+    ///
+    /// Backends without a streaming transport make one ordinary call and deliver the whole content
+    /// as a single delta.
+    async fn dispatch_stream(
+        &self,
+        req: &SanitizedModelRequest,
+        on_delta: &mut (dyn for<'d> FnMut(&'d str) + Send),
+    ) -> Result<ProviderResponse, ProviderError> {
+        let out = self.dispatch(req).await?;
+        if !out.normalized.content.is_empty() {
+            on_delta(&out.normalized.content);
+        }
+        Ok(out)
+    }
+}
+
+/// Embedding vectors in input order, plus token usage (`output_tokens` is always None).
+#[derive(Debug, Clone)]
+pub struct EmbeddingResponse {
+    pub raw_json: Value,
+    pub embeddings: Vec<Vec<f32>>,
+    pub usage: Usage,
+}
+
+/// Embeddings travel over the same transport as chat, but take bare inputs rather than a
+/// `SanitizedModelRequest`: callers embed content they already hold locally (e.g. episode text).
+#[async_trait]
+pub trait EmbeddingProvider: Send + Sync {
+    async fn embed(&self, model: &str, inputs: &[String]) -> Result<EmbeddingResponse, ProviderError>;
+}
+
+/// Recompute the request's post-redaction hash and compare it to `integrity.post_hash`.
+///
+/// Catches a `request_post.json` edited after redaction before anything leaves the process.
+pub fn verify_integrity(req: &SanitizedModelRequest) -> Result<(), ProviderError> {
+    let actual = req
+        .compute_post_hash()
+        .map_err(|e| ProviderError::InvalidRequest(format!("cannot hash request: {e}")))?;
+    if actual != req.integrity.post_hash {
+        return Err(ProviderError::IntegrityMismatch { expected: req.integrity.post_hash.clone(), actual });
+    }
+    Ok(())
+}
+
+/// `verify_integrity`, then `provider.dispatch`. Nothing is sent on mismatch.
+pub async fn dispatch_verified(
+    provider: &dyn Provider,
+    req: &SanitizedModelRequest,
+) -> Result<ProviderResponse, ProviderError> {
+    verify_integrity(req)?;
+    provider.dispatch(req).await
+}
+
+/// Connect timeout applied when only a whole-request timeout is configured.
+const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Transport knobs shared by every HTTP provider. Default = reqwest defaults (no timeout).
+#[derive(Debug, Clone, Default)]
+pub struct ProviderOptions {
+    /// Whole-request timeout (connect + send + read body).
+    pub timeout_ms: Option<u64>,
+    /// TCP/TLS connect timeout. Defaults to min(timeout_ms, 10s) when timeout_ms is set.
+    pub connect_timeout_ms: Option<u64>,
+    /// mTLS client identity: PEM holding the certificate chain and its private key.
+    /// PEM only (rustls); convert PKCS#12 bundles with `openssl pkcs12 -nodes`.
+    pub client_identity: Option<Pem>,
+    /// Extra trusted root CA (PEM), for endpoints behind a private CA.
+    pub root_ca: Option<Pem>,
+    /// Egress proxy for all traffic, e.g. http://proxy.internal:3128. When None, reqwest's
+    /// system proxy detection applies (`HTTPS_PROXY`/`HTTP_PROXY`/`ALL_PROXY`, with `NO_PROXY`).
+    pub proxy: Option<String>,
+    /// Comma-separated `NO_PROXY`-style bypass list for `proxy`; falls back to the `NO_PROXY` env var.
+    pub no_proxy: Option<String>,
+}
+
+/// PEM bytes. Debug never prints the contents, since identities carry private keys.
+#[derive(Clone)]
+pub struct Pem(pub Vec<u8>);
+
+impl std::fmt::Debug for Pem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Pem(<{} bytes>)", self.0.len())
+    }
+}
+
+impl ProviderOptions {
+    pub(crate) fn build_client(&self) -> Result<Client, ProviderError> {
+        // Some gateways compress large completions; reqwest strips Content-Encoding before `json()`.
+        let mut b = Client::builder().gzip(true).brotli(true).deflate(true);
+        if let Some(ms) = self.timeout_ms {
+            b = b.timeout(Duration::from_millis(ms));
+        }
+        let connect = match (self.connect_timeout_ms, self.timeout_ms) {
+            (Some(ms), _) => Some(Duration::from_millis(ms)),
+            (None, Some(ms)) => Some(Duration::from_millis(ms).min(DEFAULT_CONNECT_TIMEOUT)),
+            (None, None) => None,
+        };
+        if let Some(d) = connect {
+            b = b.connect_timeout(d);
+        }
+        if let Some(pem) = &self.client_identity {
+            // PEM identities are rustls-only; pin the backend so reqwest doesn't pick native-tls.
+            b = b.use_rustls_tls();
+            let identity = reqwest::Identity::from_pem(&pem.0)
+                .map_err(|e| ProviderError::InvalidRequest(format!("invalid client identity: {e}")))?;
+            b = b.identity(identity);
+        }
+        if let Some(pem) = &self.root_ca {
+            let ca = reqwest::Certificate::from_pem(&pem.0)
+                .map_err(|e| ProviderError::InvalidRequest(format!("invalid root CA: {e}")))?;
+            b = b.add_root_certificate(ca);
+        }
+        if let Some(url) = &self.proxy {
+            let bypass = match &self.no_proxy {
+                Some(list) => reqwest::NoProxy::from_string(list),
+                None => reqwest::NoProxy::from_env(),
+            };
+            let proxy = reqwest::Proxy::all(url.as_str())
+                .map_err(|e| ProviderError::InvalidRequest(format!("invalid proxy url: {e}")))?
+                .no_proxy(bypass);
+            b = b.proxy(proxy);
+        }
+        Ok(b.build()?)
+    }
+}
+
+/// Classify transport errors; timeouts get their own variant so audit can record CallStatus::Timeout,
+/// and unreachable endpoints are told apart from failures mid-request.
+fn http_error(e: reqwest::Error) -> ProviderError {
+    if e.is_timeout() {
+        ProviderError::Timeout
+    } else if e.is_connect() {
+        ProviderError::Connect(e)
+    } else {
+        ProviderError::Http(e)
+    }
+}
+
+/// Outbound body as canonical JSON (sorted keys, no whitespace), the same encoding
+/// `integrity.post_hash` is computed with, so the bytes on the wire are reproducible from the
+/// sanitized request alone. Every built-in provider sends its body this way.
+fn canonical_body<T: Serialize>(body: &T) -> Result<Vec<u8>, ProviderError> {
+    pie_common::canonical_json_bytes(body)
+        .map_err(|e| ProviderError::InvalidRequest(format!("cannot encode body: {e}")))
+}
+
+/// Attach `body` as canonical JSON.
+fn with_canonical_json<T: Serialize>(
+    r: reqwest::RequestBuilder,
+    body: &T,
+) -> Result<reqwest::RequestBuilder, ProviderError> {
+    Ok(r.header(reqwest::header::CONTENT_TYPE, "application/json").body(canonical_body(body)?))
+}
+
+/// Read a JSON body, surfacing non-2xx responses as `ProviderError::Provider`.
+/// 429 becomes `RateLimited` so callers can back off.
+async fn read_json(resp: reqwest::Response) -> Result<Value, ProviderError> {
+    check_status(resp).await?.json().await.map_err(http_error)
+}
+
+/// The non-2xx handling of `read_json`, for callers that consume the body themselves (streaming).
+async fn check_status(resp: reqwest::Response) -> Result<reqwest::Response, ProviderError> {
+    let status = resp.status();
+    if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+        return Err(ProviderError::RateLimited { retry_after: parse_retry_after(resp.headers()) });
+    }
+    if !status.is_success() {
+        let body = resp.text().await.map_err(http_error)?;
+        return Err(parse_error_body(status.as_u16(), body));
+    }
+    Ok(resp)
+}
+
+/// `{"error":{"message","type","code"}}` (OpenAI-compatible, Anthropic; Gemini uses `status` for the kind).
+/// Anything else keeps the raw body as the message.
+fn parse_error_body(status: u16, body: String) -> ProviderError {
+    let as_string = |v: &Value| match v {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    };
+    let parsed = serde_json::from_str::<Value>(&body).ok();
+    let error = parsed.as_ref().and_then(|v| v.get("error")).filter(|e| e.is_object());
+    match error.and_then(|e| Some((e, e.get("message")?.as_str()?))) {
+        Some((e, message)) => ProviderError::Provider {
+            status,
+            kind: e.get("type").or_else(|| e.get("status")).and_then(as_string),
+            code: e.get("code").and_then(as_string),
+            message: message.to_string(),
+        },
+        None => ProviderError::Provider { status, kind: None, code: None, message: body },
+    }
+}
+
+/// Header names carrying the provider's canonical request id, in preference order.
+const REQUEST_ID_HEADERS: [&str; 3] = ["x-request-id", "openai-request-id", "request-id"];
+
+/// Provider request id from response headers (what provider support asks for). Read before the body is consumed.
+fn header_request_id(headers: &reqwest::header::HeaderMap) -> Option<String> {
+    REQUEST_ID_HEADERS
+        .iter()
+        .filter_map(|h| headers.get(*h))
+        .filter_map(|v| v.to_str().ok())
+        .map(|v| v.trim().to_string())
+        .find(|v| !v.is_empty())
+}
+
+/// Prefer the header request id; fall back to the body `id` already set by normalization.
+fn apply_header_request_id(mut out: ProviderResponse, header_id: Option<String>) -> ProviderResponse {
+    if header_id.is_some() {
+        out.normalized.provider_request_id = header_id;
+    }
+    out
+}
+
+/// `Retry-After` in delta-seconds form. The HTTP-date form is ignored (None).
+fn parse_retry_after(headers: &reqwest::header::HeaderMap) -> Option<Duration> {
+    headers
+        .get(reqwest::header::RETRY_AFTER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .map(Duration::from_secs)
 }
 
 fn to_chat_msgs(messages: &[PromptMessage]) -> Vec<ChatMsg> {
@@ -61,15 +424,178 @@ fn to_chat_msgs(messages: &[PromptMessage]) -> Vec<ChatMsg> {
         .collect()
 }
 
+/// Hoist the leading `system` messages out of `messages` for backends that take the system
+/// prompt as a separate field. Several are joined in order with a blank line; later
+/// `system` turns stay where they are.
+pub(crate) fn split_system(messages: Vec<ChatMsg>) -> (Option<String>, Vec<ChatMsg>) {
+    let lead = messages.iter().take_while(|m| m.role == "system").count();
+    let mut rest = messages;
+    let system: Vec<String> = rest.drain(..lead).map(|m| m.content.text()).collect();
+    ((!system.is_empty()).then(|| system.join("\n\n")), rest)
+}
+
+/// For backends whose request shape carries only the sampling basics (max tokens, temperature,
+/// top_p, stop): reject any other prompt option that is set rather than silently dropping it.
+/// `n: 1` and `logprobs: false` are what these backends do anyway, so they pass.
+fn reject_unsupported_options(prompt: &Prompt, provider: &str) -> Result<(), ProviderError> {
+    let options = [
+        ("tools", !prompt.tools.is_empty()),
+        ("tool_choice", prompt.tool_choice.is_some()),
+        ("n", prompt.n.is_some_and(|n| n != 1)),
+        ("seed", prompt.seed.is_some()),
+        ("response_format", prompt.response_format.is_some()),
+        ("frequency_penalty", prompt.frequency_penalty.is_some()),
+        ("presence_penalty", prompt.presence_penalty.is_some()),
+        ("logprobs", prompt.logprobs == Some(true)),
+        ("top_logprobs", prompt.top_logprobs.is_some()),
+        ("reasoning_effort", prompt.reasoning_effort.is_some()),
+    ];
+    let set: Vec<&str> = options.iter().filter(|(_, set)| *set).map(|(name, _)| *name).collect();
+    if set.is_empty() {
+        return Ok(());
+    }
+    Err(ProviderError::InvalidRequest(format!("{provider} does not support prompt.{}", set.join(", prompt."))))
+}
+
+/// Flatten to plain-text messages for backends whose chat shape has no image parts here.
+/// Images are rejected rather than silently dropped.
+fn to_text_chat_msgs(messages: &[PromptMessage], provider: &str) -> Result<Vec<ChatMsg>, ProviderError> {
+    messages
+        .iter()
+        .map(|m| {
+            if m.content.has_images() {
+                return Err(ProviderError::InvalidRequest(format!("{provider} does not accept image content parts")));
+            }
+            Ok(ChatMsg { role: m.role.clone(), content: MessageContent::Text(m.content.text()) })
+        })
+        .collect()
+}
+
 pub struct OpenAICompatProvider {
-    client: Client,
+    client: Arc<Client>,
     base_url: String,
     api_key: Option<String>,
+    /// Sent as `OpenAI-Organization` / `OpenAI-Project` when set.
+    organization: Option<String>,
+    project: Option<String>,
+    /// Merged into every request (gateway routing, User-Agent); see `request` for the headers that replace these.
+    default_headers: HeaderMap,
 }
 
 impl OpenAICompatProvider {
     pub fn new(base_url: String, api_key: Option<String>) -> Self {
-        Self { client: Client::new(), base_url, api_key }
+        Self::with_client(base_url, api_key, Arc::new(Client::new()))
+    }
+
+    /// Share one pooled client (keep-alive connections) across providers, e.g. for batch dispatch.
+    pub fn with_client(base_url: String, api_key: Option<String>, client: Arc<Client>) -> Self {
+        Self { client, base_url, api_key, organization: None, project: None, default_headers: HeaderMap::new() }
+    }
+
+    /// Attribute every call to an OpenAI organization and/or project (billing). Other
+    /// OpenAI-compatible backends ignore the extra headers.
+    pub fn with_organization(mut self, organization: Option<String>, project: Option<String>) -> Self {
+        self.organization = organization;
+        self.project = project;
+        self
+    }
+
+    /// Send `headers` on every request, e.g. a per-tenant routing header for an API gateway.
+    /// `Authorization`, `OpenAI-Organization`/`OpenAI-Project` and `Content-Type` defaults are
+    /// dropped whenever the provider sets that header itself.
+    pub fn with_default_headers(mut self, headers: HeaderMap) -> Self {
+        self.default_headers.extend(headers);
+        self
+    }
+
+    /// Replace reqwest's default `User-Agent`.
+    pub fn with_user_agent(mut self, user_agent: String) -> Result<Self, ProviderError> {
+        let value = reqwest::header::HeaderValue::try_from(user_agent)
+            .map_err(|e| ProviderError::InvalidRequest(format!("invalid user agent: {e}")))?;
+        self.default_headers.insert(reqwest::header::USER_AGENT, value);
+        Ok(self)
+    }
+
+    /// Same as `new`, but bounded by a whole-request timeout (connect timeout derived from it).
+    pub fn with_timeout(base_url: String, api_key: Option<String>, timeout_ms: u64) -> Result<Self, ProviderError> {
+        Self::with_options(base_url, api_key, &ProviderOptions { timeout_ms: Some(timeout_ms), ..Default::default() })
+    }
+
+    /// Present a client certificate (mTLS), optionally trusting a private root CA. Both are PEM.
+    pub fn with_mtls(
+        base_url: String,
+        api_key: Option<String>,
+        identity_pem: Vec<u8>,
+        root_ca_pem: Option<Vec<u8>>,
+    ) -> Result<Self, ProviderError> {
+        let opts = ProviderOptions {
+            client_identity: Some(Pem(identity_pem)),
+            root_ca: root_ca_pem.map(Pem),
+            ..Default::default()
+        };
+        Self::with_options(base_url, api_key, &opts)
+    }
+
+    /// Route every request through an egress proxy, except hosts in the `no_proxy` list.
+    pub fn with_proxy(
+        base_url: String,
+        api_key: Option<String>,
+        proxy: String,
+        no_proxy: Option<String>,
+    ) -> Result<Self, ProviderError> {
+        Self::with_options(base_url, api_key, &ProviderOptions { proxy: Some(proxy), no_proxy, ..Default::default() })
+    }
+
+    pub fn with_options(base_url: String, api_key: Option<String>, opts: &ProviderOptions) -> Result<Self, ProviderError> {
+        Ok(Self::with_client(base_url, api_key, Arc::new(opts.build_client()?)))
+    }
+
+    /// POST to `/v1/chat/completions`.
+    fn chat_completions(&self) -> reqwest::RequestBuilder {
+        self.request(reqwest::Method::POST, "/v1/chat/completions")
+    }
+
+    /// `method` on `endpoint` with the default and organization/project headers, and bearer auth
+    /// when a non-empty key is configured.
+    fn request(&self, method: reqwest::Method, endpoint: &str) -> reqwest::RequestBuilder {
+        use reqwest::header::{AUTHORIZATION, CONTENT_TYPE};
+        let url = format!("{}{endpoint}", self.base_url.trim_end_matches('/'));
+        // RequestBuilder::header appends, so a colliding default would be sent twice.
+        let mut defaults = self.default_headers.clone();
+        if self.organization.is_some() {
+            defaults.remove("openai-organization");
+        }
+        if self.project.is_some() {
+            defaults.remove("openai-project");
+        }
+        if self.api_key.as_deref().is_some_and(|k| !k.is_empty()) {
+            defaults.remove(AUTHORIZATION);
+        }
+        // Request bodies set their own.
+        defaults.remove(CONTENT_TYPE);
+        let mut r = self.client.request(method, url).headers(defaults);
+        if let Some(org) = &self.organization {
+            r = r.header("OpenAI-Organization", org);
+        }
+        if let Some(project) = &self.project {
+            r = r.header("OpenAI-Project", project);
+        }
+        match &self.api_key {
+            Some(k) if !k.is_empty() => r.bearer_auth(k),
+            _ => r,
+        }
+    }
+
+    /// Model ids the endpoint serves (`GET /v1/models`, `data[].id`), e.g. to check a target
+    /// model exists before dispatching to it.
+    pub async fn list_models(&self) -> Result<Vec<String>, ProviderError> {
+        let resp = self.request(reqwest::Method::GET, "/v1/models").send().await.map_err(http_error)?;
+        let raw = read_json(resp).await?;
+        let data = raw
+            .get("data")
+            .and_then(|d| d.as_array())
+            .ok_or_else(|| ProviderError::InvalidResponse("missing data[]".into()))?;
+        Ok(data.iter().filter_map(|m| m.get("id").and_then(|v| v.as_str()).map(str::to_string)).collect())
     }
 }
 
@@ -82,63 +608,1031 @@ struct OpenAICompatRequest<'a> {
     top_p: f64,
     #[serde(skip_serializing_if = "Vec::is_empty")]
     stop: Vec<String>,
+    #[serde(skip_serializing_if = "<[Value]>::is_empty")]
+    tools: &'a [Value],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_choice: Option<&'a Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    n: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seed: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_format: Option<&'a Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    frequency_penalty: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    presence_penalty: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    logprobs: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_logprobs: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reasoning_effort: Option<&'a str>,
+}
+
+/// `stream: true` body; `include_usage` asks for a final usage chunk.
+#[derive(Debug, Serialize)]
+struct OpenAIStreamRequest<'a> {
+    #[serde(flatten)]
+    inner: OpenAICompatRequest<'a>,
+    stream: bool,
+    stream_options: Value,
+}
+
+fn build_openai_request(req: &SanitizedModelRequest) -> OpenAICompatRequest<'_> {
+    OpenAICompatRequest {
+        model: &req.model.0,
+        messages: to_chat_msgs(&req.prompt.messages),
+        max_tokens: req.prompt.max_output_tokens,
+        temperature: req.prompt.temperature,
+        top_p: req.prompt.top_p,
+        stop: req.prompt.stop.clone(),
+        tools: &req.prompt.tools,
+        tool_choice: req.prompt.tool_choice.as_ref(),
+        n: req.prompt.n,
+        seed: req.prompt.seed,
+        response_format: req.prompt.response_format.as_ref(),
+        frequency_penalty: req.prompt.frequency_penalty,
+        presence_penalty: req.prompt.presence_penalty,
+        logprobs: req.prompt.logprobs,
+        top_logprobs: req.prompt.top_logprobs,
+        reasoning_effort: req.prompt.reasoning_effort.as_deref(),
+    }
+}
+
+/// choices[0].message.tool_calls[] -> ToolCall (function calls only)
+fn parse_tool_calls(message: Option<&Value>) -> Vec<ToolCall> {
+    message
+        .and_then(|m| m.get("tool_calls"))
+        .and_then(|t| t.as_array())
+        .map(|calls| {
+            calls
+                .iter()
+                .filter_map(|c| {
+                    let f = c.get("function")?;
+                    Some(ToolCall {
+                        id: c.get("id").and_then(|v| v.as_str()).unwrap_or("").to_string(),
+                        name: f.get("name")?.as_str()?.to_string(),
+                        arguments: f.get("arguments").and_then(|v| v.as_str()).unwrap_or("").to_string(),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// `message.content` is either a string or an array of typed parts.
+/// Text parts are concatenated in order; non-text parts (images, audio) carry no text and are skipped.
+/// They remain visible in `raw_json`.
+fn content_text(content: &Value) -> Option<String> {
+    match content {
+        Value::String(s) => Some(s.clone()),
+        Value::Array(parts) => Some(
+            parts
+                .iter()
+                .filter(|p| p.get("type").and_then(|t| t.as_str()) == Some("text"))
+                .filter_map(|p| p.get("text").and_then(|t| t.as_str()))
+                .collect::<String>(),
+        ),
+        _ => None,
+    }
+}
+
+fn parse_choice(idx: usize, choice: &Value) -> Result<ProviderChoice, ProviderError> {
+    let message = choice.get("message");
+    let tool_calls = parse_tool_calls(message);
+    let finish_reason = choice.get("finish_reason").and_then(|v| v.as_str()).map(canonical_finish_reason);
+    let refusal = refusal_signal(
+        message.and_then(|m| m.get("refusal")).and_then(|v| v.as_str()),
+        finish_reason.as_deref(),
+    );
+
+    // A pure tool-call turn, or a refusal, carries `content: null`.
+    let content = match message.and_then(|m| m.get("content")).and_then(content_text) {
+        Some(c) => c,
+        None if !tool_calls.is_empty() || refusal.is_some() => String::new(),
+        None => return Err(ProviderError::InvalidResponse(format!("missing choices[{idx}].message.content"))),
+    };
+
+    let index = choice.get("index").and_then(|v| v.as_u64()).unwrap_or(idx as u64);
+
+    Ok(ProviderChoice { index, content, finish_reason, tool_calls, refusal })
+}
+
+/// Normalize minimal shape: choices[].message.{content,tool_calls}, finish_reason, usage.
+/// The top-level reply mirrors choices[0].
+fn normalize_openai(raw: Value) -> Result<ProviderResponse, ProviderError> {
+    let choices = raw
+        .get("choices")
+        .and_then(|c| c.as_array())
+        .filter(|c| !c.is_empty())
+        .ok_or_else(|| ProviderError::InvalidResponse("missing choices[0].message.content".into()))?
+        .iter()
+        .enumerate()
+        .map(|(i, c)| parse_choice(i, c))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let first = choices[0].clone();
+    let logprobs = raw.pointer("/choices/0/logprobs").filter(|v| !v.is_null()).cloned();
+
+    let input_tokens = raw.get("usage").and_then(|u| u.get("prompt_tokens")).and_then(|v| v.as_u64());
+    let output_tokens = raw.get("usage").and_then(|u| u.get("completion_tokens")).and_then(|v| v.as_u64());
+    let reasoning_tokens = raw.pointer("/usage/completion_tokens_details/reasoning_tokens").and_then(|v| v.as_u64());
+
+    let provider_request_id = raw.get("id").and_then(|v| v.as_str()).map(|s| s.to_string());
+
+    Ok(ProviderResponse {
+        raw_json: raw.clone(),
+        normalized: ProviderReply {
+            content: first.content,
+            finish_reason: first.finish_reason,
+            usage: Usage { input_tokens, output_tokens, reasoning_tokens },
+            provider_request_id,
+            tool_calls: first.tool_calls,
+            refusal: first.refusal,
+            logprobs,
+            choices,
+        },
+    })
 }
 
 #[async_trait]
 impl Provider for OpenAICompatProvider {
+    fn name(&self) -> &'static str {
+        "openai"
+    }
+
+    fn request_body(&self, req: &SanitizedModelRequest) -> Result<Option<Vec<u8>>, ProviderError> {
+        canonical_body(&build_openai_request(req)).map(Some)
+    }
+
     async fn dispatch(&self, req: &SanitizedModelRequest) -> Result<ProviderResponse, ProviderError> {
-        let url = format!("{}/v1/chat/completions", self.base_url.trim_end_matches('/'));
-        let body = OpenAICompatRequest {
-            model: &req.model.0,
-            messages: to_chat_msgs(&req.prompt.messages),
-            max_tokens: req.prompt.max_output_tokens,
-            temperature: req.prompt.temperature,
-            top_p: req.prompt.top_p,
-            stop: req.prompt.stop.clone(),
+        let body = build_openai_request(req);
+
+        let resp = with_canonical_json(self.chat_completions(), &body)?.send().await.map_err(http_error)?;
+        let header_id = header_request_id(resp.headers());
+        let raw = read_json(resp).await?;
+
+        Ok(apply_header_request_id(normalize_openai(raw)?, header_id))
+    }
+
+    async fn dispatch_stream(
+        &self,
+        req: &SanitizedModelRequest,
+        on_delta: &mut (dyn for<'d> FnMut(&'d str) + Send),
+    ) -> Result<ProviderResponse, ProviderError> {
+        let body = OpenAIStreamRequest {
+            inner: build_openai_request(req),
+            stream: true,
+            stream_options: serde_json::json!({ "include_usage": true }),
         };
 
-        let mut r = self.client.post(url).json(&body);
-        if let Some(k) = &self.api_key {
-            if !k.is_empty() {
-                r = r.bearer_auth(k);
+        let resp = with_canonical_json(self.chat_completions(), &body)?.send().await.map_err(http_error)?;
+        let header_id = header_request_id(resp.headers());
+        let mut resp = check_status(resp).await?;
+
+        let mut agg = sse::ChunkAggregator::default();
+        while let Some(bytes) = resp.chunk().await.map_err(http_error)? {
+            for delta in agg.push(&bytes)? {
+                on_delta(&delta);
             }
         }
-        let resp = r.send().await?;
-        let raw: Value = resp.json().await?;
-
-        // Normalize minimal shape: choices[0].message.content, finish_reason, usage
-        let content = raw
-            .get("choices")
-            .and_then(|c| c.get(0))
-            .and_then(|c0| c0.get("message"))
-            .and_then(|m| m.get("content"))
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| ProviderError::InvalidResponse("missing choices[0].message.content".into()))?
-            .to_string();
-
-        let finish_reason = raw
-            .get("choices")
-            .and_then(|c| c.get(0))
-            .and_then(|c0| c0.get("finish_reason"))
-            .and_then(|v| v.as_str())
-            .map(|s| s.to_string());
-
-        let input_tokens = raw.get("usage").and_then(|u| u.get("prompt_tokens")).and_then(|v| v.as_u64());
-        let output_tokens = raw.get("usage").and_then(|u| u.get("completion_tokens")).and_then(|v| v.as_u64());
-
-        let provider_request_id = raw.get("id").and_then(|v| v.as_str()).map(|s| s.to_string());
-
-        Ok(ProviderResponse {
-            raw_json: raw.clone(),
-            normalized: ProviderReply {
-                content,
-                finish_reason,
-                usage: Usage { input_tokens, output_tokens },
-                provider_request_id,
-            },
-        })
+
+        Ok(apply_header_request_id(normalize_openai(agg.finish()?)?, header_id))
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct OpenAIEmbeddingRequest<'a> {
+    model: &'a str,
+    input: &'a [String],
+}
+
+/// Normalize data[].embedding (ordered by `index`), usage.prompt_tokens
+fn normalize_embeddings(raw: Value, expected: usize) -> Result<EmbeddingResponse, ProviderError> {
+    let data = raw
+        .get("data")
+        .and_then(|d| d.as_array())
+        .ok_or_else(|| ProviderError::InvalidResponse("missing data[]".into()))?;
+
+    let mut indexed = Vec::with_capacity(data.len());
+    for (pos, item) in data.iter().enumerate() {
+        let idx = item.get("index").and_then(|v| v.as_u64()).unwrap_or(pos as u64);
+        let vector = item
+            .get("embedding")
+            .and_then(|e| e.as_array())
+            .ok_or_else(|| ProviderError::InvalidResponse(format!("missing data[{pos}].embedding")))?
+            .iter()
+            .map(|x| x.as_f64().map(|f| f as f32))
+            .collect::<Option<Vec<f32>>>()
+            .ok_or_else(|| ProviderError::InvalidResponse(format!("non-numeric data[{pos}].embedding")))?;
+        indexed.push((idx, vector));
+    }
+    indexed.sort_by_key(|(idx, _)| *idx);
+
+    if indexed.len() != expected {
+        return Err(ProviderError::InvalidResponse(format!(
+            "expected {expected} embeddings, got {}",
+            indexed.len()
+        )));
+    }
+
+    let input_tokens = raw.get("usage").and_then(|u| u.get("prompt_tokens")).and_then(|v| v.as_u64());
+
+    Ok(EmbeddingResponse {
+        raw_json: raw.clone(),
+        embeddings: indexed.into_iter().map(|(_, v)| v).collect(),
+        usage: Usage { input_tokens, output_tokens: None, reasoning_tokens: None },
+    })
+}
+
+#[async_trait]
+impl EmbeddingProvider for OpenAICompatProvider {
+    async fn embed(&self, model: &str, inputs: &[String]) -> Result<EmbeddingResponse, ProviderError> {
+        let body = OpenAIEmbeddingRequest { model, input: inputs };
+
+        let r = with_canonical_json(self.request(reqwest::Method::POST, "/v1/embeddings"), &body)?;
+        let resp = r.send().await.map_err(http_error)?;
+        let raw = read_json(resp).await?;
+
+        normalize_embeddings(raw, inputs.len())
     }
 }
 
-// Placeholder: Anthropic/XAI can be added as separate providers later
-// You can still route "anthropic" and "xai" through OpenAICompat if your infra supports it
+// "xai" is not a factory id (it is UnknownProvider); an OpenAI-compatible xAI endpoint can be
+// reached by declaring provider "openai" with that base URL.
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{msg, sanitized, seal};
+    use pie_redaction::{ContentPart, ImageUrl};
+    use serde_json::json;
+    use wiremock::matchers::{body_partial_json, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn non_success_status_is_surfaced_with_body() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .respond_with(ResponseTemplate::new(401).set_body_json(json!({
+                "error": { "message": "Incorrect API key provided", "type": "invalid_request_error", "code": "invalid_api_key" }
+            })))
+            .mount(&server)
+            .await;
+
+        let p = OpenAICompatProvider::new(server.uri(), Some("bad".into()));
+        let err = p.dispatch(&sanitized("openai", "gpt", vec![msg("user", "hi")])).await.unwrap_err();
+        match err {
+            ProviderError::Provider { status, kind, code, message } => {
+                assert_eq!(status, 401);
+                assert_eq!(kind.as_deref(), Some("invalid_request_error"));
+                assert_eq!(code.as_deref(), Some("invalid_api_key"));
+                assert_eq!(message, "Incorrect API key provided");
+            }
+            other => panic!("expected Provider, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn unstructured_error_body_is_kept_as_message() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .respond_with(ResponseTemplate::new(502).set_body_string("<html>Bad Gateway</html>"))
+            .mount(&server)
+            .await;
+
+        let p = OpenAICompatProvider::new(server.uri(), None);
+        let err = p.dispatch(&sanitized("openai", "gpt", vec![msg("user", "hi")])).await.unwrap_err();
+        match err {
+            ProviderError::Provider { status, kind, code, message } => {
+                assert_eq!(status, 502);
+                assert!(kind.is_none() && code.is_none());
+                assert_eq!(message, "<html>Bad Gateway</html>");
+            }
+            other => panic!("expected Provider, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn dispatch_stream_delivers_deltas_and_matches_non_streamed_shape() {
+        let server = MockServer::start().await;
+        let sse = concat!(
+            "data: {\"id\":\"chatcmpl-s\",\"model\":\"gpt\",\"choices\":[{\"index\":0,\"delta\":{\"content\":\"Hi \"}}]}\n\n",
+            "data: {\"id\":\"chatcmpl-s\",\"choices\":[{\"index\":0,\"delta\":{\"content\":\"there\"},\"finish_reason\":\"stop\"}]}\n\n",
+            "data: {\"id\":\"chatcmpl-s\",\"choices\":[],\"usage\":{\"prompt_tokens\":4,\"completion_tokens\":2}}\n\n",
+            "data: [DONE]\n\n",
+        );
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .and(body_partial_json(json!({ "stream": true, "stream_options": { "include_usage": true } })))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(sse, "text/event-stream"))
+            .mount(&server)
+            .await;
+
+        let p = OpenAICompatProvider::new(server.uri(), None);
+        let mut deltas = Vec::new();
+        let out = p
+            .dispatch_stream(&sanitized("openai", "gpt", vec![msg("user", "hi")]), &mut |d| deltas.push(d.to_string()))
+            .await
+            .unwrap();
+        assert_eq!(deltas, ["Hi ", "there"]);
+        assert_eq!(out.normalized.content, "Hi there");
+        assert_eq!(out.normalized.finish_reason.as_deref(), Some("stop"));
+        assert_eq!(out.normalized.usage.output_tokens, Some(2));
+        assert_eq!(out.normalized.provider_request_id.as_deref(), Some("chatcmpl-s"));
+        assert_eq!(out.raw_json["object"], "chat.completion");
+    }
+
+    #[tokio::test]
+    async fn too_many_requests_maps_to_rate_limited_with_retry_after() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "5"))
+            .mount(&server)
+            .await;
+
+        let p = OpenAICompatProvider::new(server.uri(), None);
+        let err = p.dispatch(&sanitized("openai", "gpt", vec![msg("user", "hi")])).await.unwrap_err();
+        assert!(matches!(
+            err,
+            ProviderError::RateLimited { retry_after: Some(d) } if d == Duration::from_secs(5)
+        ));
+    }
+
+    #[tokio::test]
+    async fn tool_calls_are_sent_and_normalized() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .and(body_partial_json(json!({ "tool_choice": "auto", "tools": [{ "type": "function" }] })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "id": "chatcmpl-1",
+                "choices": [{
+                    "index": 0,
+                    "message": {
+                        "role": "assistant",
+                        "content": null,
+                        "tool_calls": [{
+                            "id": "call_1",
+                            "type": "function",
+                            "function": { "name": "get_weather", "arguments": "{\"city\":\"Oslo\"}" }
+                        }]
+                    },
+                    "finish_reason": "tool_calls"
+                }]
+            })))
+            .mount(&server)
+            .await;
+
+        let mut req = sanitized("openai", "gpt", vec![msg("user", "weather?")]);
+        req.prompt.tools = vec![json!({
+            "type": "function",
+            "function": { "name": "get_weather", "parameters": { "type": "object" } }
+        })];
+        req.prompt.tool_choice = Some(json!("auto"));
+
+        let p = OpenAICompatProvider::new(server.uri(), None);
+        let out = p.dispatch(&req).await.unwrap();
+        assert_eq!(out.normalized.content, "");
+        assert_eq!(out.normalized.finish_reason.as_deref(), Some("tool_calls"));
+        assert_eq!(
+            out.normalized.tool_calls,
+            vec![ToolCall { id: "call_1".into(), name: "get_weather".into(), arguments: "{\"city\":\"Oslo\"}".into() }]
+        );
+    }
+
+    #[tokio::test]
+    async fn multiple_choices_are_all_captured() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .and(body_partial_json(json!({ "n": 2 })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "id": "chatcmpl-2",
+                "choices": [
+                    { "index": 0, "message": { "role": "assistant", "content": "first" }, "finish_reason": "stop" },
+                    { "index": 1, "message": { "role": "assistant", "content": "second" }, "finish_reason": "length" }
+                ],
+                "usage": { "prompt_tokens": 5, "completion_tokens": 9 }
+            })))
+            .mount(&server)
+            .await;
+
+        let mut req = sanitized("openai", "gpt", vec![msg("user", "hi")]);
+        req.prompt.n = Some(2);
+
+        let p = OpenAICompatProvider::new(server.uri(), None);
+        let out = p.dispatch(&req).await.unwrap();
+        assert_eq!(out.normalized.content, "first");
+        assert_eq!(out.normalized.choices.len(), 2);
+        assert_eq!(out.normalized.choices[1].index, 1);
+        assert_eq!(out.normalized.choices[1].content, "second");
+        assert_eq!(out.normalized.choices[1].finish_reason.as_deref(), Some("length"));
+    }
+
+    #[tokio::test]
+    async fn request_id_header_is_preferred_over_body_id() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("x-request-id", "req_abc123")
+                    .set_body_json(json!({
+                        "id": "chatcmpl-body",
+                        "choices": [{ "message": { "role": "assistant", "content": "ok" }, "finish_reason": "stop" }]
+                    })),
+            )
+            .mount(&server)
+            .await;
+
+        let p = OpenAICompatProvider::new(server.uri(), None);
+        let out = p.dispatch(&sanitized("openai", "gpt", vec![msg("user", "hi")])).await.unwrap();
+        assert_eq!(out.normalized.provider_request_id.as_deref(), Some("req_abc123"));
+        // The body id is still available in the raw artifact.
+        assert_eq!(out.raw_json["id"], "chatcmpl-body");
+    }
+
+    #[test]
+    fn string_content_is_used_directly() {
+        let out = normalize_openai(json!({
+            "choices": [{ "message": { "role": "assistant", "content": "plain" }, "finish_reason": "stop" }]
+        }))
+        .unwrap();
+        assert_eq!(out.normalized.content, "plain");
+    }
+
+    #[test]
+    fn array_content_parts_are_concatenated() {
+        let out = normalize_openai(json!({
+            "choices": [{
+                "message": {
+                    "role": "assistant",
+                    "content": [
+                        { "type": "text", "text": "hello " },
+                        { "type": "image_url", "image_url": { "url": "https://example.com/x.png" } },
+                        { "type": "text", "text": "world" }
+                    ]
+                },
+                "finish_reason": "stop"
+            }]
+        }))
+        .unwrap();
+        assert_eq!(out.normalized.content, "hello world");
+        assert_eq!(out.normalized.choices[0].content, "hello world");
+    }
+
+    #[test]
+    fn length_finish_reason_marks_truncation() {
+        let out = normalize_openai(json!({
+            "choices": [{ "message": { "content": "partial" }, "finish_reason": "length" }]
+        }))
+        .unwrap();
+        assert!(out.normalized.was_truncated());
+
+        let out = normalize_openai(json!({
+            "choices": [{ "message": { "content": "done" }, "finish_reason": "stop" }]
+        }))
+        .unwrap();
+        assert!(!out.normalized.was_truncated());
+        assert!(!ProviderReply::default().was_truncated());
+    }
+
+    #[test]
+    fn filtered_responses_carry_a_refusal_signal() {
+        let out = normalize_openai(json!({
+            "choices": [{
+                "message": { "role": "assistant", "content": null, "refusal": "I can't help with that." },
+                "finish_reason": "stop"
+            }]
+        }))
+        .unwrap();
+        assert_eq!(out.normalized.refusal.as_deref(), Some("I can't help with that."));
+        assert_eq!(out.normalized.content, "");
+
+        let out = normalize_openai(json!({
+            "choices": [{ "message": { "content": "" }, "finish_reason": "content_filter" }]
+        }))
+        .unwrap();
+        assert_eq!(out.normalized.refusal.as_deref(), Some("content_filter"));
+    }
+
+    #[test]
+    fn a_short_normal_completion_is_not_a_refusal() {
+        let out = normalize_openai(json!({
+            "choices": [{ "message": { "content": "ok", "refusal": null }, "finish_reason": "stop" }]
+        }))
+        .unwrap();
+        assert!(out.normalized.refusal.is_none());
+        assert!(!serde_json::to_value(&out.normalized).unwrap().as_object().unwrap().contains_key("refusal"));
+    }
+
+    #[test]
+    fn image_parts_serialize_as_openai_content_array() {
+        let req = sanitized(
+            "openai",
+            "gpt-4o",
+            vec![PromptMessage {
+                role: "user".into(),
+                content: MessageContent::Parts(vec![
+                    ContentPart::Text { text: "describe".into() },
+                    ContentPart::ImageUrl {
+                        image_url: ImageUrl { url: "data:image/png;base64,iVBORw0KGgo=".into(), detail: Some("low".into()) },
+                    },
+                ]),
+            }],
+        );
+        let body = serde_json::to_value(build_openai_request(&req)).unwrap();
+        assert_eq!(
+            body["messages"][0]["content"],
+            json!([
+                { "type": "text", "text": "describe" },
+                { "type": "image_url", "image_url": { "url": "data:image/png;base64,iVBORw0KGgo=", "detail": "low" } }
+            ])
+        );
+
+        let err = to_text_chat_msgs(&req.prompt.messages, "ollama").unwrap_err();
+        assert!(matches!(err, ProviderError::InvalidRequest(_)));
+    }
+
+    #[test]
+    fn plain_requests_omit_tool_fields() {
+        let req = sanitized("openai", "gpt", vec![msg("user", "hi")]);
+        let body = serde_json::to_value(build_openai_request(&req)).unwrap();
+        assert!(body.get("tools").is_none());
+        assert!(body.get("tool_choice").is_none());
+        assert!(body.get("n").is_none());
+    }
+
+    #[test]
+    fn sampling_controls_are_serialized_only_when_set() {
+        let mut req = sanitized("openai", "gpt", vec![msg("user", "hi")]);
+        let body = serde_json::to_value(build_openai_request(&req)).unwrap();
+        for k in [
+            "seed", "response_format", "frequency_penalty", "presence_penalty", "logprobs", "top_logprobs",
+            "reasoning_effort",
+        ] {
+            assert!(body.get(k).is_none(), "{k} should be omitted");
+        }
+
+        req.prompt.seed = Some(42);
+        req.prompt.response_format = Some(json!({ "type": "json_object" }));
+        req.prompt.frequency_penalty = Some(0.5);
+        req.prompt.presence_penalty = Some(-0.25);
+        let body = serde_json::to_value(build_openai_request(&req)).unwrap();
+        assert_eq!(body["seed"], 42);
+        assert_eq!(body["response_format"], json!({ "type": "json_object" }));
+        assert_eq!(body["frequency_penalty"], 0.5);
+        assert_eq!(body["presence_penalty"], -0.25);
+    }
+
+    #[tokio::test]
+    async fn reasoning_effort_is_sent_and_reasoning_tokens_are_normalized() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .and(body_partial_json(json!({ "reasoning_effort": "high" })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "choices": [{ "message": { "content": "42" }, "finish_reason": "stop" }],
+                "usage": {
+                    "prompt_tokens": 10,
+                    "completion_tokens": 120,
+                    "completion_tokens_details": { "reasoning_tokens": 100 }
+                }
+            })))
+            .mount(&server)
+            .await;
+
+        let mut req = sanitized("openai", "o3-mini", vec![msg("user", "think")]);
+        req.prompt.reasoning_effort = Some("high".into());
+        let out = OpenAICompatProvider::new(server.uri(), None).dispatch(&req).await.unwrap();
+        assert_eq!(out.normalized.usage.output_tokens, Some(120));
+        assert_eq!(out.normalized.usage.reasoning_tokens, Some(100));
+        assert_eq!(out.normalized.content, "42");
+    }
+
+    #[tokio::test]
+    async fn requested_logprobs_are_captured_outside_the_content() {
+        let token_logprobs = json!({ "content": [
+            { "token": "ok", "logprob": -0.01, "top_logprobs": [{ "token": "ok", "logprob": -0.01 }] }
+        ] });
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .and(body_partial_json(json!({ "logprobs": true, "top_logprobs": 1 })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "choices": [{
+                    "message": { "role": "assistant", "content": "ok" },
+                    "logprobs": token_logprobs,
+                    "finish_reason": "stop"
+                }]
+            })))
+            .mount(&server)
+            .await;
+
+        let mut req = sanitized("openai", "gpt", vec![msg("user", "hi")]);
+        req.prompt.logprobs = Some(true);
+        req.prompt.top_logprobs = Some(1);
+        let out = OpenAICompatProvider::new(server.uri(), None).dispatch(&req).await.unwrap();
+        assert_eq!(out.normalized.content, "ok");
+        assert_eq!(out.normalized.logprobs, Some(token_logprobs));
+
+        let plain = normalize_openai(json!({
+            "choices": [{ "message": { "content": "ok" }, "logprobs": null, "finish_reason": "stop" }]
+        }))
+        .unwrap();
+        assert!(plain.normalized.logprobs.is_none());
+    }
+
+    #[tokio::test]
+    async fn providers_share_one_injected_client() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "choices": [{ "message": { "content": "pong" }, "finish_reason": "stop" }]
+            })))
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let client = Arc::new(Client::new());
+        let a = OpenAICompatProvider::with_client(server.uri(), None, client.clone());
+        let b = OpenAICompatProvider::with_client(server.uri(), Some("k".into()), client.clone());
+        assert_eq!(Arc::strong_count(&client), 3);
+
+        let req = sanitized("openai", "gpt", vec![msg("user", "ping")]);
+        assert_eq!(a.dispatch(&req).await.unwrap().normalized.content, "pong");
+        assert_eq!(b.dispatch(&req).await.unwrap().normalized.content, "pong");
+    }
+
+    #[tokio::test]
+    async fn gzip_encoded_responses_are_decoded() {
+        use std::io::Write;
+        let body = json!({
+            "choices": [{ "message": { "content": "x".repeat(4096) }, "finish_reason": "stop" }]
+        });
+        let mut gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        gz.write_all(body.to_string().as_bytes()).unwrap();
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("Content-Encoding", "gzip")
+                    .set_body_raw(gz.finish().unwrap(), "application/json"),
+            )
+            .mount(&server)
+            .await;
+
+        let req = sanitized("openai", "gpt", vec![msg("user", "hi")]);
+        let opts = ProviderOptions { timeout_ms: Some(5_000), ..Default::default() };
+        for p in [
+            OpenAICompatProvider::new(server.uri(), None),
+            OpenAICompatProvider::with_options(server.uri(), None, &opts).unwrap(),
+        ] {
+            assert_eq!(p.dispatch(&req).await.unwrap().normalized.content.len(), 4096);
+        }
+    }
+
+    #[tokio::test]
+    async fn default_headers_and_user_agent_are_sent_on_every_request() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .and(header("user-agent", "pie-gateway/1.0"))
+            .and(header("x-tenant", "acme"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "choices": [{ "message": { "content": "pong" }, "finish_reason": "stop" }]
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/v1/embeddings"))
+            .and(header("x-tenant", "acme"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "data": [{ "index": 0, "embedding": [1.0] }]
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let mut headers = HeaderMap::new();
+        headers.insert("x-tenant", "acme".parse().unwrap());
+        let p = OpenAICompatProvider::new(server.uri(), None)
+            .with_default_headers(headers)
+            .with_user_agent("pie-gateway/1.0".into())
+            .unwrap();
+        p.dispatch(&sanitized("openai", "gpt", vec![msg("user", "ping")])).await.unwrap();
+        p.embed("e", &["a".to_string()]).await.unwrap();
+
+        let bad = OpenAICompatProvider::new(server.uri(), None).with_user_agent("bad\nagent".into());
+        assert!(matches!(bad.err(), Some(ProviderError::InvalidRequest(_))));
+    }
+
+    #[tokio::test]
+    async fn per_request_headers_replace_colliding_defaults() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "choices": [{ "message": { "content": "pong" }, "finish_reason": "stop" }]
+            })))
+            .mount(&server)
+            .await;
+
+        let mut headers = HeaderMap::new();
+        headers.insert("authorization", "Bearer default".parse().unwrap());
+        headers.insert("openai-organization", "org-default".parse().unwrap());
+        headers.insert("content-type", "text/plain".parse().unwrap());
+        headers.insert("x-tenant", "acme".parse().unwrap());
+        let p = OpenAICompatProvider::new(server.uri(), Some("sk-k".into()))
+            .with_organization(Some("org-x".into()), None)
+            .with_default_headers(headers);
+        p.dispatch(&sanitized("openai", "gpt", vec![msg("user", "ping")])).await.unwrap();
+
+        let received = server.received_requests().await.unwrap();
+        let values = |name: &str| -> Vec<String> {
+            received[0].headers.get_all(name).iter().map(|v| v.to_str().unwrap().to_string()).collect()
+        };
+        assert_eq!(values("authorization"), ["Bearer sk-k"]);
+        assert_eq!(values("openai-organization"), ["org-x"]);
+        assert_eq!(values("content-type"), ["application/json"]);
+        assert_eq!(values("x-tenant"), ["acme"]);
+    }
+
+    #[tokio::test]
+    async fn proxy_routes_requests_through_the_gateway() {
+        // The mock server plays the forward proxy: plain-HTTP proxying sends the absolute URI to it.
+        let proxy = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "choices": [{ "message": { "content": "via proxy" }, "finish_reason": "stop" }]
+            })))
+            .expect(1)
+            .mount(&proxy)
+            .await;
+
+        let p = OpenAICompatProvider::with_proxy("http://api.example.invalid".into(), None, proxy.uri(), None).unwrap();
+        let out = p.dispatch(&sanitized("openai", "gpt", vec![msg("user", "hi")])).await.unwrap();
+        assert_eq!(out.normalized.content, "via proxy");
+
+        let bypassed = OpenAICompatProvider::with_proxy(
+            "http://127.0.0.1:9".into(),
+            None,
+            proxy.uri(),
+            Some("127.0.0.1".into()),
+        )
+        .unwrap();
+        assert!(bypassed.dispatch(&sanitized("openai", "gpt", vec![msg("user", "hi")])).await.is_err());
+
+        let bad = OpenAICompatProvider::with_proxy("http://x".into(), None, "::not a url".into(), None);
+        assert!(matches!(bad.err(), Some(ProviderError::InvalidRequest(_))));
+    }
+
+    #[tokio::test]
+    async fn list_models_returns_the_served_ids() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/models"))
+            .and(header("authorization", "Bearer k"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "object": "list",
+                "data": [
+                    { "id": "gpt-4o", "object": "model", "owned_by": "system" },
+                    { "id": "gpt-4o-mini", "object": "model", "owned_by": "system" }
+                ]
+            })))
+            .mount(&server)
+            .await;
+
+        let models = OpenAICompatProvider::new(server.uri(), Some("k".into())).list_models().await.unwrap();
+        assert_eq!(models, ["gpt-4o", "gpt-4o-mini"]);
+    }
+
+    #[tokio::test]
+    async fn organization_and_project_headers_are_sent_only_when_configured() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "choices": [{ "message": { "content": "pong" }, "finish_reason": "stop" }]
+            })))
+            .mount(&server)
+            .await;
+
+        let req = sanitized("openai", "gpt", vec![msg("user", "ping")]);
+        OpenAICompatProvider::new(server.uri(), None)
+            .with_organization(Some("org-1".into()), Some("proj-1".into()))
+            .dispatch(&req)
+            .await
+            .unwrap();
+        OpenAICompatProvider::new(server.uri(), None).dispatch(&req).await.unwrap();
+
+        let received = server.received_requests().await.unwrap();
+        assert_eq!(received[0].headers.get("openai-organization").unwrap(), "org-1");
+        assert_eq!(received[0].headers.get("openai-project").unwrap(), "proj-1");
+        assert!(received[1].headers.get("openai-organization").is_none());
+        assert!(received[1].headers.get("openai-project").is_none());
+    }
+
+    #[tokio::test]
+    async fn embed_returns_vectors_in_input_order() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/embeddings"))
+            .and(body_partial_json(json!({ "model": "text-embedding-3-small", "input": ["alpha", "beta"] })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "object": "list",
+                "data": [
+                    { "object": "embedding", "index": 1, "embedding": [0.5, -0.5] },
+                    { "object": "embedding", "index": 0, "embedding": [0.25, 0.75] }
+                ],
+                "model": "text-embedding-3-small",
+                "usage": { "prompt_tokens": 4, "total_tokens": 4 }
+            })))
+            .mount(&server)
+            .await;
+
+        let p = OpenAICompatProvider::new(server.uri(), None);
+        let out = p.embed("text-embedding-3-small", &["alpha".into(), "beta".into()]).await.unwrap();
+        assert_eq!(out.embeddings, vec![vec![0.25, 0.75], vec![0.5, -0.5]]);
+        assert_eq!(out.usage.input_tokens, Some(4));
+        assert_eq!(out.usage.output_tokens, None);
+    }
+
+    #[tokio::test]
+    async fn deadline_fires_before_slow_response() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(5)))
+            .mount(&server)
+            .await;
+
+        // No client timeout: only the deadline bounds the call.
+        let p = OpenAICompatProvider::new(server.uri(), None);
+        let req = sanitized("openai", "gpt", vec![msg("user", "hi")]);
+        let start = Instant::now();
+        let err = p.dispatch_with_deadline(&req, start + Duration::from_millis(100)).await.unwrap_err();
+        assert!(matches!(err, ProviderError::Timeout), "got {err:?}");
+        assert!(start.elapsed() < Duration::from_secs(2));
+    }
+
+    #[test]
+    fn mtls_builder_accepts_self_signed_identity() {
+        let ck = rcgen::generate_simple_self_signed(vec!["gateway.internal".into()]).unwrap();
+        let cert_pem = ck.cert.pem();
+        let identity = format!("{}{}", cert_pem, ck.key_pair.serialize_pem());
+
+        let p = OpenAICompatProvider::with_mtls(
+            "https://gateway.internal".into(),
+            None,
+            identity.into_bytes(),
+            Some(cert_pem.into_bytes()),
+        )
+        .unwrap();
+        assert_eq!(p.name(), "openai");
+
+        let err = OpenAICompatProvider::with_mtls("https://gateway.internal".into(), None, b"not pem".to_vec(), None)
+            .err()
+            .unwrap();
+        assert!(matches!(err, ProviderError::InvalidRequest(ref m) if m.contains("client identity")), "got {err:?}");
+
+        let opts = ProviderOptions { client_identity: Some(Pem(b"secret".to_vec())), ..Default::default() };
+        assert!(!format!("{opts:?}").contains("secret"));
+    }
+
+    #[tokio::test]
+    async fn slow_provider_hits_client_timeout() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_millis(500)))
+            .mount(&server)
+            .await;
+
+        let p = OpenAICompatProvider::with_timeout(server.uri(), None, 100).unwrap();
+        let err = p.dispatch(&sanitized("openai", "gpt", vec![msg("user", "hi")])).await.unwrap_err();
+        assert!(matches!(err, ProviderError::Timeout), "got {err:?}");
+    }
+
+    #[tokio::test]
+    async fn unreachable_endpoint_is_a_connect_error() {
+        // Bind then drop a listener so the port is known to refuse connections.
+        let port = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        let p = OpenAICompatProvider::with_timeout(format!("http://127.0.0.1:{port}"), None, 2_000).unwrap();
+        let err = p.dispatch(&sanitized("openai", "gpt", vec![msg("user", "hi")])).await.unwrap_err();
+        assert!(matches!(err, ProviderError::Connect(_)), "got {err:?}");
+    }
+
+    #[tokio::test]
+    async fn dispatched_body_is_the_canonical_encoding_of_a_verified_request() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .and(header("content-type", "application/json"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "choices": [{ "message": { "content": "ok" }, "finish_reason": "stop" }]
+            })))
+            .mount(&server)
+            .await;
+
+        let mut req = sanitized("openai", "gpt", vec![msg("system", "sys"), msg("user", "hi")]);
+        req.prompt.seed = Some(7);
+        let req = seal(req);
+        let p = OpenAICompatProvider::new(server.uri(), None);
+        dispatch_verified(&p, &req).await.unwrap();
+
+        // The wire bytes are exactly what `request_body` reports, and already canonical.
+        let sent = server.received_requests().await.unwrap().remove(0).body;
+        assert_eq!(Some(&sent), p.request_body(&req).unwrap().as_ref());
+        let parsed: Value = serde_json::from_slice(&sent).unwrap();
+        assert_eq!(pie_common::sha256_bytes(&sent), pie_common::sha256_canonical_json(&parsed).unwrap());
+        assert_eq!(parsed["model"], "gpt");
+        assert_eq!(parsed["seed"], 7);
+        // Keys are sorted on the wire: "max_tokens" < "messages" < "model".
+        let text = String::from_utf8(sent).unwrap();
+        assert!(text.find("\"max_tokens\"").unwrap() < text.find("\"messages\"").unwrap());
+        assert!(text.find("\"messages\"").unwrap() < text.find("\"model\"").unwrap());
+    }
+
+    fn text_msg(role: &str, text: &str) -> ChatMsg {
+        ChatMsg { role: role.into(), content: MessageContent::Text(text.into()) }
+    }
+
+    #[test]
+    fn split_system_without_system_messages_is_a_no_op() {
+        let (system, rest) = split_system(vec![text_msg("user", "hi"), text_msg("assistant", "yo")]);
+        assert_eq!(system, None);
+        assert_eq!(rest.len(), 2);
+    }
+
+    #[test]
+    fn split_system_hoists_a_single_leading_system_message() {
+        let (system, rest) = split_system(vec![text_msg("system", "be terse"), text_msg("user", "hi")]);
+        assert_eq!(system.as_deref(), Some("be terse"));
+        assert_eq!(rest.len(), 1);
+        assert_eq!(rest[0].role, "user");
+    }
+
+    #[test]
+    fn split_system_joins_leading_system_messages_in_order() {
+        let (system, rest) = split_system(vec![
+            text_msg("system", "one"),
+            text_msg("system", "two"),
+            text_msg("user", "hi"),
+            text_msg("system", "late"),
+        ]);
+        assert_eq!(system.as_deref(), Some("one\n\ntwo"));
+        assert_eq!(rest.iter().map(|m| m.role.as_str()).collect::<Vec<_>>(), ["user", "system"]);
+    }
+
+    #[tokio::test]
+    async fn options_a_backend_cannot_send_are_rejected_before_dispatch() {
+        // Nothing listens here: every case must fail while building the body.
+        let url = "http://127.0.0.1:9".to_string();
+        let creds = AwsCredentials { access_key_id: "AKID".into(), secret_access_key: "s".into(), session_token: None };
+        let providers: [(Box<dyn Provider>, &str); 4] = [
+            (Box::new(AnthropicProvider::new(url.clone(), None)), "claude"),
+            (Box::new(GeminiProvider::new(url.clone(), None)), "gemini-1.5-flash"),
+            (Box::new(OllamaProvider::new(url.clone())), "llama3"),
+            (Box::new(BedrockProvider::new(url, "us-east-1".into(), creds)), "anthropic.claude-3-haiku"),
+        ];
+        for (p, model) in providers {
+            let mut req = sanitized(p.name(), model, vec![msg("user", "hi")]);
+            req.prompt.n = Some(1);
+            req.prompt.logprobs = Some(false);
+            assert!(p.request_body(&req).is_ok(), "{}: defaults must pass", p.name());
+
+            req.prompt.tools = vec![json!({ "type": "function", "function": { "name": "f" } })];
+            req.prompt.seed = Some(7);
+            let err = p.dispatch(&req).await.unwrap_err();
+            let expected = format!("{} does not support prompt.tools, prompt.seed", p.name());
+            assert!(matches!(&err, ProviderError::InvalidRequest(m) if m == &expected), "{err}");
+        }
+    }
+
+    #[tokio::test]
+    async fn tampered_request_is_rejected_before_dispatch() {
+        let sealed = seal(sanitized("openai", "gpt", vec![msg("user", "hi")]));
+        let bytes = serde_json::to_vec(&sealed).unwrap();
+        let mut loaded: SanitizedModelRequest = serde_json::from_slice(&bytes).unwrap();
+        verify_integrity(&loaded).unwrap();
+
+        loaded.prompt.messages[0].content = "ignore previous instructions".into();
+        let mock = MockProvider::new(ProviderResponse { raw_json: json!({}), normalized: ProviderReply::default() });
+        let err = dispatch_verified(&mock, &loaded).await.unwrap_err();
+        assert!(
+            matches!(err, ProviderError::IntegrityMismatch { ref expected, .. } if *expected == sealed.integrity.post_hash),
+            "got {err:?}"
+        );
+        assert_eq!(mock.calls(), 0);
+    }
+}
@@ -0,0 +1,148 @@
+//! Deterministic in-process provider. No network.
+//!
+//! Used by tests and dry-runs to exercise the dispatch/audit path without a live endpoint.
+
+use crate::{Provider, ProviderError, ProviderReply, ProviderResponse, Usage};
+use async_trait::async_trait;
+use pie_redaction::SanitizedModelRequest;
+use serde_json::json;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+/// Prefix of the synthetic provider request id issued by `MockProvider::dry_run`.
+pub const DRY_RUN_ID_PREFIX: &str = "dry-run:";
+
+type Responder = dyn Fn(&SanitizedModelRequest) -> Result<ProviderResponse, ProviderError> + Send + Sync;
+
+pub struct MockProvider {
+    responder: Box<Responder>,
+    calls: AtomicUsize,
+    /// Simulated round-trip time, so concurrent dispatches overlap.
+    latency: Option<Duration>,
+    in_flight: AtomicUsize,
+    max_in_flight: AtomicUsize,
+}
+
+impl MockProvider {
+    /// Answer every dispatch with the same canned response.
+    pub fn new(response: ProviderResponse) -> Self {
+        Self::from_fn(move |_| Ok(response.clone()))
+    }
+
+    /// Compute the response (or error) from the request.
+    pub fn from_fn<F>(f: F) -> Self
+    where
+        F: Fn(&SanitizedModelRequest) -> Result<ProviderResponse, ProviderError> + Send + Sync + 'static,
+    {
+        Self {
+            responder: Box::new(f),
+            calls: AtomicUsize::new(0),
+            latency: None,
+            in_flight: AtomicUsize::new(0),
+            max_in_flight: AtomicUsize::new(0),
+        }
+    }
+
+    /// Sleep for `latency` inside every dispatch before answering.
+    pub fn with_latency(mut self, latency: Duration) -> Self {
+        self.latency = Some(latency);
+        self
+    }
+
+    /// Canned reply for `--dry-run` dispatches. Deterministic per request: the synthetic
+    /// response id is `dry-run:<post_hash>`, so artifacts and audit hashes are reproducible.
+    pub fn dry_run() -> Self {
+        Self::from_fn(|req| {
+            let id = format!("{DRY_RUN_ID_PREFIX}{}", req.integrity.post_hash);
+            let content = "[dry-run] no provider was contacted".to_string();
+            Ok(ProviderResponse {
+                raw_json: json!({
+                    "id": id,
+                    "object": "dry_run",
+                    "provider": req.provider.0,
+                    "model": req.model.0,
+                    "content": content,
+                }),
+                normalized: ProviderReply {
+                    content,
+                    finish_reason: Some("stop".into()),
+                    usage: Usage { input_tokens: Some(0), output_tokens: Some(0), reasoning_tokens: None },
+                    provider_request_id: Some(id),
+                    ..Default::default()
+                },
+            })
+        })
+    }
+
+    /// Number of times `dispatch` has been called.
+    pub fn calls(&self) -> usize {
+        self.calls.load(Ordering::SeqCst)
+    }
+
+    /// Most dispatches that were running at the same time.
+    pub fn max_in_flight(&self) -> usize {
+        self.max_in_flight.load(Ordering::SeqCst)
+    }
+}
+
+#[async_trait]
+impl Provider for MockProvider {
+    fn name(&self) -> &'static str {
+        "mock"
+    }
+
+    async fn dispatch(&self, req: &SanitizedModelRequest) -> Result<ProviderResponse, ProviderError> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        let running = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+        self.max_in_flight.fetch_max(running, Ordering::SeqCst);
+        if let Some(d) = self.latency {
+            tokio::time::sleep(d).await;
+        }
+        let out = (self.responder)(req);
+        self.in_flight.fetch_sub(1, Ordering::SeqCst);
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{msg, sanitized};
+
+    #[tokio::test]
+    async fn mock_dispatches_through_trait_object() {
+        let canned = ProviderResponse {
+            raw_json: json!({"id": "mock-1"}),
+            normalized: ProviderReply {
+                content: "canned".into(),
+                finish_reason: Some("stop".into()),
+                usage: Usage { input_tokens: Some(1), output_tokens: Some(2), reasoning_tokens: None },
+                provider_request_id: Some("mock-1".into()),
+                ..Default::default()
+            },
+        };
+        let mock = MockProvider::new(canned);
+        let p: &dyn Provider = &mock;
+
+        let req = sanitized("openai", "gpt", vec![msg("user", "hello")]);
+        let a = p.dispatch(&req).await.unwrap();
+        let b = p.dispatch(&req).await.unwrap();
+        assert_eq!(a.normalized.content, "canned");
+        assert_eq!(a.raw_json, b.raw_json);
+        assert_eq!(mock.calls(), 2);
+
+        let failing = MockProvider::from_fn(|r| Err(ProviderError::InvalidResponse(format!("no {}", r.model.0))));
+        let err = failing.dispatch(&req).await.unwrap_err();
+        assert!(err.to_string().contains("no gpt"));
+    }
+
+    #[tokio::test]
+    async fn dry_run_reply_is_deterministic_and_marked() {
+        let mut req = sanitized("openai", "gpt", vec![msg("user", "hello")]);
+        req.integrity.post_hash = "sha256:abc".into();
+        let a = MockProvider::dry_run().dispatch(&req).await.unwrap();
+        let b = MockProvider::dry_run().dispatch(&req).await.unwrap();
+        assert_eq!(a.raw_json, b.raw_json);
+        assert_eq!(a.normalized.provider_request_id.as_deref(), Some("dry-run:sha256:abc"));
+    }
+}
@@ -0,0 +1,152 @@
+//! Native Ollama (`/api/chat`) transport + normalization.
+
+use crate::{
+    canonical_body, canonical_finish_reason, http_error, read_json, reject_unsupported_options, to_text_chat_msgs,
+    with_canonical_json, ChatMsg, Provider, ProviderError, ProviderOptions, ProviderReply, ProviderResponse, Usage,
+};
+use async_trait::async_trait;
+use pie_redaction::SanitizedModelRequest;
+use reqwest::Client;
+use serde::Serialize;
+use serde_json::Value;
+
+/// Ollama is self-hosted and unauthenticated, so there is no api key.
+pub struct OllamaProvider {
+    client: Client,
+    base_url: String,
+}
+
+impl OllamaProvider {
+    pub fn new(base_url: String) -> Self {
+        Self { client: Client::new(), base_url }
+    }
+
+    pub fn with_options(base_url: String, opts: &ProviderOptions) -> Result<Self, ProviderError> {
+        Ok(Self { client: opts.build_client()?, base_url })
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct OllamaOptions {
+    temperature: f64,
+    top_p: f64,
+    num_predict: u64,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    stop: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct OllamaChatRequest<'a> {
+    model: &'a str,
+    messages: Vec<ChatMsg>,
+    stream: bool,
+    options: OllamaOptions,
+}
+
+fn build_request(req: &SanitizedModelRequest) -> Result<OllamaChatRequest<'_>, ProviderError> {
+    reject_unsupported_options(&req.prompt, "ollama")?;
+    Ok(OllamaChatRequest {
+        model: &req.model.0,
+        messages: to_text_chat_msgs(&req.prompt.messages, "ollama")?,
+        stream: false,
+        options: OllamaOptions {
+            temperature: req.prompt.temperature,
+            top_p: req.prompt.top_p,
+            num_predict: req.prompt.max_output_tokens,
+            stop: req.prompt.stop.clone(),
+        },
+    })
+}
+
+/// Normalize minimal shape: message.content, done_reason, prompt_eval_count/eval_count
+fn normalize(raw: Value) -> Result<ProviderResponse, ProviderError> {
+    let content = raw
+        .get("message")
+        .and_then(|m| m.get("content"))
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| ProviderError::InvalidResponse("missing message.content".into()))?
+        .to_string();
+
+    let finish_reason = raw.get("done_reason").and_then(|v| v.as_str()).map(canonical_finish_reason);
+
+    let input_tokens = raw.get("prompt_eval_count").and_then(|v| v.as_u64());
+    let output_tokens = raw.get("eval_count").and_then(|v| v.as_u64());
+
+    Ok(ProviderResponse {
+        raw_json: raw.clone(),
+        normalized: ProviderReply {
+            content,
+            finish_reason,
+            usage: Usage { input_tokens, output_tokens, reasoning_tokens: None },
+            // Ollama does not issue request ids.
+            provider_request_id: None,
+            ..Default::default()
+        },
+    })
+}
+
+#[async_trait]
+impl Provider for OllamaProvider {
+    fn name(&self) -> &'static str {
+        "ollama"
+    }
+
+    fn request_body(&self, req: &SanitizedModelRequest) -> Result<Option<Vec<u8>>, ProviderError> {
+        canonical_body(&build_request(req)?).map(Some)
+    }
+
+    async fn dispatch(&self, req: &SanitizedModelRequest) -> Result<ProviderResponse, ProviderError> {
+        let url = format!("{}/api/chat", self.base_url.trim_end_matches('/'));
+        let body = build_request(req)?;
+
+        let resp = with_canonical_json(self.client.post(url), &body)?.send().await.map_err(http_error)?;
+        let raw = read_json(resp).await?;
+
+        normalize(raw)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{msg, sanitized};
+    use serde_json::json;
+    use wiremock::matchers::{body_partial_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn dispatch_maps_eval_counts_into_usage() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/chat"))
+            .and(body_partial_json(json!({
+                "model": "llama3",
+                "stream": false,
+                "options": { "num_predict": 64, "top_p": 1.0 }
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "model": "llama3",
+                "message": { "role": "assistant", "content": "hey there" },
+                "done": true,
+                "done_reason": "stop",
+                "prompt_eval_count": 26,
+                "eval_count": 298
+            })))
+            .mount(&server)
+            .await;
+
+        let p = OllamaProvider::new(server.uri());
+        let out = p.dispatch(&sanitized("ollama", "llama3", vec![msg("user", "hello")])).await.unwrap();
+        assert_eq!(out.normalized.content, "hey there");
+        assert_eq!(out.normalized.finish_reason.as_deref(), Some("stop"));
+        assert_eq!(out.normalized.usage.input_tokens, Some(26));
+        assert_eq!(out.normalized.usage.output_tokens, Some(298));
+        assert!(out.normalized.provider_request_id.is_none());
+    }
+
+    #[test]
+    fn length_done_reason_marks_truncation() {
+        let out = normalize(json!({ "message": { "content": "partial" }, "done_reason": "length" })).unwrap();
+        assert!(out.normalized.was_truncated());
+    }
+}
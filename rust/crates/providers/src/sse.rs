@@ -0,0 +1,135 @@
+//! OpenAI-compatible `stream: true` (server-sent events) aggregation.
+
+use crate::ProviderError;
+use serde_json::{json, Value};
+
+/// Folds `chat.completion.chunk` events back into the equivalent non-streaming `chat.completion`
+/// body, so a streamed call normalizes (and hashes) through the same path as `dispatch`.
+///
+/// Only choice 0 and text deltas are aggregated; tool-call deltas are not supported here.
+#[derive(Debug, Default)]
+pub(crate) struct ChunkAggregator {
+    /// Bytes after the last newline (an event can be split across network reads).
+    pending: Vec<u8>,
+    chunks: u64,
+    id: Option<String>,
+    model: Option<String>,
+    content: String,
+    finish_reason: Option<String>,
+    usage: Option<Value>,
+}
+
+impl ChunkAggregator {
+    /// Feed one network read; returns the content deltas it completed, in order.
+    pub(crate) fn push(&mut self, bytes: &[u8]) -> Result<Vec<String>, ProviderError> {
+        self.pending.extend_from_slice(bytes);
+        let mut deltas = Vec::new();
+        while let Some(nl) = self.pending.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = self.pending.drain(..=nl).collect();
+            let line = String::from_utf8_lossy(&line);
+            if let Some(delta) = self.line(line.trim_end_matches(['\r', '\n']))? {
+                deltas.push(delta);
+            }
+        }
+        Ok(deltas)
+    }
+
+    fn line(&mut self, line: &str) -> Result<Option<String>, ProviderError> {
+        // Blank lines separate events; ':' lines are comments / keep-alives.
+        let Some(data) = line.strip_prefix("data:").map(str::trim) else {
+            return Ok(None);
+        };
+        if data == "[DONE]" {
+            return Ok(None);
+        }
+        let chunk: Value = serde_json::from_str(data)
+            .map_err(|e| ProviderError::InvalidResponse(format!("invalid stream chunk: {e}")))?;
+        self.chunks += 1;
+        if self.id.is_none() {
+            self.id = chunk.get("id").and_then(|v| v.as_str()).map(|s| s.to_string());
+        }
+        if self.model.is_none() {
+            self.model = chunk.get("model").and_then(|v| v.as_str()).map(|s| s.to_string());
+        }
+        if let Some(u) = chunk.get("usage").filter(|u| u.is_object()) {
+            self.usage = Some(u.clone());
+        }
+        let Some(choice) = chunk
+            .get("choices")
+            .and_then(|c| c.as_array())
+            .and_then(|c| c.iter().find(|c| c.get("index").and_then(|i| i.as_u64()).unwrap_or(0) == 0))
+        else {
+            return Ok(None);
+        };
+        if let Some(r) = choice.get("finish_reason").and_then(|v| v.as_str()) {
+            self.finish_reason = Some(r.to_string());
+        }
+        match choice.get("delta").and_then(|d| d.get("content")).and_then(|c| c.as_str()) {
+            Some(delta) if !delta.is_empty() => {
+                self.content.push_str(delta);
+                Ok(Some(delta.to_string()))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// The aggregated `chat.completion` body. Errors if the stream carried no chunks at all.
+    pub(crate) fn finish(mut self) -> Result<Value, ProviderError> {
+        if !self.pending.is_empty() {
+            let rest = String::from_utf8_lossy(&std::mem::take(&mut self.pending)).into_owned();
+            self.line(rest.trim())?;
+        }
+        if self.chunks == 0 {
+            return Err(ProviderError::InvalidResponse("stream ended without any chunks".into()));
+        }
+        let mut raw = json!({
+            "object": "chat.completion",
+            "choices": [{
+                "index": 0,
+                "message": { "role": "assistant", "content": self.content },
+                "finish_reason": self.finish_reason,
+            }],
+        });
+        for (k, v) in [("id", self.id.map(Value::from)), ("model", self.model.map(Value::from)), ("usage", self.usage)] {
+            if let Some(v) = v {
+                raw[k] = v;
+            }
+        }
+        Ok(raw)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn events_split_across_reads_are_reassembled() {
+        let mut agg = ChunkAggregator::default();
+        let body = concat!(
+            ": keep-alive\n\n",
+            "data: {\"id\":\"c1\",\"model\":\"gpt\",\"choices\":[{\"index\":0,\"delta\":{\"role\":\"assistant\"}}]}\n\n",
+            "data: {\"id\":\"c1\",\"choices\":[{\"index\":0,\"delta\":{\"content\":\"Hel\"}}]}\n\n",
+            "data: {\"id\":\"c1\",\"choices\":[{\"index\":0,\"delta\":{\"content\":\"lo\"},\"finish_reason\":\"stop\"}]}\n\n",
+            "data: {\"id\":\"c1\",\"choices\":[],\"usage\":{\"prompt_tokens\":3,\"completion_tokens\":2}}\n\n",
+            "data: [DONE]\n\n",
+        );
+        let (a, b) = body.as_bytes().split_at(120);
+        let mut deltas = agg.push(a).unwrap();
+        deltas.extend(agg.push(b).unwrap());
+        assert_eq!(deltas, ["Hel", "lo"]);
+
+        let raw = agg.finish().unwrap();
+        assert_eq!(raw["id"], "c1");
+        assert_eq!(raw["choices"][0]["message"]["content"], "Hello");
+        assert_eq!(raw["choices"][0]["finish_reason"], "stop");
+        assert_eq!(raw["usage"]["completion_tokens"], 2);
+    }
+
+    #[test]
+    fn empty_stream_is_invalid() {
+        let mut agg = ChunkAggregator::default();
+        agg.push(b"data: [DONE]\n\n").unwrap();
+        assert!(matches!(agg.finish(), Err(ProviderError::InvalidResponse(_))));
+    }
+}
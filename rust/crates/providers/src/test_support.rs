@@ -0,0 +1,63 @@
+//! Shared fixtures for provider tests.
+
+use pie_redaction::{
+    AgentRole, ContextRefs, IntegrityBlock, ModelId, Prompt, PromptMessage, ProviderId, RedactionBlock, RunId,
+    SanitizedModelRequest, TickId,
+};
+
+pub(crate) fn sanitized(provider: &str, model: &str, messages: Vec<PromptMessage>) -> SanitizedModelRequest {
+    SanitizedModelRequest {
+        schema_version: 1,
+        run_id: RunId("run1".into()),
+        tick_id: TickId(1),
+        role: AgentRole::Planner,
+        provider: ProviderId(provider.into()),
+        model: ModelId(model.into()),
+        prompt: Prompt {
+            format: "chat".into(),
+            messages,
+            max_output_tokens: 64,
+            temperature: 0.2,
+            top_p: 1.0,
+            stop: vec![],
+            tools: vec![],
+            tool_choice: None,
+            n: None,
+            seed: None,
+            response_format: None,
+            frequency_penalty: None,
+            presence_penalty: None,
+            logprobs: None,
+            top_logprobs: None,
+            reasoning_effort: None,
+        },
+        context_refs: ContextRefs {
+            gsama: vec![],
+            working_memory: vec![],
+            openmemory: vec![],
+            artifacts: vec![],
+            files: vec![],
+        },
+        redaction: RedactionBlock {
+            policy_id: "policy123".into(),
+            profile: "strict".into(),
+            summary_budget_chars: 1200,
+            transform_log: vec![],
+        },
+        integrity: IntegrityBlock {
+            pre_hash: "sha256:pre".into(),
+            post_hash: "sha256:post".into(),
+            nonce: "sha256:nonce".into(),
+        },
+    }
+}
+
+pub(crate) fn msg(role: &str, content: &str) -> PromptMessage {
+    PromptMessage { role: role.into(), content: content.into() }
+}
+
+/// Stamp the real post hash so the request passes `verify_integrity`.
+pub(crate) fn seal(mut req: SanitizedModelRequest) -> SanitizedModelRequest {
+    req.integrity.post_hash = req.compute_post_hash().unwrap();
+    req
+}
@@ -16,12 +16,19 @@
 use pie_audit_log::AuditAppender;
 use pie_audit_spec as spec;
 use pie_common::{canonical_json_bytes, sha256_bytes, sha256_canonical_json};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 use thiserror::Error;
 use uuid::Uuid;
 
+mod reversible;
+mod scan;
+
+pub use reversible::{decrypt_redaction_map, REDACTION_MAP_FILE};
+
 // ----------------------------
 // Errors
 // ----------------------------
@@ -38,21 +45,23 @@ pub enum RedactionError {
     Audit(#[from] pie_audit_log::AuditLogError),
     #[error("invalid allowlist entry: {0}")]
     InvalidAllowlist(String),
+    #[error("redaction map error: {0}")]
+    RedactionMap(String),
 }
 
 // ----------------------------
 // Request/Response primitives
 // ----------------------------
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(transparent)]
 pub struct RunId(pub String);
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(transparent)]
 pub struct TickId(pub u64);
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum AgentRole {
     Planner,
@@ -61,21 +70,77 @@ pub enum AgentRole {
     Summarizer,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(transparent)]
 pub struct ProviderId(pub String);
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(transparent)]
 pub struct ModelId(pub String);
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct PromptMessage {
     pub role: String,   // "system"|"user"|"assistant"
-    pub content: String,
+    pub content: MessageContent,
+}
+
+/// Plain text, or typed parts for multimodal prompts (OpenAI chat wire shape).
+/// Text serializes as a bare string, so text-only prompts hash exactly as before.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum MessageContent {
+    Text(String),
+    Parts(Vec<ContentPart>),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ContentPart {
+    Text { text: String },
+    ImageUrl { image_url: ImageUrl },
+}
+
+/// `url` is a remote URL or a `data:` URL.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ImageUrl {
+    pub url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+impl MessageContent {
+    /// Text parts concatenated in order; image parts contribute nothing.
+    pub fn text(&self) -> String {
+        match self {
+            MessageContent::Text(s) => s.clone(),
+            MessageContent::Parts(parts) => parts
+                .iter()
+                .filter_map(|p| match p {
+                    ContentPart::Text { text } => Some(text.as_str()),
+                    ContentPart::ImageUrl { .. } => None,
+                })
+                .collect(),
+        }
+    }
+
+    pub fn has_images(&self) -> bool {
+        matches!(self, MessageContent::Parts(parts) if parts.iter().any(|p| matches!(p, ContentPart::ImageUrl { .. })))
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl From<String> for MessageContent {
+    fn from(s: String) -> Self {
+        MessageContent::Text(s)
+    }
+}
+
+impl From<&str> for MessageContent {
+    fn from(s: &str) -> Self {
+        MessageContent::Text(s.to_string())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Prompt {
     pub format: String, // "chat"
     pub messages: Vec<PromptMessage>,
@@ -83,10 +148,38 @@ pub struct Prompt {
     pub temperature: f64,
     pub top_p: f64,
     pub stop: Vec<String>,
+    /// Function-calling tool definitions, passed through verbatim to the provider.
+    /// Omitted when empty so hashes of tool-less prompts are unchanged.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tools: Vec<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_choice: Option<serde_json::Value>,
+    /// Number of choices to sample (best-of). None = provider default (1).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub n: Option<u64>,
+    /// Sampling seed for best-effort reproducibility.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+    /// Output format constraint, e.g. `{"type":"json_object"}`, passed through verbatim.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response_format: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub frequency_penalty: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub presence_penalty: Option<f64>,
+    /// Ask for per-token log probabilities (returned in `ProviderReply.logprobs`, not the content).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub logprobs: Option<bool>,
+    /// Alternatives per token position, 0..=20; needs `logprobs`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top_logprobs: Option<u8>,
+    /// Reasoning budget for o-series / thinking models (e.g. "low", "medium", "high"), passed through verbatim.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reasoning_effort: Option<String>,
 }
 
 /// Internal, unsafe request (never outbound).
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ModelRequest {
     pub schema_version: u8,
     pub run_id: RunId,
@@ -107,13 +200,13 @@ pub struct ModelRequest {
 // Redaction outputs
 // ----------------------------
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct HashRef {
     pub r#type: String, // "hash_ref"
     pub value: String,  // sha256:...
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ContextRefs {
     #[serde(default)]
     pub gsama: Vec<HashRef>,
@@ -127,7 +220,7 @@ pub struct ContextRefs {
     pub files: Vec<HashRef>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum TransformKind {
     Drop,
@@ -136,22 +229,33 @@ pub enum TransformKind {
     Summarize,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct TransformReplacement {
     pub r#type: String, // "hash_ref" etc
     pub value: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct RedactionTransform {
     pub kind: TransformKind,
-    pub path: String,   // deterministic JSON-ish pointer (simple)
+    pub path: String,   // deterministic JSON-ish pointer (simple); `.`/`[`/`]`/`\` in keys are `\`-escaped
     pub reason: String, // stable reason key
     #[serde(skip_serializing_if = "Option::is_none")]
     pub replacement: Option<TransformReplacement>,
+    /// Where in a message the transform applied, for in-text secret scanning.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub region: Option<TextRegion>,
+}
+
+/// Part of a message's text: inside a ``` fenced code block, or ordinary prose.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TextRegion {
+    Code,
+    Prose,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct RedactionBlock {
     pub policy_id: String,
     pub profile: String, // "strict"|"explicit_allowlist"
@@ -159,7 +263,7 @@ pub struct RedactionBlock {
     pub transform_log: Vec<RedactionTransform>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct IntegrityBlock {
     pub pre_hash: String,
     pub post_hash: String,
@@ -167,7 +271,7 @@ pub struct IntegrityBlock {
 }
 
 /// Safe outbound request. This is the only thing you send to a provider backend.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct SanitizedModelRequest {
     pub schema_version: u8,
     pub run_id: RunId,
@@ -182,6 +286,26 @@ pub struct SanitizedModelRequest {
     pub integrity: IntegrityBlock,
 }
 
+/// Placeholder carried in the integrity block while the post hash is computed.
+pub const PENDING_HASH: &str = "sha256:pending";
+
+/// Hash algorithm behind the integrity block (pre/post hashes and nonce).
+pub const INTEGRITY_HASH_ALGORITHM: &str = "sha256";
+
+impl SanitizedModelRequest {
+    /// Recompute the post-redaction hash.
+    ///
+    /// The hash covers the whole request with both integrity hashes reset to
+    /// `PENDING_HASH` (the hash cannot cover itself), so it can be checked against
+    /// `integrity.post_hash` after the request has been written and reloaded.
+    pub fn compute_post_hash(&self) -> Result<String, RedactionError> {
+        let mut unsealed = self.clone();
+        unsealed.integrity.pre_hash = PENDING_HASH.into();
+        unsealed.integrity.post_hash = PENDING_HASH.into();
+        Ok(sha256_canonical_json(&unsealed)?)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ArtifactBundle {
     pub pre_request_path: PathBuf,
@@ -190,9 +314,11 @@ pub struct ArtifactBundle {
     pub pre_request_hash: String,
     pub post_request_hash: String,
     pub transform_log_hash: String,
+    /// Encrypted `REDACTION_MAP_FILE`, written only by a reversible engine.
+    pub redaction_map_path: Option<PathBuf>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct CallManifest {
     pub schema_version: u8,
     pub call_id: String,
@@ -212,12 +338,16 @@ pub struct RedactionResult {
 // Profiles + allowlist
 // ----------------------------
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct RedactionAllowlist {
     /// Explicit allowlist of JSON pointer-ish paths inside `context` that may be copied outbound.
     /// Keep this boring. No glob. No regex.
     #[serde(default)]
     pub context_paths: Vec<String>,
+    /// Send image URLs in prompt content parts outbound (hash recorded in the transform log).
+    /// When false, each image part is replaced by a text placeholder carrying its hash.
+    #[serde(default)]
+    pub allow_image_urls: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -235,6 +365,53 @@ impl RedactionProfile {
     }
 }
 
+/// Threshold for treating a token in message text as a secret: at least `min_len` characters
+/// of base64/base64url alphabet with Shannon entropy of at least `min_bits_per_char`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScanRules {
+    pub min_len: usize,
+    pub min_bits_per_char: f64,
+}
+
+impl ScanRules {
+    /// API-key-sized random strings; long snake_case identifiers stay below the entropy bar.
+    pub const STRICT: ScanRules = ScanRules { min_len: 24, min_bits_per_char: 4.0 };
+    /// Only very long, near-random tokens; keeps identifiers and short base64 fixtures in code.
+    pub const LENIENT: ScanRules = ScanRules { min_len: 64, min_bits_per_char: 5.0 };
+}
+
+/// How replaced content is rendered in the outbound prompt. The transform log records the
+/// kind and hash as structured data whichever format is used.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ReplacementFormat {
+    /// `<redacted:large_message sha256:...>`
+    #[default]
+    Angle,
+    /// `sha256:...`
+    BareHash,
+    /// `{"hash":"sha256:...","redacted":"large_message"}`
+    JsonTag,
+}
+
+impl ReplacementFormat {
+    /// Render the marker for content of `kind` (e.g. "large_message", "secret") hashing to `hash`.
+    pub fn render(self, kind: &str, hash: &str) -> String {
+        match self {
+            ReplacementFormat::Angle => format!("<redacted:{} {}>", kind, hash),
+            ReplacementFormat::BareHash => hash.to_string(),
+            ReplacementFormat::JsonTag => serde_json::json!({ "redacted": kind, "hash": hash }).to_string(),
+        }
+    }
+}
+
+/// In-text secret scanning, with separate rules inside fenced code blocks and in prose.
+/// `None` leaves that region untouched.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SecretScan {
+    pub prose: Option<ScanRules>,
+    pub code: Option<ScanRules>,
+}
+
 // ----------------------------
 // Artifact writing
 // ----------------------------
@@ -252,7 +429,8 @@ fn write_json_artifact(path: &Path, value: &impl Serialize) -> Result<(String, u
     Ok((h, bytes.len() as u64))
 }
 
-fn models_artifact_dir(base: &Path, run_id: &RunId, call_id: &Uuid) -> PathBuf {
+/// Call directory redaction writes a call's artifacts into: `<base>/runtime/artifacts/models/<run>/<call>`.
+pub fn models_artifact_dir(base: &Path, run_id: &RunId, call_id: &Uuid) -> PathBuf {
     base.join("runtime")
         .join("artifacts")
         .join("models")
@@ -268,16 +446,65 @@ pub struct RedactionEngine {
     pub policy_id: String,
     pub profile: RedactionProfile,
     pub summary_budget_chars: u64,
+    /// Profile to use instead of `profile` for requests to a given `ProviderId`, e.g. a looser
+    /// allowlist for a self-hosted model on the LAN.
+    pub provider_overrides: HashMap<String, RedactionProfile>,
+    /// Hash-replace high-entropy tokens in message text. Off by default.
+    pub secret_scan: Option<SecretScan>,
+    /// Also write `REDACTION_MAP_FILE`: every transform path's original value, encrypted
+    /// (XChaCha20-Poly1305) under `redaction_map_key`. Off by default.
+    pub reversible: bool,
+    pub redaction_map_key: Option<[u8; 32]>,
+    /// Marker format for large-message, secret and image-URL replacements.
+    pub replacement_format: ReplacementFormat,
 }
 
 impl RedactionEngine {
     pub fn new(policy_id: String, profile: RedactionProfile, summary_budget_chars: u64) -> Self {
-        Self { policy_id, profile, summary_budget_chars }
+        Self {
+            policy_id,
+            profile,
+            summary_budget_chars,
+            provider_overrides: HashMap::new(),
+            secret_scan: None,
+            reversible: false,
+            redaction_map_key: None,
+            replacement_format: ReplacementFormat::default(),
+        }
+    }
+
+    pub fn with_secret_scan(mut self, scan: SecretScan) -> Self {
+        self.secret_scan = Some(scan);
+        self
+    }
+
+    pub fn with_replacement_format(mut self, format: ReplacementFormat) -> Self {
+        self.replacement_format = format;
+        self
+    }
+
+    /// Turn on `reversible` with the key the redaction map is encrypted under.
+    pub fn with_reversible_map(mut self, key: [u8; 32]) -> Self {
+        self.reversible = true;
+        self.redaction_map_key = Some(key);
+        self
+    }
+
+    pub fn with_provider_override(mut self, provider: impl Into<String>, profile: RedactionProfile) -> Self {
+        self.provider_overrides.insert(provider.into(), profile);
+        self
+    }
+
+    /// The profile applied to requests for `provider`: its override, else the base profile.
+    /// Recorded as `redaction.profile` in the sanitized request and the ModelRequestRedacted event.
+    pub fn profile_for(&self, provider: &ProviderId) -> &RedactionProfile {
+        self.provider_overrides.get(&provider.0).unwrap_or(&self.profile)
     }
 
     /// Perform redaction + write artifacts + emit audit events.
     ///
     /// `repo_root` is the project root where `runtime/` exists.
+    #[allow(clippy::too_many_arguments)]
     pub fn redact_and_audit(
         &self,
         repo_root: &Path,
@@ -305,10 +532,16 @@ impl RedactionEngine {
         // 2) Redact to sanitized request + transforms
         let (sanitized, transforms, context_refs) = self.redact_request(request)?;
 
-        // 3) Compute post hash + write post + transform log artifacts
-        let post_hash = sha256_canonical_json(&sanitized)?;
+        // 3) Compute post hash, patch the integrity block (authoritative hashes), then write
+        // post + transform log artifacts. request_post.json carries the real hashes so
+        // dispatch can re-verify it.
+        let post_hash = sanitized.compute_post_hash()?;
+        let mut sanitized_fixed = sanitized.clone();
+        sanitized_fixed.integrity.pre_hash = pre_hash.clone();
+        sanitized_fixed.integrity.post_hash = post_hash.clone();
+
         let post_path = artifacts_dir.join("request_post.json");
-        let (post_artifact_hash, _post_size) = write_json_artifact(&post_path, &sanitized)?;
+        let (post_artifact_hash, _post_size) = write_json_artifact(&post_path, &sanitized_fixed)?;
 
         let transform_log_path = artifacts_dir.join("transform_log.json");
         let (transform_log_hash, _log_size) = write_json_artifact(&transform_log_path, &transforms)?;
@@ -323,13 +556,19 @@ impl RedactionEngine {
         };
         let _ = write_json_artifact(&artifacts_dir.join("call_manifest.json"), &manifest)?;
 
-        // 3.1) Patch integrity block in-memory (authoritative hashes)
-        // NOTE: artifacts already written above; this only corrects the returned struct and
-        // any future outbound use of the SanitizedModelRequest object.
-        let mut sanitized_fixed = sanitized.clone();
-        sanitized_fixed.integrity.pre_hash = pre_hash.clone();
-        sanitized_fixed.integrity.post_hash = post_hash.clone();
-        
+        let redaction_map_path = if self.reversible {
+            let key = self
+                .redaction_map_key
+                .as_ref()
+                .ok_or_else(|| RedactionError::RedactionMap("reversible redaction requires a map key".into()))?;
+            let map = reversible::build_map(&serde_json::to_value(request)?, &transforms);
+            let path = artifacts_dir.join(REDACTION_MAP_FILE);
+            fs::write(&path, reversible::encrypt_map(key, &map)?)?;
+            Some(path)
+        } else {
+            None
+        };
+
         // 4) Emit audit: ModelCallPrepared
         let prepared = spec::AuditEvent::ModelCallPrepared(spec::ModelCallPrepared {
             schema_version: 1,
@@ -363,7 +602,7 @@ impl RedactionEngine {
             ts: ts_redacted,
             model_call: spec::CallId(call_id),
             redaction: spec::RedactionMeta {
-                profile: self.profile.name().into(),
+                profile: self.profile_for(&request.provider).name().into(),
                 transform_count: transforms.len() as u64,
                 transform_log_hash: transform_log_hash.clone(),
                 summary_budget_chars: self.summary_budget_chars,
@@ -389,6 +628,7 @@ impl RedactionEngine {
             pre_request_hash: pre_hash,
             post_request_hash: post_hash,
             transform_log_hash,
+            redaction_map_path,
         };
 
         // Ensure sanitized includes the derived context_refs (deterministically)
@@ -402,6 +642,53 @@ impl RedactionEngine {
         })
     }
 
+    /// Hash-replace `text` wholesale if it exceeds the summary budget; returns whether it did.
+    fn hash_large_text(&self, text: &mut String, path: String, transforms: &mut Vec<RedactionTransform>) -> bool {
+        if text.len() > (self.summary_budget_chars as usize) {
+            let h = sha256_bytes(text.as_bytes());
+            *text = self.replacement_format.render("large_message", &h);
+            transforms.push(RedactionTransform {
+                kind: TransformKind::ReplaceWithHash,
+                path,
+                reason: "message_too_large_hashed".into(),
+                replacement: Some(TransformReplacement { r#type: "hash_ref".into(), value: h }),
+                region: None,
+            });
+            return true;
+        }
+        false
+    }
+
+    /// Hash-replace tokens flagged by `secret_scan` in `text`, using the code or prose rules per
+    /// fenced region. Each replacement is logged with its region.
+    fn scan_text(&self, text: &mut String, path: &str, transforms: &mut Vec<RedactionTransform>) {
+        let Some(scan) = &self.secret_scan else { return };
+        let mut out = String::with_capacity(text.len());
+        for (region, segment) in scan::regions(text) {
+            let rules = match region {
+                TextRegion::Code => scan.code,
+                TextRegion::Prose => scan.prose,
+            };
+            let Some(rules) = rules else {
+                out.push_str(segment);
+                continue;
+            };
+            out.push_str(&scan::scrub(segment, &rules, |token| {
+                let h = sha256_bytes(token.as_bytes());
+                let replaced = self.replacement_format.render("secret", &h);
+                transforms.push(RedactionTransform {
+                    kind: TransformKind::ReplaceWithHash,
+                    path: path.to_string(),
+                    reason: "high_entropy_token_hashed".into(),
+                    replacement: Some(TransformReplacement { r#type: "hash_ref".into(), value: h }),
+                    region: Some(region),
+                });
+                replaced
+            }));
+        }
+        *text = out;
+    }
+
     fn redact_request(
         &self,
         request: &ModelRequest,
@@ -430,48 +717,50 @@ impl RedactionEngine {
         let ctx = &request.context;
 
         // Always hash the whole context so you can prove what was omitted without leaking it.
-        let ctx_bytes = canonical_json_bytes(ctx)?;
-        let ctx_hash = sha256_bytes(&ctx_bytes);
+        // The buckets below are hashed alongside it (CPU-bound for large contexts).
+        let (ctx_hash, bucket_hashes) =
+            rayon::join(|| canonical_json_bytes(ctx).map(|b| sha256_bytes(&b)), || hash_context_buckets(ctx));
+        let ctx_hash = ctx_hash?;
         transforms.push(RedactionTransform {
             kind: TransformKind::ReplaceWithHash,
             path: "context".into(),
             reason: "context_omitted".into(),
             replacement: Some(TransformReplacement { r#type: "hash_ref".into(), value: ctx_hash.clone() }),
+            region: None,
         });
 
         // Also extract hash refs for known sensitive buckets if present.
         // This supports later policy-based allowlists without ever sending content.
-        if let Some(obj) = ctx.as_object() {
-            for (k, v) in obj.iter() {
-                let h = sha256_bytes(&canonical_json_bytes(v)?);
-                let href = HashRef { r#type: "hash_ref".into(), value: h.clone() };
-
-                match k.as_str() {
-                    "gsama" => refs.gsama.push(href),
-                    "working_memory" => refs.working_memory.push(href),
-                    "openmemory" => refs.openmemory.push(href),
-                    "tool_results" | "tool_result" => refs.artifacts.push(href),
-                    "diff" | "diffs" => refs.artifacts.push(href),
-                    "files" | "file" => refs.files.push(href),
-                    _ => {
-                        // Unknown context bucket: treat as generic artifact ref (still not outbound content)
-                        refs.artifacts.push(href);
-                    }
+        for (k, h) in bucket_hashes? {
+            let href = HashRef { r#type: "hash_ref".into(), value: h.clone() };
+
+            match k {
+                "gsama" => refs.gsama.push(href),
+                "working_memory" => refs.working_memory.push(href),
+                "openmemory" => refs.openmemory.push(href),
+                "tool_results" | "tool_result" => refs.artifacts.push(href),
+                "diff" | "diffs" => refs.artifacts.push(href),
+                "files" | "file" => refs.files.push(href),
+                _ => {
+                    // Unknown context bucket: treat as generic artifact ref (still not outbound content)
+                    refs.artifacts.push(href);
                 }
-
-                transforms.push(RedactionTransform {
-                    kind: TransformKind::ReplaceWithHash,
-                    path: format!("context.{}", k),
-                    reason: "context_bucket_hashed".into(),
-                    replacement: Some(TransformReplacement { r#type: "hash_ref".into(), value: h }),
-                });
             }
+
+            transforms.push(RedactionTransform {
+                kind: TransformKind::ReplaceWithHash,
+                path: format!("context.{}", reversible::escape_key(k)),
+                reason: "context_bucket_hashed".into(),
+                replacement: Some(TransformReplacement { r#type: "hash_ref".into(), value: h }),
+                region: None,
+            });
         }
 
         // If explicit allowlist is set, we may copy specific context paths outbound.
         // NOTE: We still record transforms for any copied paths.
         let mut allow_copied: Vec<(String, serde_json::Value)> = vec![];
-        if let RedactionProfile::ExplicitAllowlist(allow) = &self.profile {
+        let profile = self.profile_for(&request.provider);
+        if let RedactionProfile::ExplicitAllowlist(allow) = profile {
             for p in allow.context_paths.iter() {
                 let v = get_by_simple_path(ctx, p)
                     .ok_or_else(|| RedactionError::InvalidAllowlist(p.clone()))?;
@@ -481,6 +770,7 @@ impl RedactionEngine {
                     path: format!("context.{}", p),
                     reason: "explicit_allowlist_copied".into(),
                     replacement: None,
+                    region: None,
                 });
             }
         }
@@ -488,26 +778,72 @@ impl RedactionEngine {
         // Build outbound prompt:
         // For now, we DO NOT attempt semantic scanning; we keep it structural and deterministic.
         // Any sensitive content should be kept out of the prompt projection upstream.
-        // We still defensively hash-replace any message that is extremely large (likely a dump).
+        // We still defensively hash-replace any message that is extremely large (likely a dump),
+        // and, if `secret_scan` is set, high-entropy tokens (the only content-level check).
+        // Image URLs are hashed out unless the allowlist explicitly permits them.
+        let allow_images = matches!(profile, RedactionProfile::ExplicitAllowlist(a) if a.allow_image_urls);
         let mut prompt = request.prompt.clone();
         for (i, msg) in prompt.messages.iter_mut().enumerate() {
-            if msg.content.len() > (self.summary_budget_chars as usize) {
-                let h = sha256_bytes(msg.content.as_bytes());
-                msg.content = format!("<redacted:large_message {}>", h);
-                transforms.push(RedactionTransform {
-                    kind: TransformKind::ReplaceWithHash,
-                    path: format!("prompt.messages[{}].content", i),
-                    reason: "message_too_large_hashed".into(),
-                    replacement: Some(TransformReplacement { r#type: "hash_ref".into(), value: h }),
-                });
+            match &mut msg.content {
+                MessageContent::Text(text) => {
+                    let path = format!("prompt.messages[{}].content", i);
+                    if !self.hash_large_text(text, path.clone(), &mut transforms) {
+                        self.scan_text(text, &path, &mut transforms);
+                    }
+                }
+                MessageContent::Parts(parts) => {
+                    for (j, part) in parts.iter_mut().enumerate() {
+                        let path = format!("prompt.messages[{}].content[{}]", i, j);
+                        match part {
+                            ContentPart::Text { text } => {
+                                let path = format!("{}.text", path);
+                                if !self.hash_large_text(text, path.clone(), &mut transforms) {
+                                    self.scan_text(text, &path, &mut transforms);
+                                }
+                            }
+                            ContentPart::ImageUrl { image_url } => {
+                                let h = sha256_bytes(image_url.url.as_bytes());
+                                let replacement = Some(TransformReplacement { r#type: "hash_ref".into(), value: h.clone() });
+                                if allow_images {
+                                    transforms.push(RedactionTransform {
+                                        kind: TransformKind::ReplaceWithRef,
+                                        path: format!("{}.image_url", path),
+                                        reason: "explicit_allowlist_image_url".into(),
+                                        replacement,
+                                        region: None,
+                                    });
+                                } else {
+                                    // Swap for a text part so the outbound request stays well-formed.
+                                    let text = self.replacement_format.render("image_url", &h);
+                                    *part = ContentPart::Text { text };
+                                    transforms.push(RedactionTransform {
+                                        kind: TransformKind::ReplaceWithHash,
+                                        path: format!("{}.image_url", path),
+                                        reason: "image_url_hashed".into(),
+                                        replacement,
+                                        region: None,
+                                    });
+                                }
+                            }
+                        }
+                    }
+                }
             }
         }
 
         // Nonce is deterministic per run/tick/provider/model (no randomness).
         // This prevents “helpful” provider retries from being indistinguishable.
+        // The schema version and hash algorithm are folded in so bumping either cannot reuse a nonce.
         let nonce_material = format!(
-            "run:{}|tick:{}|role:{:?}|provider:{}|model:{}|policy:{}",
-            request.run_id.0, request.tick_id.0, request.role, request.provider.0, request.model.0, self.policy_id
+            "schema:{}|alg:{}|run:{}|tick:{}|role:{:?}|provider:{}|model:{}|policy:{}",
+            request.schema_version,
+            INTEGRITY_HASH_ALGORITHM,
+            request.run_id.0,
+            request.tick_id.0,
+            request.role,
+            request.provider.0,
+            request.model.0,
+            self.policy_id
         );
         let nonce = sha256_bytes(nonce_material.as_bytes());
 
@@ -523,13 +859,13 @@ impl RedactionEngine {
             context_refs: refs.clone(),
             redaction: RedactionBlock {
                 policy_id: self.policy_id.clone(),
-                profile: self.profile.name().into(),
+                profile: profile.name().into(),
                 summary_budget_chars: self.summary_budget_chars,
                 transform_log: vec![], // filled below
             },
             integrity: IntegrityBlock {
-                pre_hash: "sha256:pending".into(),
-                post_hash: "sha256:pending".into(),
+                pre_hash: PENDING_HASH.into(),
+                post_hash: PENDING_HASH.into(),
                 nonce,
             },
         };
@@ -550,6 +886,7 @@ impl RedactionEngine {
                 path: "context.allowlist_copied_values".into(),
                 reason: "allowlist_copy_not_embedded_refs_only".into(),
                 replacement: None,
+                region: None,
             });
         }
 
@@ -567,8 +904,23 @@ fn map_role(r: &AgentRole) -> spec::AgentRole {
     }
 }
 
+/// sha256 of each top-level `context` bucket's canonical JSON, sorted by key. Buckets hash in
+/// parallel; the sort keeps transforms and ContextRefs in the same order as a sequential pass.
+fn hash_context_buckets(ctx: &serde_json::Value) -> Result<Vec<(&str, String)>, RedactionError> {
+    use rayon::prelude::*;
+    let Some(obj) = ctx.as_object() else { return Ok(vec![]) };
+    let buckets: Vec<(&String, &serde_json::Value)> = obj.iter().collect();
+    let mut hashed = buckets
+        .par_iter()
+        .map(|(k, v)| Ok((k.as_str(), sha256_bytes(&canonical_json_bytes(v)?))))
+        .collect::<Result<Vec<_>, RedactionError>>()?;
+    hashed.sort_by(|a, b| a.0.cmp(b.0));
+    Ok(hashed)
+}
+
 /// Very simple dotted path accessor for allowlists:
 /// - "a.b.c"
+///
 /// Only supports objects (no arrays).
 fn get_by_simple_path<'a>(root: &'a serde_json::Value, path: &str) -> Option<&'a serde_json::Value> {
     let mut cur = root;
@@ -584,6 +936,110 @@ fn get_by_simple_path<'a>(root: &'a serde_json::Value, path: &str) -> Option<&'a
     Some(cur)
 }
 
+// ----------------------------
+// Validation
+// ----------------------------
+
+/// Values accepted in `PromptMessage.role`.
+pub const KNOWN_MESSAGE_ROLES: &[&str] = &["system", "user", "assistant", "tool"];
+
+/// One failed invariant, located by a dotted field path (same style as transform log paths).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ValidationIssue {
+    pub path: String,
+    pub message: String,
+}
+
+fn issue(issues: &mut Vec<ValidationIssue>, path: impl Into<String>, message: impl Into<String>) {
+    issues.push(ValidationIssue { path: path.into(), message: message.into() });
+}
+
+impl Prompt {
+    /// Basic sanity checks: chat format, non-empty messages with known roles, sampling params in
+    /// the ranges providers accept.
+    pub fn validate(&self) -> Vec<ValidationIssue> {
+        let mut issues = Vec::new();
+        if self.format != "chat" {
+            issue(&mut issues, "prompt.format", format!("unsupported format {:?} (expected \"chat\")", self.format));
+        }
+        if self.messages.is_empty() {
+            issue(&mut issues, "prompt.messages", "must contain at least one message");
+        }
+        for (i, m) in self.messages.iter().enumerate() {
+            if !KNOWN_MESSAGE_ROLES.contains(&m.role.as_str()) {
+                issue(&mut issues, format!("prompt.messages[{}].role", i), format!("unknown role {:?}", m.role));
+            }
+        }
+        if self.max_output_tokens == 0 {
+            issue(&mut issues, "prompt.max_output_tokens", "must be greater than 0");
+        }
+        if !(0.0..=2.0).contains(&self.temperature) {
+            issue(&mut issues, "prompt.temperature", format!("{} is outside [0, 2]", self.temperature));
+        }
+        if !(self.top_p > 0.0 && self.top_p <= 1.0) {
+            issue(&mut issues, "prompt.top_p", format!("{} is outside (0, 1]", self.top_p));
+        }
+        if self.n == Some(0) {
+            issue(&mut issues, "prompt.n", "must be at least 1");
+        }
+        for (name, v) in [("frequency_penalty", self.frequency_penalty), ("presence_penalty", self.presence_penalty)] {
+            if let Some(v) = v.filter(|v| !(-2.0..=2.0).contains(v)) {
+                issue(&mut issues, format!("prompt.{}", name), format!("{} is outside [-2, 2]", v));
+            }
+        }
+        if let Some(k) = self.top_logprobs {
+            if k > 20 {
+                issue(&mut issues, "prompt.top_logprobs", format!("{} is outside [0, 20]", k));
+            } else if self.logprobs != Some(true) {
+                issue(&mut issues, "prompt.top_logprobs", "requires prompt.logprobs = true");
+            }
+        }
+        issues
+    }
+}
+
+fn validate_target(provider: &ProviderId, model: &ModelId, issues: &mut Vec<ValidationIssue>) {
+    if provider.0.trim().is_empty() {
+        issue(issues, "provider", "must not be empty");
+    }
+    if model.0.trim().is_empty() {
+        issue(issues, "model", "must not be empty");
+    }
+}
+
+impl ModelRequest {
+    /// Invariants checked before redaction. An empty result means the request is valid.
+    pub fn validate(&self) -> Vec<ValidationIssue> {
+        let mut issues = Vec::new();
+        validate_target(&self.provider, &self.model, &mut issues);
+        issues.extend(self.prompt.validate());
+        issues
+    }
+}
+
+impl SanitizedModelRequest {
+    /// Invariants checked before dispatch: the prompt checks plus a sealed integrity block whose
+    /// post hash matches the request contents.
+    pub fn validate(&self) -> Vec<ValidationIssue> {
+        let mut issues = Vec::new();
+        validate_target(&self.provider, &self.model, &mut issues);
+        issues.extend(self.prompt.validate());
+        if self.integrity.pre_hash == PENDING_HASH || !self.integrity.pre_hash.starts_with("sha256:") {
+            issue(&mut issues, "integrity.pre_hash", "must be a sealed sha256 hash");
+        }
+        match self.compute_post_hash() {
+            Ok(h) if h == self.integrity.post_hash => {}
+            Ok(h) => issue(
+                &mut issues,
+                "integrity.post_hash",
+                format!("{} does not match recomputed {}", self.integrity.post_hash, h),
+            ),
+            Err(e) => issue(&mut issues, "integrity.post_hash", e.to_string()),
+        }
+        issues
+    }
+}
+
 // ----------------------------
 // Tests
 // ----------------------------
@@ -598,15 +1054,9 @@ mod tests {
         std::env::temp_dir().join("pie_redaction_repo_root")
     }
 
-    #[test]
-    fn redaction_is_deterministic_for_same_input() {
-        let root = tmp_root();
-        let _ = fs::remove_dir_all(&root);
-        fs::create_dir_all(root.join("runtime/logs")).unwrap();
-
-        let mut audit = AuditAppender::open(root.join("runtime/logs/audit_rust.jsonl")).unwrap();
-
-        let req = ModelRequest {
+    /// Minimal openai request with a single "hi" user message.
+    fn request(context: serde_json::Value) -> ModelRequest {
+        ModelRequest {
             schema_version: 1,
             run_id: RunId("run1".into()),
             tick_id: TickId(1),
@@ -615,21 +1065,40 @@ mod tests {
             model: ModelId("gpt".into()),
             prompt: Prompt {
                 format: "chat".into(),
-                messages: vec![
-                    PromptMessage { role: "system".into(), content: "sys".into() },
-                    PromptMessage { role: "user".into(), content: "hello".into() },
-                ],
-                max_output_tokens: 64,
+                messages: vec![PromptMessage { role: "user".into(), content: "hi".into() }],
+                max_output_tokens: 16,
                 temperature: 0.2,
                 top_p: 1.0,
                 stop: vec![],
+                tools: vec![],
+                tool_choice: None,
+                n: None,
+                seed: None,
+                response_format: None,
+                frequency_penalty: None,
+                presence_penalty: None,
+                logprobs: None,
+                top_logprobs: None,
+                reasoning_effort: None,
             },
-            context: serde_json::json!({
-                "gsama": { "z": [1,2,3] },
-                "working_memory": { "secret": "dont leak" },
-                "diff": "diff --git a/x b/x"
-            }),
-        };
+            context,
+        }
+    }
+
+    #[test]
+    fn redaction_is_deterministic_for_same_input() {
+        let root = tmp_root();
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("runtime/logs")).unwrap();
+
+        let mut audit = AuditAppender::open(root.join("runtime/logs/audit_rust.jsonl")).unwrap();
+
+        let mut req = request(serde_json::json!({
+            "gsama": { "z": [1,2,3] },
+            "working_memory": { "secret": "dont leak" },
+            "diff": "diff --git a/x b/x"
+        }));
+        req.prompt.messages.insert(0, PromptMessage { role: "system".into(), content: "sys".into() });
 
         let eng = RedactionEngine::new("policy123".into(), RedactionProfile::Strict, 1200);
 
@@ -656,36 +1125,216 @@ mod tests {
 
         assert_eq!(r1.artifacts.post_request_hash, r2.artifacts.post_request_hash);
 
+        // The written artifact carries the real hashes and re-verifies after reload
+        let loaded: SanitizedModelRequest =
+            serde_json::from_slice(&fs::read(&r1.artifacts.post_request_path).unwrap()).unwrap();
+        assert_eq!(loaded.integrity.post_hash, r1.artifacts.post_request_hash);
+        assert_eq!(loaded.compute_post_hash().unwrap(), loaded.integrity.post_hash);
+
         // Verify audit chain integrity
         let last = verify_log(root.join("runtime/logs/audit_rust.jsonl")).unwrap();
         assert!(last.starts_with("sha256:"));
     }
 
+    #[test]
+    fn nonce_covers_schema_version() {
+        let mut req = request(serde_json::json!({}));
+        let eng = RedactionEngine::new("policy123".into(), RedactionProfile::Strict, 1200);
+        let v1 = eng.redact_request(&req).unwrap().0.integrity.nonce;
+        assert_eq!(v1, eng.redact_request(&req).unwrap().0.integrity.nonce);
+
+        req.schema_version = 2;
+        assert_ne!(v1, eng.redact_request(&req).unwrap().0.integrity.nonce);
+    }
+
+    #[test]
+    fn prompt_validation_flags_out_of_range_sampling() {
+        let mut prompt = request(serde_json::json!({})).prompt;
+        assert!(prompt.validate().is_empty());
+
+        prompt.n = Some(0);
+        prompt.presence_penalty = Some(-2.5);
+        let paths: Vec<_> = prompt.validate().into_iter().map(|i| i.path).collect();
+        assert_eq!(paths, ["prompt.n", "prompt.presence_penalty"]);
+
+        prompt.top_logprobs = Some(3);
+        let paths: Vec<_> = prompt.validate().into_iter().map(|i| i.path).collect();
+        assert_eq!(paths.last().map(String::as_str), Some("prompt.top_logprobs"));
+        prompt.logprobs = Some(true);
+        assert_eq!(prompt.validate().len(), 2);
+    }
+
     #[test]
     fn large_message_is_hashed() {
-        let req = ModelRequest {
-            schema_version: 1,
-            run_id: RunId("run1".into()),
-            tick_id: TickId(1),
-            role: AgentRole::Planner,
-            provider: ProviderId("openai".into()),
-            model: ModelId("gpt".into()),
-            prompt: Prompt {
-                format: "chat".into(),
-                messages: vec![
-                    PromptMessage { role: "user".into(), content: "x".repeat(2000) },
-                ],
-                max_output_tokens: 64,
-                temperature: 0.2,
-                top_p: 1.0,
-                stop: vec![],
-            },
-            context: serde_json::json!({}),
-        };
+        let mut req = request(serde_json::json!({}));
+        req.prompt.messages[0].content = "x".repeat(2000).into();
 
         let eng = RedactionEngine::new("policy123".into(), RedactionProfile::Strict, 1200);
         let (san, transforms, _refs) = eng.redact_request(&req).unwrap();
-        assert!(san.prompt.messages[0].content.starts_with("<redacted:large_message "));
+        assert!(san.prompt.messages[0].content.text().starts_with("<redacted:large_message "));
         assert!(transforms.iter().any(|t| t.reason == "message_too_large_hashed"));
     }
+
+    #[test]
+    fn image_urls_are_hashed_unless_allowlisted() {
+        let image = |url: &str| ContentPart::ImageUrl { image_url: ImageUrl { url: url.into(), detail: None } };
+        let mut req = request(serde_json::json!({}));
+        req.prompt.messages[0].content = MessageContent::Parts(vec![
+            ContentPart::Text { text: "what is this?".into() },
+            image("https://cdn.example/cat.png?sig=secret"),
+        ]);
+
+        let strict = RedactionEngine::new("policy123".into(), RedactionProfile::Strict, 1200);
+        let (san, transforms, _refs) = strict.redact_request(&req).unwrap();
+        let MessageContent::Parts(parts) = &san.prompt.messages[0].content else { panic!("parts expected") };
+        assert!(matches!(&parts[1], ContentPart::Text { text } if text.starts_with("<redacted:image_url sha256:")));
+        assert!(!serde_json::to_string(&san).unwrap().contains("sig=secret"));
+        assert!(transforms.iter().any(|t| t.reason == "image_url_hashed" && t.path == "prompt.messages[0].content[1].image_url"));
+
+        let allow = RedactionAllowlist { context_paths: vec![], allow_image_urls: true };
+        let eng = RedactionEngine::new("policy123".into(), RedactionProfile::ExplicitAllowlist(allow), 1200);
+        let (san, transforms, _refs) = eng.redact_request(&req).unwrap();
+        assert!(san.prompt.messages[0].content.has_images());
+        assert!(transforms.iter().any(|t| t.reason == "explicit_allowlist_image_url"));
+    }
+
+    #[test]
+    fn provider_override_replaces_the_base_profile() {
+        let mut req = request(serde_json::json!({}));
+        req.prompt.messages[0].content = MessageContent::Parts(vec![ContentPart::ImageUrl {
+            image_url: ImageUrl { url: "http://nas.lan/cam.png".into(), detail: None },
+        }]);
+        let lan = RedactionAllowlist { context_paths: vec![], allow_image_urls: true };
+        let eng = RedactionEngine::new("policy123".into(), RedactionProfile::Strict, 1200)
+            .with_provider_override("local", RedactionProfile::ExplicitAllowlist(lan));
+
+        let (public, _, _) = eng.redact_request(&req).unwrap();
+        assert_eq!(public.redaction.profile, "strict");
+        assert!(!public.prompt.messages[0].content.has_images());
+
+        req.provider = ProviderId("local".into());
+        let (local, _, _) = eng.redact_request(&req).unwrap();
+        assert_eq!(local.redaction.profile, "explicit_allowlist");
+        assert!(local.prompt.messages[0].content.has_images());
+    }
+
+    #[test]
+    fn reversible_map_decrypts_to_original_values_and_is_opt_in() {
+        let root = std::env::temp_dir().join("pie_redaction_reversible_root");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        let mut audit = AuditAppender::open(root.join("audit.jsonl")).unwrap();
+        let mut req = request(serde_json::json!({ "working_memory": { "secret": "dont leak" } }));
+        req.prompt.messages[0].content = "x".repeat(50).into();
+        let key = [7u8; 32];
+        let eng = RedactionEngine::new("policy123".into(), RedactionProfile::Strict, 10).with_reversible_map(key);
+        let r = eng.redact_and_audit(&root, &mut audit, &req, "pol".into(), true, 1.0, 2.0).unwrap();
+
+        let path = r.artifacts.redaction_map_path.unwrap();
+        assert!(path.ends_with(REDACTION_MAP_FILE));
+        let bytes = fs::read(&path).unwrap();
+        assert!(!String::from_utf8_lossy(&bytes).contains("dont leak"));
+        let map = decrypt_redaction_map(&key, &bytes).unwrap();
+        assert_eq!(map["context.working_memory"], serde_json::json!({ "secret": "dont leak" }));
+        assert_eq!(map["prompt.messages[0].content"], "x".repeat(50));
+        assert!(matches!(decrypt_redaction_map(&[8u8; 32], &bytes), Err(RedactionError::RedactionMap(_))));
+
+        let plain = RedactionEngine::new("policy123".into(), RedactionProfile::Strict, 10);
+        let r = plain.redact_and_audit(&root, &mut audit, &req, "pol".into(), true, 3.0, 4.0).unwrap();
+        assert!(r.artifacts.redaction_map_path.is_none());
+        assert!(!r.artifacts.post_request_path.with_file_name(REDACTION_MAP_FILE).exists());
+    }
+
+    #[test]
+    fn reversible_map_keeps_dotted_context_keys_distinct_from_nested_ones() {
+        let root = std::env::temp_dir().join("pie_redaction_reversible_dotted_root");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        let mut audit = AuditAppender::open(root.join("audit.jsonl")).unwrap();
+        let req = request(serde_json::json!({ "a.b": "dotted", "a": { "b": "nested" }, "c[0]": 1 }));
+        let key = [7u8; 32];
+        let eng = RedactionEngine::new("policy123".into(), RedactionProfile::Strict, 1200).with_reversible_map(key);
+        let r = eng.redact_and_audit(&root, &mut audit, &req, "pol".into(), true, 1.0, 2.0).unwrap();
+
+        let map = decrypt_redaction_map(&key, &fs::read(r.artifacts.redaction_map_path.unwrap()).unwrap()).unwrap();
+        assert_eq!(map[r"context.a\.b"], "dotted");
+        assert_eq!(map["context.a"], serde_json::json!({ "b": "nested" }));
+        assert_eq!(map[r"context.c\[0\]"], 1);
+    }
+
+    #[test]
+    fn replacement_format_controls_the_outbound_marker_only() {
+        let mut req = request(serde_json::json!({}));
+        req.prompt.messages[0].content = "x".repeat(50).into();
+        let h = sha256_bytes("x".repeat(50).as_bytes());
+        let cases = [
+            (ReplacementFormat::Angle, format!("<redacted:large_message {h}>")),
+            (ReplacementFormat::BareHash, h.clone()),
+            (ReplacementFormat::JsonTag, format!(r#"{{"hash":"{h}","redacted":"large_message"}}"#)),
+        ];
+        for (format, expected) in cases {
+            let eng = RedactionEngine::new("policy123".into(), RedactionProfile::Strict, 10).with_replacement_format(format);
+            let (san, transforms, _) = eng.redact_request(&req).unwrap();
+            assert_eq!(san.prompt.messages[0].content.text(), expected, "{format:?}");
+            let t = transforms.iter().find(|t| t.reason == "message_too_large_hashed").unwrap();
+            assert_eq!(t.replacement.as_ref().unwrap().value, h);
+        }
+        assert_eq!(ReplacementFormat::BareHash.render("secret", "sha256:ab"), "sha256:ab");
+    }
+
+    #[test]
+    fn parallel_bucket_hashing_matches_a_sequential_pass() {
+        let mut ctx = serde_json::Map::new();
+        for i in 0..200 {
+            ctx.insert(format!("bucket_{i:03}"), serde_json::json!({ "n": i, "blob": "y".repeat(i) }));
+        }
+        ctx.insert("working_memory".into(), serde_json::json!({ "secret": "dont leak" }));
+        let ctx = serde_json::Value::Object(ctx);
+        let req = request(ctx.clone());
+
+        // Reference: one bucket at a time, in key order.
+        let mut keys: Vec<&String> = ctx.as_object().unwrap().keys().collect();
+        keys.sort();
+        let sequential: Vec<(String, String)> = keys
+            .iter()
+            .map(|k| (format!("context.{k}"), sha256_bytes(&canonical_json_bytes(&ctx[k.as_str()]).unwrap())))
+            .collect();
+
+        let eng = RedactionEngine::new("policy123".into(), RedactionProfile::Strict, 1200);
+        let (san, transforms, refs) = eng.redact_request(&req).unwrap();
+        let parallel: Vec<(String, String)> = transforms
+            .iter()
+            .filter(|t| t.reason == "context_bucket_hashed")
+            .map(|t| (t.path.clone(), t.replacement.as_ref().unwrap().value.clone()))
+            .collect();
+        assert_eq!(parallel, sequential);
+        assert_eq!(refs.working_memory[0].value, sequential.last().unwrap().1);
+        let again = eng.redact_request(&req).unwrap();
+        assert_eq!(canonical_json_bytes(&san).unwrap(), canonical_json_bytes(&again.0).unwrap());
+    }
+
+    #[test]
+    fn code_fences_get_the_lenient_ruleset() {
+        let token = "q8Zr2LxT0vN5bKp7Wm3YcH9dF1gJ4sA6";
+        let text = format!("my key is {token}\n```python\nFIXTURE = \"{token}\"\n```\n");
+        let mut req = request(serde_json::json!({}));
+        req.prompt.messages[0].content = text.into();
+        let eng = RedactionEngine::new("policy123".into(), RedactionProfile::Strict, 1200)
+            .with_secret_scan(SecretScan { prose: Some(ScanRules::STRICT), code: Some(ScanRules::LENIENT) });
+
+        let (san, transforms, _) = eng.redact_request(&req).unwrap();
+        let hash = sha256_bytes(token.as_bytes());
+        assert_eq!(
+            san.prompt.messages[0].content.text(),
+            format!("my key is <redacted:secret {hash}>\n```python\nFIXTURE = \"{token}\"\n```\n")
+        );
+        let scanned: Vec<_> = transforms.iter().filter(|t| t.reason == "high_entropy_token_hashed").collect();
+        assert_eq!(scanned.len(), 1);
+        assert_eq!(scanned[0].region, Some(TextRegion::Prose));
+        assert_eq!(scanned[0].path, "prompt.messages[0].content");
+
+        // Deterministic: same input, same sanitized request.
+        let again = eng.redact_request(&req).unwrap().0;
+        assert_eq!(sha256_canonical_json(&san).unwrap(), sha256_canonical_json(&again).unwrap());
+    }
 }
@@ -0,0 +1,86 @@
+//! Opt-in reversible redaction: an encrypted side artifact mapping each transform path to the
+//! value it had before redaction, for authorized debugging. It is written next to the other call
+//! artifacts, never referenced by the sanitized request, and never sent outbound.
+
+use crate::{RedactionError, RedactionTransform};
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::XChaCha20Poly1305;
+use pie_common::canonical_json_bytes;
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// File name of the encrypted map in the call's artifact directory.
+pub const REDACTION_MAP_FILE: &str = "redaction_map.json.enc";
+
+const NONCE_LEN: usize = 24;
+
+/// Original value at each transform path. Paths that do not exist in the original request
+/// (synthetic markers such as `context.allowlist_copied_values`) are skipped.
+pub(crate) fn build_map(original: &Value, transforms: &[RedactionTransform]) -> BTreeMap<String, Value> {
+    transforms
+        .iter()
+        .filter_map(|t| lookup(original, &t.path).map(|v| (t.path.clone(), v.clone())))
+        .collect()
+}
+
+/// XChaCha20-Poly1305 under a fresh random nonce; the output is `nonce || ciphertext`.
+pub(crate) fn encrypt_map(key: &[u8; 32], map: &BTreeMap<String, Value>) -> Result<Vec<u8>, RedactionError> {
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = XChaCha20Poly1305::new(key.into())
+        .encrypt(&nonce, canonical_json_bytes(map)?.as_slice())
+        .map_err(|_| RedactionError::RedactionMap("encryption failed".into()))?;
+    let mut out = nonce.to_vec();
+    out.extend(ciphertext);
+    Ok(out)
+}
+
+/// Decrypt a `redaction_map.json.enc` written under `key`.
+pub fn decrypt_redaction_map(key: &[u8; 32], bytes: &[u8]) -> Result<BTreeMap<String, Value>, RedactionError> {
+    if bytes.len() < NONCE_LEN {
+        return Err(RedactionError::RedactionMap("truncated map".into()));
+    }
+    let (nonce, ciphertext) = bytes.split_at(NONCE_LEN);
+    let plaintext = XChaCha20Poly1305::new(key.into())
+        .decrypt(nonce.into(), ciphertext)
+        .map_err(|_| RedactionError::RedactionMap("wrong key or corrupted map".into()))?;
+    Ok(serde_json::from_slice(&plaintext)?)
+}
+
+/// Backslash-escape `.`, `[`, `]` and `\\` so an arbitrary object key is one transform-path segment.
+pub(crate) fn escape_key(key: &str) -> String {
+    let mut out = String::with_capacity(key.len());
+    for c in key.chars() {
+        if matches!(c, '.' | '[' | ']' | '\\') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Resolve a transform-log path such as `prompt.messages[0].content[1].image_url`, honouring
+/// `escape_key` escapes (`context.a\.b` is the key `a.b`, not `a` then `b`).
+fn lookup<'a>(root: &'a Value, path: &str) -> Option<&'a Value> {
+    fn step<'a>(cur: &'a Value, name: &mut String) -> Option<&'a Value> {
+        if name.is_empty() {
+            return Some(cur);
+        }
+        cur.get(std::mem::take(name).as_str())
+    }
+    let mut cur = root;
+    let mut name = String::new();
+    let mut chars = path.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => name.push(chars.next()?),
+            '.' => cur = step(cur, &mut name)?,
+            '[' => {
+                cur = step(cur, &mut name)?;
+                let index: String = chars.by_ref().take_while(|&c| c != ']').collect();
+                cur = cur.get(index.parse::<usize>().ok()?)?;
+            }
+            c => name.push(c),
+        }
+    }
+    step(cur, &mut name)
+}
@@ -0,0 +1,92 @@
+//! Deterministic in-text secret scanning: fenced-code/prose region splitting and high-entropy
+//! token detection. No regex; every decision is a pure function of the text and the rules.
+
+use crate::{ScanRules, TextRegion};
+
+/// Split `text` into consecutive (region, slice) runs that concatenate back to `text`.
+///
+/// A line whose first non-blank characters are ``` opens or closes a fence. Fence lines belong
+/// to the code region; an unclosed fence runs to the end of the text.
+pub(crate) fn regions(text: &str) -> Vec<(TextRegion, &str)> {
+    let mut out = Vec::new();
+    let (mut start, mut offset) = (0, 0);
+    let mut current = TextRegion::Prose;
+    let mut in_fence = false;
+    for line in text.split_inclusive('\n') {
+        let fence = line.trim_start().starts_with("```");
+        let region = if in_fence || fence { TextRegion::Code } else { TextRegion::Prose };
+        if fence {
+            in_fence = !in_fence;
+        }
+        if region != current && offset > start {
+            out.push((current, &text[start..offset]));
+            start = offset;
+        }
+        current = region;
+        offset += line.len();
+    }
+    if offset > start {
+        out.push((current, &text[start..offset]));
+    }
+    out
+}
+
+/// Rewrite `segment`, passing every token that `rules` flags through `replace`. Tokens are
+/// maximal runs of base64/base64url characters; everything else is copied verbatim.
+pub(crate) fn scrub(segment: &str, rules: &ScanRules, mut replace: impl FnMut(&str) -> String) -> String {
+    let mut out = String::with_capacity(segment.len());
+    let mut rest = segment;
+    while let Some(start) = rest.find(is_token_char) {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let end = rest.find(|c| !is_token_char(c)).unwrap_or(rest.len());
+        let token = &rest[..end];
+        if token.len() >= rules.min_len && entropy_bits(token) >= rules.min_bits_per_char {
+            out.push_str(&replace(token));
+        } else {
+            out.push_str(token);
+        }
+        rest = &rest[end..];
+    }
+    out.push_str(rest);
+    out
+}
+
+fn is_token_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '+' | '/' | '=' | '_' | '-')
+}
+
+/// Shannon entropy of an ASCII token, in bits per character.
+fn entropy_bits(token: &str) -> f64 {
+    let mut counts = [0u32; 128];
+    for b in token.bytes() {
+        counts[b as usize & 0x7f] += 1;
+    }
+    let n = token.len() as f64;
+    counts.iter().filter(|&&c| c > 0).map(|&c| c as f64 / n).map(|p| -p * p.log2()).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fenced_blocks_are_code_and_regions_round_trip() {
+        let text = "see below\n```rust\nlet x = 1;\n```\nthanks\n```\nunclosed";
+        let runs = regions(text);
+        let kinds: Vec<TextRegion> = runs.iter().map(|r| r.0).collect();
+        assert_eq!(kinds, [TextRegion::Prose, TextRegion::Code, TextRegion::Prose, TextRegion::Code]);
+        assert_eq!(runs[1].1, "```rust\nlet x = 1;\n```\n");
+        assert_eq!(runs.iter().map(|r| r.1).collect::<String>(), text);
+    }
+
+    #[test]
+    fn only_long_high_entropy_tokens_are_replaced() {
+        let out = scrub(
+            "key: q8Zr2LxT0vN5bKp7Wm3YcH9dF1gJ4sA6 for get_user_account_settings_handler",
+            &ScanRules::STRICT,
+            |_| "<x>".into(),
+        );
+        assert_eq!(out, "key: <x> for get_user_account_settings_handler");
+    }
+}